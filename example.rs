@@ -19,6 +19,23 @@ fn main() {
 
     let mut renderer = Renderer::new(|x| { window.get_proc_address(x) }, true, true, FaceCulling::Front, vec![DebugFilter::Info]);
 
+    let material = renderer.add_material(Material::from_shader_files(
+        "tests/vertex_shader.glsl",
+        "tests/fragment_shader.glsl",
+        vec![
+            Texture::new(
+                WrappingType::Repeat,
+                FilteringType::Linear,
+                FilteringType::Linear,
+                0,
+                Texture::get_from_location("wall.jpg")
+            )
+        ],
+        vec![
+            ("time", AttributeType::Float1(0.1))
+        ],
+    ));
+
     let my_triangle = renderer.create_object(
         Some(vec![
             Vertex {
@@ -44,22 +61,7 @@ fn main() {
             },
         ]),
         Some(vec![0, 1, 2]),
-        Material::from_shader_files(
-            "tests/vertex_shader.glsl",
-            "tests/fragment_shader.glsl",
-            vec![
-                Texture::new(
-                    WrappingType::Repeat,
-                    FilteringType::Linear,
-                    FilteringType::Linear,
-                    0,
-                    Texture::get_from_location("wall.jpg")
-                )
-            ],
-            vec![
-                ("time", AttributeType::Float1(0.1))
-            ],
-        ),
+        material,
     );
 
     while !window.should_close() {
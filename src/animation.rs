@@ -0,0 +1,327 @@
+use crate::math::{Matrix4, Quaternion, Vector3};
+
+// Joint hierarchy of a skinned mesh. Joints are referenced by their index, which is also their index in
+// the joint matrices uploaded with `Renderer::set_object_joint_matrices` (and in `SkinVertex::joints`).
+//
+// let skeleton = Skeleton::new(parents, inverse_bind_matrices, bind_pose);
+// let mut pose = skeleton.get_bind_pose().clone();
+// clip.sample(time, true, &mut pose);
+// renderer.set_object_joint_matrices(object, &skeleton.joint_matrices(&pose));
+//
+// Should be exposed to the user.
+#[derive(Clone, Debug)]
+pub struct Skeleton {
+    names: Vec<Option<String>>,
+    parents: Vec<Option<usize>>,
+    inverse_bind_matrices: Vec<Matrix4>,
+    bind_pose: Pose,
+    // Joint indices with every parent before its children.
+    order: Vec<usize>,
+}
+
+impl Skeleton {
+    // `parents[i]` is the parent of joint `i`. The inverse bind matrices move a vertex from model space into the
+    // space of the joint in the bind pose (the pose the mesh was modelled in).
+    pub fn new(parents: Vec<Option<usize>>, inverse_bind_matrices: Vec<Matrix4>, bind_pose: Pose) -> Self {
+        let count = parents.len();
+        if inverse_bind_matrices.len() != count || bind_pose.get_joint_count() != count {
+            panic!("A skeleton needs one inverse bind matrix and one bind pose transform per joint!");
+        }
+
+        // Children are visited after their parents, joints in a cycle are never reached.
+        let mut children = vec![vec![]; count];
+        for (joint, parent) in parents.iter().enumerate() {
+            match parent {
+                Some(parent) if *parent >= count => panic!("Joint {} has a parent that doesn't exist!", joint),
+                Some(parent) => children[*parent].push(joint),
+                None => {}
+            }
+        }
+        let mut order = (0..count).filter(|x| parents[*x].is_none()).collect::<Vec<_>>();
+        let mut i = 0;
+        while i < order.len() {
+            order.extend(children[order[i]].iter().copied());
+            i += 1;
+        }
+        if order.len() != count {
+            panic!("The joints of a skeleton can't be their own ancestors!");
+        }
+
+        Skeleton {
+            names: vec![None; count],
+            parents,
+            inverse_bind_matrices,
+            bind_pose,
+            order,
+        }
+    }
+
+    pub fn with_names(mut self, names: Vec<Option<String>>) -> Self {
+        if names.len() != self.names.len() {
+            panic!("A skeleton needs one name per joint!");
+        }
+        self.names = names;
+        self
+    }
+
+    pub fn get_joint_count(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn get_name(&self, joint: usize) -> Option<&str> {
+        self.names[joint].as_deref()
+    }
+
+    pub fn find_joint(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|x| x.as_deref() == Some(name))
+    }
+
+    pub fn get_parent(&self, joint: usize) -> Option<usize> {
+        self.parents[joint]
+    }
+
+    pub fn get_inverse_bind_matrix(&self, joint: usize) -> Matrix4 {
+        self.inverse_bind_matrices[joint]
+    }
+
+    // The pose the mesh was modelled in. Clips that don't animate every joint should be sampled on top of it.
+    pub fn get_bind_pose(&self) -> &Pose {
+        &self.bind_pose
+    }
+
+    // The model-space matrix of every joint in the given pose.
+    pub fn model_matrices(&self, pose: &Pose) -> Vec<Matrix4> {
+        let mut matrices = vec![Matrix4::identity(); self.get_joint_count()];
+        for joint in self.order.iter().copied() {
+            let local = pose.get_local_matrix(joint);
+            matrices[joint] = match self.parents[joint] {
+                Some(parent) => matrices[parent] * local,
+                None => local,
+            };
+        }
+
+        matrices
+    }
+
+    // The matrices the vertex shader skins with: they move a vertex from where it is in the bind pose
+    // to where it is in `pose`, both in model space.
+    pub fn joint_matrices(&self, pose: &Pose) -> Vec<Matrix4> {
+        self.model_matrices(pose).iter().zip(self.inverse_bind_matrices.iter()).map(|(a, b)| *a * *b).collect()
+    }
+}
+
+// The local transform (relative to the parent joint) of every joint of a skeleton.
+//
+// Should be exposed to the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pose {
+    pub translations: Vec<Vector3>,
+    pub rotations: Vec<Quaternion>,
+    pub scales: Vec<Vector3>,
+}
+
+impl Pose {
+    // Every joint at its parent's origin, without rotation or scale.
+    pub fn new(joint_count: usize) -> Self {
+        Pose {
+            translations: vec![Vector3::zero(); joint_count],
+            rotations: vec![Quaternion::identity(); joint_count],
+            scales: vec![Vector3::one(); joint_count],
+        }
+    }
+
+    pub fn get_joint_count(&self) -> usize {
+        self.translations.len()
+    }
+
+    pub fn get_local_matrix(&self, joint: usize) -> Matrix4 {
+        Matrix4::from_transform(self.translations[joint], self.rotations[joint], self.scales[joint])
+    }
+
+    // Mixes two poses of the same skeleton, e.g. to cross-fade between clips. 0.0 is `self`, 1.0 is `other`.
+    pub fn blend(&self, other: &Pose, t: f32) -> Pose {
+        Pose {
+            translations: self.translations.iter().zip(other.translations.iter()).map(|(a, b)| a.lerp(*b, t)).collect(),
+            rotations: self.rotations.iter().zip(other.rotations.iter()).map(|(a, b)| a.slerp(*b, t)).collect(),
+            scales: self.scales.iter().zip(other.scales.iter()).map(|(a, b)| a.lerp(*b, t)).collect(),
+        }
+    }
+}
+
+// How values between two keyframes are computed.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Interpolation {
+    // Keeps the value of the previous keyframe.
+    Step,
+    // Straight lines for translations and scales, spherical (slerp) for rotations.
+    Linear,
+    // Hermite splines with an in- and out-tangent per keyframe, see `ChannelValues`.
+    CubicSpline,
+}
+
+// The keyframe values of a channel. With `Interpolation::CubicSpline` every keyframe has three values, in the order
+// in-tangent, value, out-tangent (like in glTF), otherwise one.
+//
+// Should be exposed to the user.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChannelValues {
+    Translations(Vec<Vector3>),
+    Rotations(Vec<Quaternion>),
+    Scales(Vec<Vector3>),
+    // Morph target weights, one per target for every keyframe value. See `AnimationClip::sample_weights`.
+    Weights(Vec<f32>),
+}
+
+// Animates one property of one joint.
+//
+// Should be exposed to the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Channel {
+    // For weights this is whatever has the morph targets, like the object or the glTF node.
+    pub joint: usize,
+    pub interpolation: Interpolation,
+    // Keyframe times in seconds, in increasing order.
+    pub times: Vec<f32>,
+    pub values: ChannelValues,
+}
+
+impl Channel {
+    // The keyframes around `time` and how far it is between them (0.0 to 1.0).
+    // Before the first and after the last keyframe both are the same keyframe.
+    fn keyframes(&self, time: f32) -> (usize, usize, f32) {
+        let next = self.times.partition_point(|x| *x <= time);
+        if next == 0 {
+            return (0, 0, 0.0);
+        }
+        if next == self.times.len() {
+            return (next - 1, next - 1, 0.0);
+        }
+
+        let (start, end) = (self.times[next - 1], self.times[next]);
+        (next - 1, next, (time - start) / (end - start))
+    }
+
+    fn sample_vector(&self, values: &[Vector3], time: f32) -> Vector3 {
+        let (a, b, t) = self.keyframes(time);
+        match self.interpolation {
+            Interpolation::Step => values[a],
+            Interpolation::Linear => values[a].lerp(values[b], t),
+            Interpolation::CubicSpline => {
+                let duration = self.times[b] - self.times[a];
+                let [h00, h10, h01, h11] = hermite(t);
+                values[a * 3 + 1] * h00 + values[a * 3 + 2] * (h10 * duration)
+                    + values[b * 3 + 1] * h01 + values[b * 3] * (h11 * duration)
+            }
+        }
+    }
+
+    fn sample_weights(&self, values: &[f32], time: f32) -> Vec<f32> {
+        let per_keyframe = if self.interpolation == Interpolation::CubicSpline { 3 } else { 1 };
+        let count = values.len() / (self.times.len() * per_keyframe);
+        let value = |keyframe: usize, element: usize| &values[(keyframe * per_keyframe + element) * count..][..count];
+
+        let (a, b, t) = self.keyframes(time);
+        match self.interpolation {
+            Interpolation::Step => value(a, 0).to_vec(),
+            Interpolation::Linear => value(a, 0).iter().zip(value(b, 0)).map(|(x, y)| x + (y - x) * t).collect(),
+            Interpolation::CubicSpline => {
+                let duration = self.times[b] - self.times[a];
+                let [h00, h10, h01, h11] = hermite(t);
+                (0..count).map(|i| {
+                    value(a, 1)[i] * h00 + value(a, 2)[i] * h10 * duration + value(b, 1)[i] * h01 + value(b, 0)[i] * h11 * duration
+                }).collect()
+            }
+        }
+    }
+
+    fn sample_rotation(&self, values: &[Quaternion], time: f32) -> Quaternion {
+        let (a, b, t) = self.keyframes(time);
+        match self.interpolation {
+            Interpolation::Step => values[a],
+            Interpolation::Linear => values[a].slerp(values[b], t),
+            Interpolation::CubicSpline => {
+                let duration = self.times[b] - self.times[a];
+                let [h00, h10, h01, h11] = hermite(t);
+                let weights = [
+                    (values[a * 3 + 1], h00),
+                    (values[a * 3 + 2], h10 * duration),
+                    (values[b * 3 + 1], h01),
+                    (values[b * 3], h11 * duration),
+                ];
+                weights.iter().fold(Quaternion::new(0.0, 0.0, 0.0, 0.0), |sum, (q, w)| {
+                    Quaternion::new(sum.x + q.x * w, sum.y + q.y * w, sum.z + q.z * w, sum.w + q.w * w)
+                }).normalize()
+            }
+        }
+    }
+}
+
+// The Hermite basis functions for the value and tangent of the start and end keyframe.
+fn hermite(t: f32) -> [f32; 4] {
+    let (t2, t3) = (t * t, t * t * t);
+    [2.0 * t3 - 3.0 * t2 + 1.0, t3 - 2.0 * t2 + t, -2.0 * t3 + 3.0 * t2, t3 - t2]
+}
+
+// A named set of channels, like "walk" or "jump".
+//
+// Should be exposed to the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationClip {
+    pub name: Option<String>,
+    // The time of the last keyframe of all channels.
+    pub duration: f32,
+    pub channels: Vec<Channel>,
+}
+
+impl AnimationClip {
+    pub fn new(name: Option<String>, channels: Vec<Channel>) -> Self {
+        let duration = channels.iter().filter_map(|x| x.times.last()).fold(0.0, |a: f32, b| a.max(*b));
+
+        AnimationClip { name, duration, channels }
+    }
+
+    // Writes the animated values at `time` (in seconds) into `pose`. Joints and properties without a channel keep
+    // their value, so start from the skeleton's bind pose. With `looping` the time wraps around at the end,
+    // otherwise the last keyframes are held.
+    pub fn sample(&self, time: f32, looping: bool, pose: &mut Pose) {
+        let time = if looping && self.duration > 0.0 { time.rem_euclid(self.duration) } else { time };
+
+        for channel in self.channels.iter() {
+            if channel.times.is_empty() || channel.joint >= pose.get_joint_count() {
+                continue;
+            }
+
+            match &channel.values {
+                ChannelValues::Translations(values) => pose.translations[channel.joint] = channel.sample_vector(values, time),
+                ChannelValues::Rotations(values) => pose.rotations[channel.joint] = channel.sample_rotation(values, time),
+                ChannelValues::Scales(values) => pose.scales[channel.joint] = channel.sample_vector(values, time),
+                ChannelValues::Weights(_) => {}
+            }
+        }
+    }
+
+    // The morph target weights of `target` at `time`, for `Renderer::set_object_morph_weights`.
+    // `None` when the clip doesn't animate its weights.
+    pub fn sample_weights(&self, time: f32, looping: bool, target: usize) -> Option<Vec<f32>> {
+        let time = if looping && self.duration > 0.0 { time.rem_euclid(self.duration) } else { time };
+
+        self.channels.iter().filter(|x| x.joint == target && !x.times.is_empty()).find_map(|channel| match &channel.values {
+            ChannelValues::Weights(values) => Some(channel.sample_weights(values, time)),
+            _ => None,
+        })
+    }
+
+    // A copy with the joints renamed by `map`, dropping channels it returns `None` for.
+    // Used to play clips on skeletons whose joints are numbered differently, e.g. glTF clips (which animate nodes)
+    // on a skin's joints.
+    pub fn retarget<F>(&self, map: F) -> AnimationClip
+        where F: Fn(usize) -> Option<usize> {
+        let channels = self.channels.iter().filter_map(|channel| {
+            map(channel.joint).map(|joint| Channel { joint, ..channel.clone() })
+        }).collect();
+
+        AnimationClip { name: self.name.clone(), duration: self.duration, channels }
+    }
+}
@@ -0,0 +1,150 @@
+use crate::math::{Matrix4, Vector3};
+
+// Axis-aligned bounding box.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+
+impl Aabb {
+    pub fn new(min: Vector3, max: Vector3) -> Self {
+        Aabb { min, max }
+    }
+
+    // Returns `None` when there are no points.
+    pub fn from_points<I>(points: I) -> Option<Aabb>
+        where I: IntoIterator<Item = Vector3> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+
+        Some(points.fold(Aabb::new(first, first), |aabb, point| Aabb::new(aabb.min.min(point), aabb.max.max(point))))
+    }
+
+    pub fn center(&self) -> Vector3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn extents(&self) -> Vector3 {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    // The box around the transformed box. It grows with rotations but never misses a corner.
+    pub fn transform(&self, matrix: &Matrix4) -> Aabb {
+        let center = matrix.transform_point(self.center());
+        let extents = self.extents();
+        let m = &matrix.data;
+
+        let extents = Vector3::new(
+            m[0].abs() * extents.x + m[4].abs() * extents.y + m[8].abs() * extents.z,
+            m[1].abs() * extents.x + m[5].abs() * extents.y + m[9].abs() * extents.z,
+            m[2].abs() * extents.x + m[6].abs() * extents.y + m[10].abs() * extents.z,
+        );
+
+        Aabb::new(center - extents, center + extents)
+    }
+}
+
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vector3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: Vector3, radius: f32) -> Self {
+        BoundingSphere { center, radius }
+    }
+
+    // The sphere around the center of the box that contains all of `points`.
+    pub fn from_points(points: &[Vector3]) -> Option<BoundingSphere> {
+        let center = Aabb::from_points(points.iter().copied())?.center();
+        let radius = points.iter().map(|x| (*x - center).length()).fold(0.0, f32::max);
+
+        Some(BoundingSphere::new(center, radius))
+    }
+
+    // Non-uniform scale is handled by using the largest scale of the three axes.
+    pub fn transform(&self, matrix: &Matrix4) -> BoundingSphere {
+        let scale = matrix.transform_vector(Vector3::new(1.0, 0.0, 0.0)).length()
+            .max(matrix.transform_vector(Vector3::new(0.0, 1.0, 0.0)).length())
+            .max(matrix.transform_vector(Vector3::new(0.0, 0.0, 1.0)).length());
+
+        BoundingSphere::new(matrix.transform_point(self.center), self.radius * scale)
+    }
+}
+
+// The points `p` with `normal.dot(p) + distance == 0`. The normal points to the inside of a frustum.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Plane {
+    pub normal: Vector3,
+    pub distance: f32,
+}
+
+impl Plane {
+    fn normalized(normal: Vector3, distance: f32) -> Plane {
+        let length = normal.length();
+        if length <= f32::EPSILON {
+            return Plane { normal, distance };
+        }
+
+        Plane { normal: normal * (1.0 / length), distance: distance / length }
+    }
+
+    pub fn signed_distance(&self, point: Vector3) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+}
+
+// The six planes (left, right, bottom, top, near, far) of a camera's view volume.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Frustum {
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    // Extracts the planes from a view-projection matrix, so they are in world space.
+    pub fn from_matrix(view_projection: &Matrix4) -> Self {
+        let row = |i: usize| {
+            let m = view_projection;
+            (Vector3::new(m.get(i, 0), m.get(i, 1), m.get(i, 2)), m.get(i, 3))
+        };
+        let (w, w_d) = row(3);
+        let plane = |i: usize, sign: f32| {
+            let (n, d) = row(i);
+            Plane::normalized(w + n * sign, w_d + d * sign)
+        };
+
+        Frustum {
+            planes: [plane(0, 1.0), plane(0, -1.0), plane(1, 1.0), plane(1, -1.0), plane(2, 1.0), plane(2, -1.0)],
+        }
+    }
+
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(sphere.center) >= -sphere.radius)
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // The corner furthest along the plane's normal is the last one to leave the frustum.
+            let corner = Vector3::new(
+                if plane.normal.x >= 0.0 { aabb.max.x } else { aabb.min.x },
+                if plane.normal.y >= 0.0 { aabb.max.y } else { aabb.min.y },
+                if plane.normal.z >= 0.0 { aabb.max.z } else { aabb.min.z },
+            );
+
+            plane.signed_distance(corner) >= 0.0
+        })
+    }
+}
@@ -0,0 +1,201 @@
+use crate::math::{Matrix4, Quaternion, Vector3};
+
+// How a camera projects the scene. Both variants follow the camera's aspect ratio.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Projection {
+    // `fov_y` is the vertical field of view in radians.
+    Perspective { fov_y: f32, near: f32, far: f32 },
+    // `height` is how many world units fit vertically on the screen.
+    Orthographic { height: f32, near: f32, far: f32 },
+}
+
+// A camera placed in the world. Like in OpenGL, it looks down its local -Z axis with +Y up.
+// Set it with `Renderer::set_camera` and the renderer supplies `u_view` and `u_projection` to every material.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Camera {
+    pub position: Vector3,
+    pub rotation: Quaternion,
+    pub projection: Projection,
+    // Width / height of the viewport. `Renderer::resize` keeps the active camera's aspect up to date.
+    pub aspect: f32,
+}
+
+impl Camera {
+    // `fov_y` is in degrees.
+    pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Self {
+        Camera {
+            position: Vector3::zero(),
+            rotation: Quaternion::identity(),
+            projection: Projection::Perspective { fov_y: fov_y.to_radians(), near, far },
+            aspect,
+        }
+    }
+
+    pub fn orthographic(height: f32, aspect: f32, near: f32, far: f32) -> Self {
+        Camera {
+            position: Vector3::zero(),
+            rotation: Quaternion::identity(),
+            projection: Projection::Orthographic { height, near, far },
+            aspect,
+        }
+    }
+
+    // An orthographic camera for 2D drawing in pixels, with (0, 0) at the bottom left of the screen and
+    // (width, height) at the top right. Anything between -1.0 and 1.0 on the Z axis is visible.
+    pub fn screen(width: f32, height: f32) -> Self {
+        let mut camera = Camera::orthographic(height, width / height, -1.0, 1.0);
+        camera.position = Vector3::new(width * 0.5, height * 0.5, 0.0);
+
+        camera
+    }
+
+    pub fn set_aspect(&mut self, aspect: f32) {
+        self.aspect = aspect;
+    }
+
+    // Turns the camera so that it looks at `target`. `up` is usually `Vector3::up()`.
+    pub fn look_at(&mut self, target: Vector3, up: Vector3) {
+        let forward = (target - self.position).normalize();
+        let right = forward.cross(up).normalize();
+        // Looking straight along `up` leaves no way to tell where right is.
+        if right == Vector3::zero() {
+            return;
+        }
+        let up = right.cross(forward);
+
+        self.rotation = Quaternion::from_rotation_axes(right, up, -forward);
+    }
+
+    pub fn forward(&self) -> Vector3 {
+        self.rotation.rotate(Vector3::new(0.0, 0.0, -1.0))
+    }
+
+    pub fn right(&self) -> Vector3 {
+        self.rotation.rotate(Vector3::new(1.0, 0.0, 0.0))
+    }
+
+    pub fn up(&self) -> Vector3 {
+        self.rotation.rotate(Vector3::new(0.0, 1.0, 0.0))
+    }
+
+    // Transforms from world space into the camera's space.
+    pub fn view_matrix(&self) -> Matrix4 {
+        Matrix4::rotation(self.rotation.conjugate()) * Matrix4::translation(-self.position)
+    }
+
+    pub fn projection_matrix(&self) -> Matrix4 {
+        match self.projection {
+            Projection::Perspective { fov_y, near, far } => Matrix4::perspective(fov_y, self.aspect, near, far),
+            Projection::Orthographic { height, near, far } => {
+                let half_height = height * 0.5;
+                let half_width = half_height * self.aspect;
+                Matrix4::orthographic(-half_width, half_width, -half_height, half_height, near, far)
+            }
+        }
+    }
+
+    pub fn view_projection_matrix(&self) -> Matrix4 {
+        self.projection_matrix() * self.view_matrix()
+    }
+}
+
+// First-person style controls: the mouse turns the camera and movement is relative to where it looks.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FlyController {
+    // In radians.
+    pub yaw: f32,
+    pub pitch: f32,
+    // World units per second.
+    pub speed: f32,
+    // Radians per pixel of mouse movement.
+    pub sensitivity: f32,
+}
+
+impl FlyController {
+    pub fn new(speed: f32, sensitivity: f32) -> Self {
+        FlyController { yaw: 0.0, pitch: 0.0, speed, sensitivity }
+    }
+
+    // Turns the camera by a mouse movement in pixels (positive x is right, positive y is down).
+    pub fn rotate(&mut self, camera: &mut Camera, dx: f32, dy: f32) {
+        // Stop just short of straight up/down so the camera never flips over.
+        let limit = 89f32.to_radians();
+
+        self.yaw -= dx * self.sensitivity;
+        self.pitch = (self.pitch - dy * self.sensitivity).clamp(-limit, limit);
+        camera.rotation = Quaternion::from_euler(self.yaw, self.pitch, 0.0);
+    }
+
+    // Moves the camera relative to where it looks: x is right, y is up and z is forward.
+    // `direction` doesn't have to be normalized, e.g. (1, 0, 1) from holding W and D.
+    pub fn move_by(&self, camera: &mut Camera, direction: Vector3, delta_time: f32) {
+        let direction = direction.normalize();
+        let velocity = camera.right() * direction.x + Vector3::up() * direction.y + camera.forward() * direction.z;
+
+        camera.position = camera.position + velocity * (self.speed * delta_time);
+    }
+}
+
+// Orbits the camera around a target point, like most model viewers and editors.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OrbitController {
+    pub target: Vector3,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    // In radians. A negative pitch looks down on the target.
+    pub yaw: f32,
+    pub pitch: f32,
+    // Radians per pixel of mouse movement.
+    pub sensitivity: f32,
+}
+
+impl OrbitController {
+    pub fn new(target: Vector3, distance: f32) -> Self {
+        OrbitController {
+            target,
+            distance,
+            min_distance: 0.1,
+            max_distance: f32::MAX,
+            yaw: 0.0,
+            pitch: 0.0,
+            sensitivity: 0.005,
+        }
+    }
+
+    // Orbits by a mouse movement in pixels (positive x is right, positive y is down).
+    pub fn rotate(&mut self, dx: f32, dy: f32) {
+        let limit = 89f32.to_radians();
+
+        self.yaw -= dx * self.sensitivity;
+        self.pitch = (self.pitch - dy * self.sensitivity).clamp(-limit, limit);
+    }
+
+    // Moves closer for positive amounts, e.g. the scroll wheel offset.
+    pub fn zoom(&mut self, amount: f32) {
+        self.distance = (self.distance * (1.0 - amount * 0.1)).clamp(self.min_distance, self.max_distance);
+    }
+
+    // Moves the target (and with it the camera) along the camera's right and up axes.
+    pub fn pan(&mut self, camera: &Camera, dx: f32, dy: f32) {
+        let scale = self.distance * self.sensitivity;
+
+        self.target = self.target - camera.right() * (dx * scale) + camera.up() * (dy * scale);
+    }
+
+    // Places the camera on its orbit. Call this after changing the controller.
+    pub fn apply(&self, camera: &mut Camera) {
+        let rotation = Quaternion::from_euler(self.yaw, self.pitch, 0.0);
+
+        camera.rotation = rotation;
+        camera.position = self.target + rotation.rotate(Vector3::new(0.0, 0.0, self.distance));
+    }
+}
@@ -0,0 +1,250 @@
+use std::f32::consts::PI;
+
+use crate::bounds::{Aabb, BoundingSphere};
+use crate::math::{Matrix4, Vector3};
+
+// Segments used for every circle, including the three around a sphere.
+static CIRCLE_SEGMENTS: usize = 24;
+
+pub(crate) static DEBUG_VERTEX_SHADER: &str = "#version 330 core
+layout (location = 0) in vec3 vertexPosition;
+layout (location = 1) in vec4 vertexColor;
+
+uniform mat4 u_view;
+uniform mat4 u_projection;
+
+out vec4 fragmentColor;
+
+void main() {
+    fragmentColor = vertexColor;
+    gl_Position = u_projection * u_view * vec4(vertexPosition, 1.0);
+}";
+
+pub(crate) static DEBUG_FRAGMENT_SHADER: &str = "#version 330 core
+in vec4 fragmentColor;
+
+out vec4 FragColor;
+
+void main() {
+    FragColor = fragmentColor;
+}";
+
+// Position (3) and color (4) of every line vertex.
+pub(crate) static DEBUG_VERTEX_DATA_SIZE: usize = 7;
+
+// One line segment collected by `DebugDraw`.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DebugLine {
+    pub from: Vector3,
+    pub to: Vector3,
+    pub color: (f32, f32, f32, f32),
+    // Seconds left before the line disappears. Lines with 0.0 are drawn once.
+    pub duration: f32,
+    // Whether the line is hidden behind the scene or drawn on top of it.
+    pub depth_test: bool,
+}
+
+// Collects lines for visualising bounds, physics, cameras, etc. without creating objects.
+// Every shape is turned into lines right away; `Renderer::draw_debug` draws them all from a single buffer.
+//
+// The duration and depth test of new shapes come from `set_duration` and `set_depth_test`:
+//
+// let debug = renderer.get_debug_draw_mut();
+// debug.aabb(&bounds, (0.0, 1.0, 0.0, 1.0));
+// debug.set_depth_test(false);
+// debug.axes(&transform.get_local_matrix(), 1.0);
+// debug.set_depth_test(true);
+//
+// Should be exposed to the user.
+pub struct DebugDraw {
+    lines: Vec<DebugLine>,
+    duration: f32,
+    depth_test: bool,
+}
+
+impl Default for DebugDraw {
+    fn default() -> Self {
+        DebugDraw::new()
+    }
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        DebugDraw {
+            lines: vec![],
+            duration: 0.0,
+            depth_test: true,
+        }
+    }
+
+    // How many seconds shapes added from now on stay visible. 0.0 (the default) draws them for one frame.
+    pub fn set_duration(&mut self, duration: f32) {
+        self.duration = duration;
+    }
+
+    pub fn get_duration(&self) -> f32 {
+        self.duration
+    }
+
+    // Whether shapes added from now on are hidden behind the scene (the default) or drawn on top of it.
+    pub fn set_depth_test(&mut self, depth_test: bool) {
+        self.depth_test = depth_test;
+    }
+
+    pub fn get_depth_test(&self) -> bool {
+        self.depth_test
+    }
+
+    pub fn get_lines(&self) -> &[DebugLine] {
+        &self.lines
+    }
+
+    // Removes every line, including the ones with time left.
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    // Counts down the durations and removes the lines that ran out, called by `Renderer::draw_debug`
+    // after drawing. Lines drawn for one frame are always removed.
+    pub fn update(&mut self, delta_time: f32) {
+        for line in self.lines.iter_mut() {
+            line.duration -= delta_time;
+        }
+
+        self.lines.retain(|x| x.duration > 0.0);
+    }
+
+    // The vertices of every line, depth tested ones first, and how many lines are depth tested.
+    pub(crate) fn vertex_data(&self) -> (Vec<f32>, usize) {
+        let mut data = Vec::with_capacity(self.lines.len() * 2 * DEBUG_VERTEX_DATA_SIZE);
+        let depth_tested = self.lines.iter().filter(|x| x.depth_test).collect::<Vec<_>>();
+        let on_top = self.lines.iter().filter(|x| !x.depth_test);
+
+        for line in depth_tested.iter().copied().chain(on_top) {
+            let (r, g, b, a) = line.color;
+            for point in [line.from, line.to].iter() {
+                data.extend([point.x, point.y, point.z, r, g, b, a].iter());
+            }
+        }
+
+        (data, depth_tested.len())
+    }
+
+    pub fn line(&mut self, from: Vector3, to: Vector3, color: (f32, f32, f32, f32)) {
+        self.lines.push(DebugLine { from, to, color, duration: self.duration, depth_test: self.depth_test });
+    }
+
+    // A line with an arrowhead at `to`, a fifth of the arrow's length.
+    pub fn arrow(&mut self, from: Vector3, to: Vector3, color: (f32, f32, f32, f32)) {
+        self.line(from, to, color);
+
+        let direction = to - from;
+        let length = direction.length();
+        if length <= f32::EPSILON {
+            return;
+        }
+
+        let (side, up) = perpendicular_axes(direction * (1.0 / length));
+        let base = to - direction * 0.2;
+        let head_size = length * 0.08;
+        for offset in [side, -side, up, -up].iter() {
+            self.line(to, base + *offset * head_size, color);
+        }
+    }
+
+    pub fn aabb(&mut self, aabb: &Aabb, color: (f32, f32, f32, f32)) {
+        self.oriented_box(&(Matrix4::translation(aabb.center()) * Matrix4::scale(aabb.extents() * 2.0)), color);
+    }
+
+    // The cube from -0.5 to 0.5 on every axis transformed by `transform`, e.g. an object's world matrix
+    // times the scale of its collider.
+    pub fn oriented_box(&mut self, transform: &Matrix4, color: (f32, f32, f32, f32)) {
+        let corners = cube_corners(-0.5, 0.5, |x| transform.transform_point(x));
+        self.cube_edges(&corners, color);
+    }
+
+    // The view volume of a camera, from the inverse of its view-projection matrix
+    // (e.g. `camera.view_projection_matrix()`). Does nothing for matrices that can't be inverted.
+    pub fn frustum(&mut self, view_projection: &Matrix4, color: (f32, f32, f32, f32)) {
+        let inverse = match view_projection.inverse() {
+            Some(inverse) => inverse,
+            None => return,
+        };
+
+        let corners = cube_corners(-1.0, 1.0, |x| inverse.transform_point(x));
+        self.cube_edges(&corners, color);
+    }
+
+    // A circle in the plane facing `normal`.
+    pub fn circle(&mut self, center: Vector3, normal: Vector3, radius: f32, color: (f32, f32, f32, f32)) {
+        let (side, up) = perpendicular_axes(normal.normalize());
+        let point = |i: usize| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * 2.0 * PI;
+            center + side * (angle.cos() * radius) + up * (angle.sin() * radius)
+        };
+
+        for i in 0..CIRCLE_SEGMENTS {
+            self.line(point(i), point(i + 1), color);
+        }
+    }
+
+    // Three circles around the X, Y and Z axes.
+    pub fn sphere(&mut self, center: Vector3, radius: f32, color: (f32, f32, f32, f32)) {
+        self.circle(center, Vector3::new(1.0, 0.0, 0.0), radius, color);
+        self.circle(center, Vector3::new(0.0, 1.0, 0.0), radius, color);
+        self.circle(center, Vector3::new(0.0, 0.0, 1.0), radius, color);
+    }
+
+    pub fn bounding_sphere(&mut self, sphere: &BoundingSphere, color: (f32, f32, f32, f32)) {
+        self.sphere(sphere.center, sphere.radius, color);
+    }
+
+    // A square grid on the XZ plane around `center`, `size` wide with `divisions` cells along each side.
+    pub fn grid(&mut self, center: Vector3, size: f32, divisions: u32, color: (f32, f32, f32, f32)) {
+        let divisions = divisions.max(1);
+        let half = size * 0.5;
+
+        for i in 0..=divisions {
+            let offset = i as f32 / divisions as f32 * size - half;
+            self.line(center + Vector3::new(offset, 0.0, -half), center + Vector3::new(offset, 0.0, half), color);
+            self.line(center + Vector3::new(-half, 0.0, offset), center + Vector3::new(half, 0.0, offset), color);
+        }
+    }
+
+    // The X (red), Y (green) and Z (blue) axes of `transform`, `size` long before scaling.
+    pub fn axes(&mut self, transform: &Matrix4, size: f32) {
+        let origin = transform.get_translation();
+        self.arrow(origin, transform.transform_point(Vector3::new(size, 0.0, 0.0)), (1.0, 0.0, 0.0, 1.0));
+        self.arrow(origin, transform.transform_point(Vector3::new(0.0, size, 0.0)), (0.0, 1.0, 0.0, 1.0));
+        self.arrow(origin, transform.transform_point(Vector3::new(0.0, 0.0, size)), (0.0, 0.0, 1.0, 1.0));
+    }
+
+    // The 12 edges between the corners returned by `cube_corners`.
+    fn cube_edges(&mut self, corners: &[Vector3; 8], color: (f32, f32, f32, f32)) {
+        for (a, b) in [(0, 1), (1, 3), (3, 2), (2, 0), (4, 5), (5, 7), (7, 6), (6, 4), (0, 4), (1, 5), (2, 6), (3, 7)].iter() {
+            self.line(corners[*a], corners[*b], color);
+        }
+    }
+}
+
+// The corners of the cube from `min` to `max` on every axis, indexed by bits (x = 1, y = 2, z = 4).
+fn cube_corners<F>(min: f32, max: f32, f: F) -> [Vector3; 8]
+    where F: Fn(Vector3) -> Vector3 {
+    let mut corners = [Vector3::zero(); 8];
+    for (i, corner) in corners.iter_mut().enumerate() {
+        let coordinate = |bit: usize| if i & bit == 0 { min } else { max };
+        *corner = f(Vector3::new(coordinate(1), coordinate(2), coordinate(4)));
+    }
+
+    corners
+}
+
+// Two unit vectors perpendicular to `direction` (which has to be normalized) and to each other.
+fn perpendicular_axes(direction: Vector3) -> (Vector3, Vector3) {
+    let reference = if direction.y.abs() < 0.99 { Vector3::up() } else { Vector3::new(1.0, 0.0, 0.0) };
+    let side = direction.cross(reference).normalize();
+
+    (side, side.cross(direction))
+}
@@ -0,0 +1,247 @@
+use std::fmt;
+
+use crate::preprocessor::uses_legacy_line;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+// A single message from a shader info log, pointing back into the original source files.
+// `line` and `column` are 1-based. Not every driver reports a column.
+//
+// Should be exposed to the user.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: Option<String>,
+    pub source_string: Option<usize>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+    // The offending line of source code, if it could be found.
+    pub source_line: Option<String>,
+}
+
+// Parses a driver info log into diagnostics. The following formats are understood:
+//
+// Mesa:    `0:12(5): error: 'foo' undeclared`
+// NVIDIA:  `0(12) : error C1008: undefined variable "foo"`
+// AMD:     `ERROR: 0:12: 'foo' : undeclared identifier`
+//
+// Lines that don't match any of them are kept as `Info` diagnostics without a location.
+// `files` maps source string numbers to file names and `source` is the code that was given
+// to the driver, which is used to look up the offending lines.
+pub fn parse_info_log(log: &str, files: &[String], source: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+
+    for line in log.lines() {
+        let line = line.trim_end_matches('\0').trim();
+        if line.is_empty() || is_summary_line(line) {
+            continue;
+        }
+
+        let mut diagnostic = parse_mesa(line)
+            .or_else(|| parse_nvidia(line))
+            .or_else(|| parse_amd(line))
+            .unwrap_or(Diagnostic {
+                severity: Severity::Info,
+                file: None,
+                source_string: None,
+                line: None,
+                column: None,
+                message: line.to_string(),
+                source_line: None,
+            });
+
+        if let Some(index) = diagnostic.source_string {
+            diagnostic.file = files.get(index).cloned();
+            if let Some(line) = diagnostic.line {
+                diagnostic.source_line = find_source_line(source, index, line);
+            }
+        }
+
+        diagnostics.push(diagnostic);
+    }
+
+    diagnostics
+}
+
+// `0:12(5): error: message`
+fn parse_mesa(line: &str) -> Option<Diagnostic> {
+    let (source_string, rest) = split_number(line)?;
+    let rest = rest.strip_prefix(':')?;
+    let (line_number, rest) = split_number(rest)?;
+    let rest = rest.strip_prefix('(')?;
+    let (column, rest) = split_number(rest)?;
+    let rest = rest.strip_prefix("):")?.trim_start();
+
+    let (severity, message) = split_severity(rest)?;
+
+    Some(located(severity, source_string, line_number, Some(column), message))
+}
+
+// `0(12) : error C1008: message`
+fn parse_nvidia(line: &str) -> Option<Diagnostic> {
+    let (source_string, rest) = split_number(line)?;
+    let rest = rest.strip_prefix('(')?;
+    let (line_number, rest) = split_number(rest)?;
+    let rest = rest.strip_prefix(')')?.trim_start().strip_prefix(':')?.trim_start();
+
+    let (severity, rest) = split_severity_word(rest)?;
+    // Skip the error code (`C1008`) if there is one.
+    let message = match rest.find(':') {
+        Some(i) if !rest[..i].contains(' ') => rest[i + 1..].trim_start(),
+        _ => rest.trim_start_matches(':').trim_start(),
+    };
+
+    Some(located(severity, source_string, line_number, None, message))
+}
+
+// `ERROR: 0:12: message`
+fn parse_amd(line: &str) -> Option<Diagnostic> {
+    let (severity, rest) = split_severity_word(line)?;
+    let rest = rest.strip_prefix(':')?.trim_start();
+    let (source_string, rest) = split_number(rest)?;
+    let rest = rest.strip_prefix(':')?;
+    let (line_number, rest) = split_number(rest)?;
+    let message = rest.strip_prefix(':')?.trim_start();
+
+    Some(located(severity, source_string, line_number, None, message))
+}
+
+fn located(severity: Severity, source_string: usize, line: usize, column: Option<usize>, message: &str) -> Diagnostic {
+    Diagnostic {
+        severity,
+        file: None,
+        source_string: Some(source_string),
+        line: Some(line),
+        column,
+        message: message.to_string(),
+        source_line: None,
+    }
+}
+
+fn split_number(text: &str) -> Option<(usize, &str)> {
+    let end = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    if end == 0 {
+        return None;
+    }
+
+    Some((text[..end].parse().ok()?, &text[end..]))
+}
+
+// `error: message`, `preprocessor error: message`, `warning: message`
+fn split_severity(text: &str) -> Option<(Severity, &str)> {
+    let colon = text.find(':')?;
+    let severity = severity_from_word(text[..colon].rsplit(' ').next()?)?;
+
+    Some((severity, text[colon + 1..].trim_start()))
+}
+
+// `error C1008: message`, `ERROR: message`
+fn split_severity_word(text: &str) -> Option<(Severity, &str)> {
+    let end = text.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(text.len());
+    let severity = severity_from_word(&text[..end])?;
+
+    Some((severity, text[end..].trim_start()))
+}
+
+fn severity_from_word(word: &str) -> Option<Severity> {
+    match word.to_ascii_lowercase().as_str() {
+        "error" | "fatal" => Some(Severity::Error),
+        "warning" => Some(Severity::Warning),
+        "info" | "note" | "remark" => Some(Severity::Info),
+        _ => None,
+    }
+}
+
+// AMD ends its logs with `ERROR: 2 compilation errors.  No code generated.`
+fn is_summary_line(line: &str) -> bool {
+    line.contains("compilation error") && line.contains("No code generated")
+}
+
+// Finds a line of the original source by following the `#line` directives written by the preprocessor.
+fn find_source_line(source: &str, source_string: usize, line: usize) -> Option<String> {
+    // Before GLSL 3.30 `#line N` numbers the next line N + 1.
+    let offset = if uses_legacy_line(source) { 1 } else { 0 };
+    let mut current_string = 0usize;
+    let mut current_line = 1usize;
+
+    for text in source.lines() {
+        if let Some(rest) = text.trim_start().strip_prefix("#line") {
+            let mut parts = rest.split_whitespace();
+            if let Some(Ok(number)) = parts.next().map(|x| x.parse::<usize>()) {
+                current_line = number + offset;
+                current_string = parts.next().and_then(|x| x.parse().ok()).unwrap_or(current_string);
+                continue;
+            }
+        }
+
+        if current_string == source_string && current_line == line {
+            return Some(text.to_string());
+        }
+        current_line += 1;
+    }
+
+    None
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Info => write!(f, "info"),
+        }
+    }
+}
+
+// Renders the diagnostic along with the offending line and a caret under the reported column
+// (or under the start of the line if the driver didn't give one):
+//
+// error: 'foo' undeclared
+//   --> shaders/lit.frag:12:5
+//    |
+// 12 |     foo = 1.0;
+//    |     ^
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)?;
+
+        let line = match self.line {
+            Some(line) => line,
+            None => return Ok(()),
+        };
+
+        let file = match (&self.file, self.source_string) {
+            (Some(file), _) => file.clone(),
+            (None, Some(index)) => format!("<source {}>", index),
+            (None, None) => "<source>".to_string(),
+        };
+        match self.column {
+            Some(column) => write!(f, "\n  --> {}:{}:{}", file, line, column)?,
+            None => write!(f, "\n  --> {}:{}", file, line)?,
+        }
+
+        if let Some(source_line) = &self.source_line {
+            let gutter = " ".repeat(line.to_string().len());
+            let caret = match self.column {
+                Some(column) => column.saturating_sub(1),
+                None => source_line.len() - source_line.trim_start().len(),
+            };
+
+            write!(f, "\n{} |", gutter)?;
+            write!(f, "\n{} | {}", line, source_line)?;
+            // Keep tabs so the caret lines up with the source line.
+            let padding = source_line.chars().take(caret)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+            write!(f, "\n{} | {}^", gutter, padding)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,447 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+
+use ab_glyph::{point, Font as _, FontVec, GlyphId, ScaleFont};
+
+use crate::material::{AttributeType, Material};
+use crate::sprite::{self, Sprite, SpriteBatch, SPRITE_VERTEX_SHADER};
+use crate::texture::{FilteringType, Texture, TextureData};
+
+// Empty pixels around every glyph in the atlas, so that linear filtering doesn't pick up the neighbours.
+static GLYPH_PADDING: u32 = 1;
+// The atlas doubles in height when it's full, up to this many pixels.
+static MAX_ATLAS_SIZE: i32 = 4096;
+// How many spaces a tab is wide.
+static TAB_WIDTH: f32 = 4.0;
+
+// Uses the red channel of the atlas as the coverage of the tint.
+static TEXT_FRAGMENT_SHADER: &str = "#version 330 core
+in vec4 fragmentColor;
+in vec2 fragmentTexCoords;
+
+uniform sampler2D u_texture;
+
+out vec4 FragColor;
+
+void main() {
+    FragColor = vec4(fragmentColor.rgb, fragmentColor.a * texture(u_texture, fragmentTexCoords).r);
+}";
+
+#[derive(Debug)]
+pub enum FontError {
+    FileNotFound { path: String },
+    // The data isn't a TrueType or OpenType font.
+    Invalid,
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontError::FileNotFound { path } => write!(f, "The path `{}` does not exist!", path),
+            FontError::Invalid => write!(f, "Invalid font file"),
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
+// How lines are placed relative to the position passed to `Font::layout`.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TextAlign {
+    Left,
+    // Centered on the position, or in the middle of `max_width` when there is one.
+    Center,
+    // Ending at the position, or at the end of `max_width` when there is one.
+    Right,
+}
+
+// How `Font::layout` places and colors text. Built like a `Sprite`, e.g. `TextStyle::new().align(TextAlign::Center)`.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextStyle {
+    pub color: (f32, f32, f32, f32),
+    pub align: TextAlign,
+    // Lines longer than this are wrapped between words. Words that don't fit on their own stick out.
+    pub max_width: Option<f32>,
+    // Multiplies the font's line height.
+    pub line_spacing: f32,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        TextStyle {
+            color: (1.0, 1.0, 1.0, 1.0),
+            align: TextAlign::Left,
+            max_width: None,
+            line_spacing: 1.0,
+        }
+    }
+}
+
+impl TextStyle {
+    pub fn new() -> Self {
+        TextStyle::default()
+    }
+
+    pub fn color(mut self, color: (f32, f32, f32, f32)) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn max_width(mut self, max_width: f32) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    pub fn line_spacing(mut self, line_spacing: f32) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+}
+
+// Where a rasterized glyph is in the atlas and where it goes relative to the pen on the baseline
+// (`offset` points to its top left corner, with y pointing down).
+//
+// Should NOT be exposed to the user.
+#[derive(Copy, Clone)]
+struct AtlasGlyph {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    offset: (f32, f32),
+}
+
+// A glyph placed on a line by `Font::layout_lines`, `x` being the pen position.
+//
+// Should NOT be exposed to the user.
+#[derive(Copy, Clone)]
+struct PlacedGlyph {
+    id: GlyphId,
+    x: f32,
+    advance: f32,
+    whitespace: bool,
+}
+
+// A TrueType or OpenType font at one pixel size. Glyphs are rasterized into a single channel atlas the first
+// time they are laid out, so any character the font has can be drawn. The atlas starts out big enough for a
+// few hundred glyphs and grows when it's full, which changes the texture coordinates of text laid out before.
+// Once it can't grow anymore, glyphs that don't fit are left out (see `is_atlas_full`).
+//
+// Should be exposed to the user.
+pub struct Font {
+    font: FontVec,
+    // Height in pixels from the lowest descender to the highest ascender.
+    size: f32,
+    atlas: TextureData,
+    // Created by `get_texture`, so that laying out text doesn't need an OpenGL context.
+    texture: Option<Texture>,
+    // Whether glyphs were added since the atlas was last uploaded.
+    dirty: bool,
+    // `None` for glyphs without pixels (like spaces). Glyphs that didn't fit in the atlas aren't in here.
+    glyphs: HashMap<GlyphId, Option<AtlasGlyph>>,
+    // The atlas is filled in rows: where the next glyph goes and how tall the current row is.
+    cursor: (u32, u32),
+    row_height: u32,
+    atlas_full: bool,
+}
+
+impl Font {
+    pub fn from_file(path: &str, size: f32) -> Self {
+        Font::try_from_file(path, size).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_from_file(path: &str, size: f32) -> Result<Self, FontError> {
+        let data = fs::read(path).map_err(|_| FontError::FileNotFound { path: path.to_string() })?;
+
+        Font::try_from_bytes(data, size)
+    }
+
+    // `size` is the height of a line in pixels, from the lowest descender to the highest ascender.
+    pub fn from_bytes(data: Vec<u8>, size: f32) -> Self {
+        Font::try_from_bytes(data, size).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_from_bytes(data: Vec<u8>, size: f32) -> Result<Self, FontError> {
+        let font = FontVec::try_from_vec(data).map_err(|_| FontError::Invalid)?;
+        let atlas_size = ((size.max(1.0) * 16.0) as u32).next_power_of_two().clamp(256, 4096) as i32;
+
+        Ok(Font {
+            font,
+            size,
+            atlas: TextureData {
+                width: atlas_size,
+                height: atlas_size,
+                data: vec![0; (atlas_size * atlas_size) as usize],
+            },
+            texture: None,
+            dirty: true,
+            glyphs: HashMap::new(),
+            cursor: (GLYPH_PADDING, GLYPH_PADDING),
+            row_height: 0,
+            atlas_full: false,
+        })
+    }
+
+    pub fn get_size(&self) -> f32 {
+        self.size
+    }
+
+    // Distance between the baselines of two lines, before `TextStyle::line_spacing`.
+    pub fn get_line_height(&self) -> f32 {
+        let font = self.font.as_scaled(self.size);
+
+        font.height() + font.line_gap()
+    }
+
+    // How far the font goes above the baseline, in pixels.
+    pub fn get_ascent(&self) -> f32 {
+        self.font.as_scaled(self.size).ascent()
+    }
+
+    // How far the font goes below the baseline, in pixels. Negative, like in the font file.
+    pub fn get_descent(&self) -> f32 {
+        self.font.as_scaled(self.size).descent()
+    }
+
+    // The glyph atlas, one byte of coverage per pixel.
+    pub fn get_atlas(&self) -> &TextureData {
+        &self.atlas
+    }
+
+    // Whether a glyph was left out because the atlas couldn't grow anymore. They are tried again every time
+    // they are laid out, e.g. after `clear_atlas`.
+    pub fn is_atlas_full(&self) -> bool {
+        self.atlas_full
+    }
+
+    // Removes every glyph from the atlas, so that only the ones laid out from now on take up room.
+    // The atlas keeps its size.
+    pub fn clear_atlas(&mut self) {
+        self.atlas.data.iter_mut().for_each(|x| *x = 0);
+        self.glyphs.clear();
+        self.cursor = (GLYPH_PADDING, GLYPH_PADDING);
+        self.row_height = 0;
+        self.atlas_full = false;
+        self.dirty = true;
+    }
+
+    // The atlas texture, uploading the glyphs that were added since the last call.
+    pub fn get_texture(&mut self) -> &Texture {
+        match self.texture.as_ref() {
+            Some(texture) if self.dirty => texture.update_red(&self.atlas),
+            Some(_) => {},
+            None => self.texture = Some(Texture::from_red(FilteringType::Linear, self.atlas.clone())),
+        }
+        self.dirty = false;
+
+        self.texture.as_ref().unwrap()
+    }
+
+    // The width and height of the text in pixels, as `layout` would place it.
+    pub fn measure(&self, text: &str, style: &TextStyle) -> (f32, f32) {
+        let lines = self.layout_lines(text, style.max_width);
+        let width = lines.iter().map(|(_, width)| *width).fold(0.0, f32::max);
+        let height = (lines.len() - 1) as f32 * self.get_line_height() * style.line_spacing + self.size;
+
+        (width, height)
+    }
+
+    // Turns the text into one sprite per visible glyph, with `position` at the top left of the first line
+    // (see `TextAlign` for the other alignments). Like sprites, y points up, so lines go down from there.
+    // The sprites use the atlas from `get_texture`, which has to be called after this to upload new glyphs.
+    pub fn layout(&mut self, text: &str, position: (f32, f32), style: &TextStyle) -> Vec<Sprite> {
+        let lines = self.layout_lines(text, style.max_width);
+        let line_height = self.get_line_height() * style.line_spacing;
+        let ascent = self.get_ascent();
+
+        let mut placed = vec![];
+        for (i, (glyphs, width)) in lines.iter().enumerate() {
+            let space = style.max_width.unwrap_or(0.0) - width;
+            let line_x = position.0 + match style.align {
+                TextAlign::Left => 0.0,
+                TextAlign::Center => space * 0.5,
+                TextAlign::Right => space,
+            };
+            // Glyphs are rasterized at whole pixels, so they are placed at whole pixels to stay sharp.
+            let baseline = (position.1 - ascent - i as f32 * line_height).round();
+
+            for glyph in glyphs.iter().filter(|x| !x.whitespace) {
+                if let Some(atlas_glyph) = self.rasterize(glyph.id) {
+                    placed.push(((line_x + glyph.x).round(), baseline, atlas_glyph));
+                }
+            }
+        }
+
+        // The atlas can grow while the glyphs are added, so the texture coordinates wait for its final size.
+        let atlas_size = (self.atlas.width as f32, self.atlas.height as f32);
+        placed.iter().map(|(pen_x, baseline, atlas_glyph)| {
+            let x = pen_x + atlas_glyph.offset.0;
+            let top = baseline - atlas_glyph.offset.1;
+            let (width, height) = (atlas_glyph.width as f32, atlas_glyph.height as f32);
+
+            Sprite::new((x, top - height), (width, height))
+                .source_rect(atlas_glyph.x as f32, atlas_glyph.y as f32, width, height, atlas_size)
+                .tint(style.color)
+        }).collect()
+    }
+
+    // Lays the text out and adds it to a batch that uses `text_material`.
+    pub fn draw_text(&mut self, batch: &mut SpriteBatch, text: &str, position: (f32, f32), style: &TextStyle) {
+        let sprites = self.layout(text, position, style);
+        let texture = self.get_texture().clone();
+
+        for sprite in sprites {
+            batch.draw(&texture, sprite);
+        }
+    }
+
+    // Frees the atlas texture.
+    pub fn delete(&mut self) {
+        if let Some(texture) = self.texture.as_mut() {
+            texture.delete();
+        }
+        self.texture = None;
+        self.dirty = true;
+    }
+
+    // Splits the text into lines at line breaks and, with a `max_width`, between words. Every line comes with
+    // its width without the trailing whitespace.
+    fn layout_lines(&self, text: &str, max_width: Option<f32>) -> Vec<(Vec<PlacedGlyph>, f32)> {
+        let font = self.font.as_scaled(self.size);
+        let line_width = |line: &[PlacedGlyph]| line.iter().rev().find(|x| !x.whitespace).map(|x| x.x + x.advance).unwrap_or(0.0);
+
+        let mut lines = vec![];
+        for paragraph in text.split('\n') {
+            let mut line: Vec<PlacedGlyph> = vec![];
+            let mut x = 0.0;
+            let mut previous: Option<GlyphId> = None;
+            // Where the last word on the line starts, which is where the line is broken when it gets too long.
+            let mut word_start: Option<usize> = None;
+
+            for c in paragraph.chars().filter(|x| *x != '\r') {
+                let whitespace = c.is_whitespace();
+                let id = font.glyph_id(if c == '\t' { ' ' } else { c });
+                if let Some(previous) = previous {
+                    x += font.kern(previous, id);
+                }
+                let advance = font.h_advance(id) * if c == '\t' { TAB_WIDTH } else { 1.0 };
+
+                if !whitespace && line.last().map(|x| x.whitespace).unwrap_or(true) {
+                    word_start = Some(line.len());
+                }
+                if let (Some(max_width), Some(start), false) = (max_width, word_start, whitespace) {
+                    // The first word of a line stays on it, even when it's too long.
+                    if x + advance > max_width && start > 0 {
+                        let rest = line.split_off(start);
+                        let width = line_width(&line);
+                        lines.push((line, width));
+
+                        let shift = rest.first().map(|x| x.x).unwrap_or(x);
+                        line = rest.into_iter().map(|glyph| PlacedGlyph { x: glyph.x - shift, ..glyph }).collect();
+                        x -= shift;
+                        word_start = Some(0);
+                    }
+                }
+
+                line.push(PlacedGlyph { id, x, advance, whitespace });
+                x += advance;
+                previous = Some(id);
+            }
+
+            let width = line_width(&line);
+            lines.push((line, width));
+        }
+
+        lines
+    }
+
+    // Adds a glyph to the atlas unless it's already there. `None` for glyphs without pixels and glyphs that
+    // don't fit.
+    fn rasterize(&mut self, id: GlyphId) -> Option<AtlasGlyph> {
+        if let Some(glyph) = self.glyphs.get(&id) {
+            return *glyph;
+        }
+
+        let outline = match self.font.outline_glyph(id.with_scale_and_position(self.size, point(0.0, 0.0))) {
+            Some(outline) => outline,
+            None => {
+                self.glyphs.insert(id, None);
+                return None;
+            },
+        };
+
+        let bounds = outline.px_bounds();
+        let (width, height) = (bounds.width() as u32, bounds.height() as u32);
+        let (x, y) = match self.allocate(width, height) {
+            Some(position) => position,
+            None => {
+                self.atlas_full = true;
+                return None;
+            },
+        };
+
+        let atlas_width = self.atlas.width as u32;
+        let data = &mut self.atlas.data;
+        outline.draw(|glyph_x, glyph_y, coverage| {
+            if glyph_x < width && glyph_y < height {
+                data[((y + glyph_y) * atlas_width + x + glyph_x) as usize] = (coverage.clamp(0.0, 1.0) * 255.0) as u8;
+            }
+        });
+        self.dirty = true;
+
+        let atlas_glyph = AtlasGlyph { x, y, width, height, offset: (bounds.min.x, bounds.min.y) };
+        self.glyphs.insert(id, Some(atlas_glyph));
+        Some(atlas_glyph)
+    }
+
+    // Finds room for a glyph in the current row, or starts a new one. Grows the atlas when there's no room left
+    // below and returns `None` when it can't grow anymore.
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let atlas_width = self.atlas.width as u32;
+        if width + GLYPH_PADDING * 2 > atlas_width {
+            return None;
+        }
+
+        if self.cursor.0 + width + GLYPH_PADDING > atlas_width {
+            self.cursor = (GLYPH_PADDING, self.cursor.1 + self.row_height + GLYPH_PADDING);
+            self.row_height = 0;
+        }
+        while self.cursor.1 + height + GLYPH_PADDING > self.atlas.height as u32 {
+            if self.atlas.height >= MAX_ATLAS_SIZE {
+                return None;
+            }
+
+            // New rows go below the old ones, so the glyphs that are already there stay where they are.
+            self.atlas.height *= 2;
+            self.atlas.data.resize((self.atlas.width * self.atlas.height) as usize, 0);
+            self.dirty = true;
+        }
+
+        let position = self.cursor;
+        self.cursor.0 += width + GLYPH_PADDING;
+        self.row_height = self.row_height.max(height);
+
+        Some(position)
+    }
+}
+
+// The built-in material for drawing text with a `SpriteBatch`:
+// `SpriteBatch::with_material(1000, font::text_material())`. The atlas only stores coverage, which becomes
+// the alpha of the text color.
+pub fn text_material() -> Material {
+    let mut material = Material::from_shaders(SPRITE_VERTEX_SHADER, TEXT_FRAGMENT_SHADER, vec![],
+        vec![("u_texture", AttributeType::Int1(0))]);
+    material.set_render_state(sprite::sprite_render_state());
+
+    material
+}
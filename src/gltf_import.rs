@@ -0,0 +1,730 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::animation::{AnimationClip, Channel, ChannelValues, Interpolation, Pose, Skeleton};
+use crate::camera::{Camera, Projection};
+use crate::material::{AttributeType, Material};
+use crate::math::{Matrix4, Quaternion, Vector3};
+use crate::render::{FaceCulling, MaterialHandle, MorphTarget, PrimitiveMode, Renderer, SkinVertex, Vertex};
+use crate::render_state::RenderState;
+use crate::scene::SceneGraph;
+use crate::texture::{FilteringType, Texture, TextureData, WrappingType};
+use crate::transform::Transform;
+
+// Texture units the PBR textures of an imported material are bound to. Missing textures are replaced
+// by a 1x1 texture that doesn't change the result (white, or a flat normal), so shaders can always sample all of them.
+//
+// Should be exposed to the user.
+pub static BASE_COLOR_TEXTURE_UNIT: usize = 0;
+pub static METALLIC_ROUGHNESS_TEXTURE_UNIT: usize = 1;
+pub static NORMAL_TEXTURE_UNIT: usize = 2;
+pub static OCCLUSION_TEXTURE_UNIT: usize = 3;
+pub static EMISSIVE_TEXTURE_UNIT: usize = 4;
+
+// How a texture is sampled.
+//
+// Should be exposed to the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GltfSampler {
+    pub mag_filter: FilteringType,
+    pub min_filter: FilteringType,
+    pub wrap_s: WrappingType,
+    pub wrap_t: WrappingType,
+}
+
+// Should be exposed to the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GltfTexture {
+    // Index into `GltfScene::images`.
+    pub image: usize,
+    pub sampler: GltfSampler,
+}
+
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AlphaMode {
+    Opaque,
+    // Fragments with an alpha below the cutoff should be discarded by the shader.
+    Mask(f32),
+    Blend,
+}
+
+// A PBR metallic-roughness material. Textures are indices into `GltfScene::textures`.
+//
+// Should be exposed to the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GltfMaterial {
+    pub name: Option<String>,
+    pub base_color_factor: [f32; 4],
+    pub base_color_texture: Option<usize>,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub metallic_roughness_texture: Option<usize>,
+    pub normal_texture: Option<usize>,
+    pub normal_scale: f32,
+    pub occlusion_texture: Option<usize>,
+    pub occlusion_strength: f32,
+    pub emissive_texture: Option<usize>,
+    pub emissive_factor: [f32; 3],
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool,
+}
+
+impl Default for GltfMaterial {
+    // The material glTF uses for primitives without one.
+    fn default() -> Self {
+        GltfMaterial {
+            name: None,
+            base_color_factor: [1.0, 1.0, 1.0, 1.0],
+            base_color_texture: None,
+            metallic_factor: 1.0,
+            roughness_factor: 1.0,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            normal_scale: 1.0,
+            occlusion_texture: None,
+            occlusion_strength: 1.0,
+            emissive_texture: None,
+            emissive_factor: [0.0, 0.0, 0.0],
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
+        }
+    }
+}
+
+// One draw call worth of a mesh: `POSITION`, `NORMAL`, `TEXCOORD_0` and `COLOR_0` mapped onto `Vertex`.
+// Primitives without indices get `0..vertex count` so that every one of them can be drawn the same way.
+//
+// Should be exposed to the user.
+#[derive(Clone)]
+pub struct GltfPrimitive {
+    pub mode: PrimitiveMode,
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    // Index into `GltfScene::materials`, or `None` for the default material.
+    pub material: Option<usize>,
+    // `JOINTS_0` and `WEIGHTS_0`, for primitives that have both. The joints are indices into the skin of the node.
+    pub skin: Option<Vec<SkinVertex>>,
+    // `POSITION` and `NORMAL` deltas of every morph target.
+    pub morph_targets: Vec<MorphTarget>,
+}
+
+// Should be exposed to the user.
+#[derive(Clone)]
+pub struct GltfMesh {
+    pub name: Option<String>,
+    pub primitives: Vec<GltfPrimitive>,
+    // The initial morph target weights, one per target (the same for every primitive).
+    pub weights: Vec<f32>,
+}
+
+// Should be exposed to the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GltfNode {
+    pub name: Option<String>,
+    // Relative to the parent node. `parent` is always `None`, the hierarchy is described by `children`.
+    pub transform: Transform,
+    pub mesh: Option<usize>,
+    pub camera: Option<Camera>,
+    // Index into `GltfScene::skins` for skinned meshes.
+    pub skin: Option<usize>,
+    // Morph target weights that replace the mesh's.
+    pub weights: Option<Vec<f32>>,
+    pub children: Vec<usize>,
+}
+
+// Should be exposed to the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GltfSkin {
+    pub name: Option<String>,
+    // The nodes that are the joints, in the order `SkinVertex::joints` refers to them.
+    pub joints: Vec<usize>,
+    pub inverse_bind_matrices: Vec<Matrix4>,
+}
+
+// Everything read from a `.gltf`/`.glb` file, with all buffers and images already loaded and decoded.
+// Nothing is sent to the GPU until `create_objects`, so files can be loaded on another thread.
+//
+// Should be exposed to the user.
+#[derive(Clone)]
+pub struct GltfScene {
+    pub meshes: Vec<GltfMesh>,
+    pub materials: Vec<GltfMaterial>,
+    pub textures: Vec<GltfTexture>,
+    // Decoded to RGBA.
+    pub images: Vec<TextureData>,
+    pub nodes: Vec<GltfNode>,
+    // The top-level nodes of the default scene (or the first scene if there is no default).
+    pub roots: Vec<usize>,
+    pub skins: Vec<GltfSkin>,
+    // glTF animates nodes, so the channels' `joint` is a node index. See `get_skin_animation`.
+    pub animations: Vec<AnimationClip>,
+}
+
+#[derive(Debug)]
+pub enum GltfError {
+    // The file, or one of the buffers or images it references, could not be read.
+    FileNotFound { path: String },
+    // The file isn't valid glTF, or a buffer or image in it is broken.
+    Invalid(String),
+}
+
+impl fmt::Display for GltfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GltfError::FileNotFound { path } => write!(f, "The path `{}` does not exist!", path),
+            GltfError::Invalid(message) => write!(f, "Invalid glTF file: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for GltfError {}
+
+// Loads a `.gltf` (with embedded or external buffers) or `.glb` file.
+pub fn load_gltf(path: &str) -> GltfScene {
+    try_load_gltf(path).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_load_gltf(path: &str) -> Result<GltfScene, GltfError> {
+    let data = fs::read(path).map_err(|_| GltfError::FileNotFound { path: path.to_string() })?;
+    let directory = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+
+    parse_gltf(&data, directory)
+}
+
+// Parses the contents of a `.gltf` or `.glb` file. External files are resolved relative to `directory`.
+pub fn parse_gltf(data: &[u8], directory: &Path) -> Result<GltfScene, GltfError> {
+    let gltf::Gltf { document, blob } = gltf::Gltf::from_slice(data).map_err(|e| GltfError::Invalid(e.to_string()))?;
+
+    let mut buffers: Vec<Vec<u8>> = vec![];
+    for buffer in document.buffers() {
+        let data = match buffer.source() {
+            gltf::buffer::Source::Bin => blob.clone()
+                .ok_or_else(|| GltfError::Invalid("A buffer refers to a missing GLB binary chunk".to_string()))?,
+            gltf::buffer::Source::Uri(uri) => load_uri(uri, directory)?,
+        };
+        if data.len() < buffer.length() {
+            return Err(GltfError::Invalid(format!("Buffer {} is shorter than its declared length", buffer.index())));
+        }
+        buffers.push(data);
+    }
+
+    let mut images = vec![];
+    for image in document.images() {
+        let encoded = match image.source() {
+            gltf::image::Source::View { view, .. } => {
+                let buffer = &buffers[view.buffer().index()];
+                buffer.get(view.offset()..view.offset() + view.length())
+                    .ok_or_else(|| GltfError::Invalid(format!("Image {} is outside of its buffer", image.index())))?
+                    .to_vec()
+            }
+            gltf::image::Source::Uri { uri, .. } => load_uri(uri, directory)?,
+        };
+
+        let decoded = image::load_from_memory(&encoded)
+            .map_err(|e| GltfError::Invalid(format!("Image {} could not be decoded: {}", image.index(), e)))?
+            .into_rgba8();
+        images.push(TextureData {
+            width: decoded.width() as i32,
+            height: decoded.height() as i32,
+            data: decoded.into_raw(),
+        });
+    }
+
+    let textures = document.textures().map(|texture| {
+        let sampler = texture.sampler();
+
+        GltfTexture {
+            image: texture.source().index(),
+            sampler: GltfSampler {
+                mag_filter: match sampler.mag_filter() {
+                    Some(gltf::texture::MagFilter::Nearest) => FilteringType::Nearest,
+                    _ => FilteringType::Linear,
+                },
+                min_filter: match sampler.min_filter() {
+                    Some(gltf::texture::MinFilter::Nearest)
+                    | Some(gltf::texture::MinFilter::NearestMipmapNearest)
+                    | Some(gltf::texture::MinFilter::NearestMipmapLinear) => FilteringType::Nearest,
+                    _ => FilteringType::Linear,
+                },
+                wrap_s: wrapping(sampler.wrap_s()),
+                wrap_t: wrapping(sampler.wrap_t()),
+            },
+        }
+    }).collect::<Vec<_>>();
+
+    let materials = document.materials().map(|material| {
+        let pbr = material.pbr_metallic_roughness();
+
+        GltfMaterial {
+            name: material.name().map(|x| x.to_string()),
+            base_color_factor: pbr.base_color_factor(),
+            base_color_texture: pbr.base_color_texture().map(|x| x.texture().index()),
+            metallic_factor: pbr.metallic_factor(),
+            roughness_factor: pbr.roughness_factor(),
+            metallic_roughness_texture: pbr.metallic_roughness_texture().map(|x| x.texture().index()),
+            normal_texture: material.normal_texture().map(|x| x.texture().index()),
+            normal_scale: material.normal_texture().map(|x| x.scale()).unwrap_or(1.0),
+            occlusion_texture: material.occlusion_texture().map(|x| x.texture().index()),
+            occlusion_strength: material.occlusion_texture().map(|x| x.strength()).unwrap_or(1.0),
+            emissive_texture: material.emissive_texture().map(|x| x.texture().index()),
+            emissive_factor: material.emissive_factor(),
+            alpha_mode: match material.alpha_mode() {
+                gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+                gltf::material::AlphaMode::Mask => AlphaMode::Mask(material.alpha_cutoff().unwrap_or(0.5)),
+                gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+            },
+            double_sided: material.double_sided(),
+        }
+    }).collect::<Vec<_>>();
+
+    let mut meshes = vec![];
+    for mesh in document.meshes() {
+        let mut primitives = vec![];
+        for primitive in mesh.primitives() {
+            primitives.push(read_primitive(&primitive, &buffers)
+                .map_err(|e| GltfError::Invalid(format!("Mesh {}: {}", mesh.index(), e)))?);
+        }
+
+        let weights = mesh.weights().map(|x| x.to_vec()).unwrap_or_default();
+        meshes.push(GltfMesh { name: mesh.name().map(|x| x.to_string()), primitives, weights });
+    }
+
+    let nodes = document.nodes().map(|node| {
+        let (translation, rotation, scale) = node.transform().decomposed();
+
+        GltfNode {
+            name: node.name().map(|x| x.to_string()),
+            transform: Transform::new(
+                Vector3::new(translation[0], translation[1], translation[2]),
+                Quaternion::new(rotation[0], rotation[1], rotation[2], rotation[3]),
+                Vector3::new(scale[0], scale[1], scale[2]),
+            ),
+            mesh: node.mesh().map(|x| x.index()),
+            camera: node.camera().map(|x| camera(&x)),
+            skin: node.skin().map(|x| x.index()),
+            weights: node.weights().map(|x| x.to_vec()),
+            children: node.children().map(|x| x.index()).collect(),
+        }
+    }).collect::<Vec<_>>();
+
+    let roots = match document.default_scene().or_else(|| document.scenes().next()) {
+        Some(scene) => scene.nodes().map(|x| x.index()).collect(),
+        None => vec![],
+    };
+
+    let skins = document.skins().map(|skin| {
+        let joints = skin.joints().map(|x| x.index()).collect::<Vec<_>>();
+        let reader = skin.reader(|buffer| buffers.get(buffer.index()).map(|x| x.as_slice()));
+        // Without inverse bind matrices they are all identity matrices.
+        let inverse_bind_matrices = match reader.read_inverse_bind_matrices() {
+            Some(matrices) => matrices.map(|columns| {
+                // Both are column-major.
+                let mut matrix = Matrix4::identity();
+                for (i, value) in columns.iter().flatten().enumerate() {
+                    matrix.data[i] = *value;
+                }
+                matrix
+            }).collect(),
+            None => vec![Matrix4::identity(); joints.len()],
+        };
+        if inverse_bind_matrices.len() < joints.len() {
+            return Err(GltfError::Invalid(format!("Skin {} has fewer inverse bind matrices than joints", skin.index())));
+        }
+
+        Ok(GltfSkin { name: skin.name().map(|x| x.to_string()), joints, inverse_bind_matrices })
+    }).collect::<Result<Vec<_>, _>>()?;
+
+    let mut animations = vec![];
+    for animation in document.animations() {
+        let mut channels = vec![];
+        for channel in animation.channels() {
+            channels.push(read_channel(&channel, &buffers)
+                .map_err(|e| GltfError::Invalid(format!("Animation {}: {}", animation.index(), e)))?);
+        }
+
+        animations.push(AnimationClip::new(animation.name().map(|x| x.to_string()), channels));
+    }
+
+    Ok(GltfScene { meshes, materials, textures, images, nodes, roots, skins, animations })
+}
+
+impl GltfScene {
+    // The skeleton of a skin. Its joints are in the same order as `GltfSkin::joints` and its bind pose
+    // is the transforms of the joint nodes. Parents that aren't joints of the skin are left out.
+    pub fn create_skeleton(&self, skin: usize) -> Skeleton {
+        let joints = &self.skins[skin].joints;
+
+        let mut parents = vec![None; joints.len()];
+        let mut pose = Pose::new(joints.len());
+        for (joint, node) in joints.iter().enumerate() {
+            let transform = &self.nodes[*node].transform;
+            pose.translations[joint] = transform.translation;
+            pose.rotations[joint] = transform.rotation;
+            pose.scales[joint] = transform.scale;
+
+            for child in self.nodes[*node].children.iter() {
+                if let Some(child) = joints.iter().position(|x| x == child) {
+                    parents[child] = Some(joint);
+                }
+            }
+        }
+
+        let names = joints.iter().map(|x| self.nodes[*x].name.clone()).collect();
+        Skeleton::new(parents, self.skins[skin].inverse_bind_matrices[..joints.len()].to_vec(), pose).with_names(names)
+    }
+
+    // An animation with its channels moved from nodes to the joints of a skin, ready for the skin's skeleton.
+    // Channels of nodes that aren't joints of the skin are dropped.
+    pub fn get_skin_animation(&self, animation: usize, skin: usize) -> AnimationClip {
+        let joints = &self.skins[skin].joints;
+        self.animations[animation].retarget(|node| joints.iter().position(|x| *x == node))
+    }
+
+    // Creates a scene node for every glTF node (in the same order, so `nodes[i]` becomes the i-th returned node)
+    // with one renderer object per primitive attached to it. The shaders are compiled once into a base material
+    // and every glTF material becomes an instance of it, see the README for the uniforms and texture units they get.
+    pub fn create_objects(&self, renderer: &mut Renderer, scene: &mut SceneGraph,
+        vertex_shader_path: &str, fragment_shader_path: &str) -> Vec<u32> {
+        let mut textures: HashMap<usize, Texture> = HashMap::new();
+        let mut base: Option<MaterialHandle> = None;
+        let mut materials: HashMap<Option<usize>, MaterialHandle> = HashMap::new();
+
+        let mut parents = vec![None; self.nodes.len()];
+        for (i, node) in self.nodes.iter().enumerate() {
+            for child in node.children.iter() {
+                parents[*child] = Some(i);
+            }
+        }
+
+        // Nodes are created parents first so that every parent already exists.
+        let mut scene_nodes: Vec<Option<u32>> = vec![None; self.nodes.len()];
+        let mut stack = self.roots.iter().rev().copied().collect::<Vec<_>>();
+        stack.extend((0..self.nodes.len()).rev().filter(|x| parents[*x].is_none() && !self.roots.contains(x)));
+        while let Some(i) = stack.pop() {
+            let node = &self.nodes[i];
+            let name = node.name.clone().unwrap_or_else(|| format!("node {}", i));
+            let scene_node = scene.create_node(&name, parents[i].and_then(|x| scene_nodes[x]));
+            scene.set_local_transform(scene_node, &node.transform);
+            scene_nodes[i] = Some(scene_node);
+
+            if let Some(camera) = node.camera {
+                scene.attach_camera(scene_node, camera);
+            }
+
+            if let Some(mesh) = node.mesh {
+                for primitive in self.meshes[mesh].primitives.iter() {
+                    let base = *base.get_or_insert_with(||
+                        renderer.add_material(create_base_material(vertex_shader_path, fragment_shader_path)));
+                    let material = *materials.entry(primitive.material).or_insert_with(|| match primitive.material {
+                        Some(x) => self.create_instance(renderer, base, &self.materials[x], &mut textures),
+                        None => base,
+                    });
+
+                    let object = renderer.create_object(Some(primitive.vertices.clone()), Some(primitive.indices.clone()), material);
+                    renderer.set_object_primitive_mode(object, primitive.mode);
+                    if let (Some(_), Some(skin)) = (node.skin, primitive.skin.as_ref()) {
+                        renderer.set_object_skin(object, skin.clone());
+                    }
+                    if !primitive.morph_targets.is_empty() {
+                        renderer.set_object_morph_targets(object, primitive.morph_targets.clone());
+                        let weights = node.weights.as_ref().unwrap_or(&self.meshes[mesh].weights);
+                        let count = weights.len().min(primitive.morph_targets.len());
+                        renderer.set_object_morph_weights(object, &weights[..count]);
+                    }
+                    scene.attach_object(scene_node, object);
+                }
+            }
+
+            stack.extend(node.children.iter().rev());
+        }
+
+        scene_nodes.into_iter().map(|x| x.unwrap()).collect()
+    }
+
+    // An instance of `base` with the textures, factors and render state of `material`.
+    fn create_instance(&self, renderer: &mut Renderer, base: MaterialHandle, material: &GltfMaterial,
+        textures: &mut HashMap<usize, Texture>) -> MaterialHandle {
+        let instance = renderer.create_material_instance(base);
+
+        let units = [
+            (material.base_color_texture, BASE_COLOR_TEXTURE_UNIT),
+            (material.metallic_roughness_texture, METALLIC_ROUGHNESS_TEXTURE_UNIT),
+            (material.normal_texture, NORMAL_TEXTURE_UNIT),
+            (material.occlusion_texture, OCCLUSION_TEXTURE_UNIT),
+            (material.emissive_texture, EMISSIVE_TEXTURE_UNIT),
+        ];
+        for (index, unit) in units.iter() {
+            // Textures without one keep the base material's placeholder.
+            if let Some(index) = index {
+                let texture = textures.entry(*index).or_insert_with(|| {
+                    let texture = &self.textures[*index];
+                    Texture::from_rgba(
+                        texture.sampler.wrap_s.clone(),
+                        texture.sampler.wrap_t.clone(),
+                        texture.sampler.mag_filter,
+                        texture.sampler.min_filter,
+                        self.images[texture.image].clone(),
+                    )
+                }).clone();
+                renderer.set_instance_texture(instance, *unit, texture);
+            }
+        }
+
+        for (n, t) in factor_attributes(material) {
+            renderer.set_instance_attribute(instance, n, t);
+        }
+        renderer.set_instance_render_state(instance, material_render_state(material));
+
+        instance
+    }
+}
+
+// Compiles the shaders into the material every glTF material is an instance of. It has the factors and render state of
+// `GltfMaterial::default`, and 1x1 placeholder textures: white, except for a flat normal map.
+fn create_base_material(vertex_shader_path: &str, fragment_shader_path: &str) -> Material {
+    let placeholder = |color: [u8; 4]| Texture::from_rgba(
+        WrappingType::Repeat,
+        WrappingType::Repeat,
+        FilteringType::Nearest,
+        FilteringType::Nearest,
+        TextureData { width: 1, height: 1, data: color.to_vec() },
+    );
+    let white = placeholder([255, 255, 255, 255]);
+    let flat_normal = placeholder([128, 128, 255, 255]);
+    let textures = vec![white.clone(), white.clone(), flat_normal, white.clone(), white];
+
+    let default = GltfMaterial::default();
+    let mut attributes = factor_attributes(&default);
+    attributes.extend_from_slice(&[
+        ("u_base_color_texture", AttributeType::Int1(BASE_COLOR_TEXTURE_UNIT as i32)),
+        ("u_metallic_roughness_texture", AttributeType::Int1(METALLIC_ROUGHNESS_TEXTURE_UNIT as i32)),
+        ("u_normal_texture", AttributeType::Int1(NORMAL_TEXTURE_UNIT as i32)),
+        ("u_occlusion_texture", AttributeType::Int1(OCCLUSION_TEXTURE_UNIT as i32)),
+        ("u_emissive_texture", AttributeType::Int1(EMISSIVE_TEXTURE_UNIT as i32)),
+    ]);
+
+    let mut material = Material::from_shader_files(vertex_shader_path, fragment_shader_path, textures, attributes);
+    material.set_render_state(material_render_state(&default));
+
+    material
+}
+
+fn factor_attributes(material: &GltfMaterial) -> Vec<(&'static str, AttributeType)> {
+    let [r, g, b, a] = material.base_color_factor;
+    let [er, eg, eb] = material.emissive_factor;
+    let alpha_cutoff = match material.alpha_mode {
+        AlphaMode::Mask(cutoff) => cutoff,
+        _ => 0.0,
+    };
+
+    vec![
+        ("u_base_color_factor", AttributeType::Float4(r, g, b, a)),
+        ("u_metallic_factor", AttributeType::Float1(material.metallic_factor)),
+        ("u_roughness_factor", AttributeType::Float1(material.roughness_factor)),
+        ("u_normal_scale", AttributeType::Float1(material.normal_scale)),
+        ("u_occlusion_strength", AttributeType::Float1(material.occlusion_strength)),
+        ("u_emissive_factor", AttributeType::Float3(er, eg, eb)),
+        ("u_alpha_cutoff", AttributeType::Float1(alpha_cutoff)),
+    ]
+}
+
+fn material_render_state(material: &GltfMaterial) -> RenderState {
+    let mut render_state = match material.alpha_mode {
+        AlphaMode::Blend => RenderState::transparent(),
+        _ => RenderState::opaque(),
+    };
+    render_state.cull = Some(if material.double_sided { FaceCulling::None } else { FaceCulling::Back });
+
+    render_state
+}
+
+fn read_primitive(primitive: &gltf::Primitive, buffers: &[Vec<u8>]) -> Result<GltfPrimitive, String> {
+    let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|x| x.as_slice()));
+
+    let positions = reader.read_positions()
+        .ok_or_else(|| "A primitive has no POSITION attribute".to_string())?
+        .collect::<Vec<_>>();
+    let normals = reader.read_normals().map(|x| x.collect::<Vec<_>>());
+    let tex_coords = reader.read_tex_coords(0).map(|x| x.into_f32().collect::<Vec<_>>());
+    let colors = reader.read_colors(0).map(|x| x.into_rgba_f32().collect::<Vec<_>>());
+
+    let vertices = positions.iter().enumerate().map(|(i, position)| {
+        let normal = normals.as_ref().and_then(|x| x.get(i)).copied().unwrap_or([0.0, 0.0, 0.0]);
+        let tex_coord = tex_coords.as_ref().and_then(|x| x.get(i)).copied().unwrap_or([0.0, 0.0]);
+        let color = colors.as_ref().and_then(|x| x.get(i)).copied().unwrap_or([1.0, 1.0, 1.0, 1.0]);
+
+        Vertex {
+            position: (position[0], position[1], position[2]),
+            color: (color[0], color[1], color[2], color[3]),
+            tex_coords: (tex_coord[0], tex_coord[1]),
+            normals: (normal[0], normal[1], normal[2]),
+            texture_id: 0.0,
+        }
+    }).collect::<Vec<_>>();
+
+    let skin = match (reader.read_joints(0), reader.read_weights(0)) {
+        (Some(joints), Some(weights)) => Some(joints.into_u16().zip(weights.into_f32()).map(|(j, w)| SkinVertex {
+            joints: (j[0] as f32, j[1] as f32, j[2] as f32, j[3] as f32),
+            weights: (w[0], w[1], w[2], w[3]),
+        }).collect::<Vec<_>>()),
+        _ => None,
+    };
+    if let Some(skin) = skin.as_ref() {
+        if skin.len() != vertices.len() {
+            return Err("JOINTS_0 or WEIGHTS_0 doesn't have one value per vertex".to_string());
+        }
+    }
+
+    let mut morph_targets = vec![];
+    for (positions, normals, _) in reader.read_morph_targets() {
+        let target = MorphTarget {
+            position_deltas: positions.map(|x| x.map(|p| (p[0], p[1], p[2])).collect())
+                .unwrap_or_else(|| vec![(0.0, 0.0, 0.0); vertices.len()]),
+            normal_deltas: normals.map(|x| x.map(|n| (n[0], n[1], n[2])).collect()).unwrap_or_default(),
+        };
+        if target.position_deltas.len() != vertices.len()
+            || (!target.normal_deltas.is_empty() && target.normal_deltas.len() != vertices.len()) {
+            return Err("A morph target doesn't have one value per vertex".to_string());
+        }
+        morph_targets.push(target);
+    }
+
+    let indices = match reader.read_indices() {
+        Some(indices) => indices.into_u32().collect::<Vec<_>>(),
+        None => (0..vertices.len() as u32).collect(),
+    };
+    if let Some(index) = indices.iter().find(|x| **x as usize >= vertices.len()) {
+        return Err(format!("The index {} is out of range", index));
+    }
+
+    let mode = match primitive.mode() {
+        gltf::mesh::Mode::Points => PrimitiveMode::Points,
+        gltf::mesh::Mode::Lines => PrimitiveMode::Lines,
+        gltf::mesh::Mode::LineLoop => PrimitiveMode::LineLoop,
+        gltf::mesh::Mode::LineStrip => PrimitiveMode::LineStrip,
+        gltf::mesh::Mode::Triangles => PrimitiveMode::Triangles,
+        gltf::mesh::Mode::TriangleStrip => PrimitiveMode::TriangleStrip,
+        gltf::mesh::Mode::TriangleFan => PrimitiveMode::TriangleFan,
+    };
+
+    Ok(GltfPrimitive { mode, vertices, indices, material: primitive.material().index(), skin, morph_targets })
+}
+
+fn read_channel(channel: &gltf::animation::Channel, buffers: &[Vec<u8>]) -> Result<Channel, String> {
+    let reader = channel.reader(|buffer| buffers.get(buffer.index()).map(|x| x.as_slice()));
+
+    let times = reader.read_inputs()
+        .ok_or_else(|| "A channel has no keyframe times".to_string())?
+        .collect::<Vec<_>>();
+    let values = match reader.read_outputs().ok_or_else(|| "A channel has no keyframe values".to_string())? {
+        gltf::animation::util::ReadOutputs::Translations(values) =>
+            ChannelValues::Translations(values.map(|x| Vector3::new(x[0], x[1], x[2])).collect()),
+        gltf::animation::util::ReadOutputs::Rotations(values) =>
+            ChannelValues::Rotations(values.into_f32().map(|x| Quaternion::new(x[0], x[1], x[2], x[3])).collect()),
+        gltf::animation::util::ReadOutputs::Scales(values) =>
+            ChannelValues::Scales(values.map(|x| Vector3::new(x[0], x[1], x[2])).collect()),
+        gltf::animation::util::ReadOutputs::MorphTargetWeights(values) =>
+            ChannelValues::Weights(values.into_f32().collect()),
+    };
+
+    let interpolation = match channel.sampler().interpolation() {
+        gltf::animation::Interpolation::Step => Interpolation::Step,
+        gltf::animation::Interpolation::Linear => Interpolation::Linear,
+        gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+    };
+    let value_count = match &values {
+        ChannelValues::Translations(x) | ChannelValues::Scales(x) => x.len(),
+        ChannelValues::Rotations(x) => x.len(),
+        ChannelValues::Weights(x) => x.len(),
+    };
+    let per_keyframe = if interpolation == Interpolation::CubicSpline { 3 } else { 1 };
+    // Weights have a value per morph target for every keyframe.
+    let valid = match &values {
+        ChannelValues::Weights(_) => !times.is_empty() && value_count % (times.len() * per_keyframe) == 0,
+        _ => value_count == times.len() * per_keyframe,
+    };
+    if !valid {
+        return Err("A channel doesn't have the right number of keyframe values".to_string());
+    }
+
+    Ok(Channel { joint: channel.target().node().index(), interpolation, times, values })
+}
+
+fn wrapping(mode: gltf::texture::WrappingMode) -> WrappingType {
+    match mode {
+        gltf::texture::WrappingMode::ClampToEdge => WrappingType::ClampEdge,
+        gltf::texture::WrappingMode::MirroredRepeat => WrappingType::MirroredRepeat,
+        gltf::texture::WrappingMode::Repeat => WrappingType::Repeat,
+    }
+}
+
+// glTF cameras look down -Z like ours. Perspective cameras without a far plane get one at 1000 units.
+fn camera(camera: &gltf::Camera) -> Camera {
+    match camera.projection() {
+        gltf::camera::Projection::Perspective(perspective) => Camera {
+            position: Vector3::zero(),
+            rotation: Quaternion::identity(),
+            projection: Projection::Perspective {
+                fov_y: perspective.yfov(),
+                near: perspective.znear(),
+                far: perspective.zfar().unwrap_or(1000.0),
+            },
+            aspect: perspective.aspect_ratio().unwrap_or(1.0),
+        },
+        gltf::camera::Projection::Orthographic(orthographic) => Camera {
+            position: Vector3::zero(),
+            rotation: Quaternion::identity(),
+            projection: Projection::Orthographic {
+                height: orthographic.ymag() * 2.0,
+                near: orthographic.znear(),
+                far: orthographic.zfar(),
+            },
+            aspect: orthographic.xmag() / orthographic.ymag(),
+        },
+    }
+}
+
+// Reads a `data:` URI or a file relative to `directory`.
+fn load_uri(uri: &str, directory: &Path) -> Result<Vec<u8>, GltfError> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let start = data.find(";base64,")
+            .ok_or_else(|| GltfError::Invalid("Only base64 data URIs are supported".to_string()))?;
+
+        return base64::decode(&data[start + ";base64,".len()..])
+            .map_err(|e| GltfError::Invalid(format!("Invalid base64 data: {}", e)));
+    }
+
+    let path = directory.join(percent_decode(uri));
+    fs::read(&path).map_err(|_| GltfError::FileNotFound { path: path.to_string_lossy().into_owned() })
+}
+
+// URIs escape characters like spaces (`%20`), file names don't.
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = if bytes[i] == b'%' && i + 2 < bytes.len() {
+            std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then(|x| u8::from_str_radix(x, 16).ok())
+        } else {
+            None
+        };
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
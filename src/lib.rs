@@ -2,5 +2,26 @@ pub mod render;
 pub mod shader;
 pub mod material;
 pub mod texture;
+pub mod preprocessor;
+pub mod diagnostics;
+pub mod program_cache;
+pub mod pipeline;
+pub mod render_state;
+mod render_queue;
+pub mod render_pass;
+pub mod math;
+pub mod camera;
+pub mod transform;
+pub mod scene;
+pub mod bounds;
+pub mod obj;
+pub mod gltf_import;
+pub mod mesh;
+pub mod mesh_processing;
+pub mod lod;
+pub mod animation;
+pub mod sprite;
+pub mod debug_draw;
+pub mod font;
 
 extern crate memoffset;
\ No newline at end of file
@@ -0,0 +1,51 @@
+use crate::bounds::BoundingSphere;
+use crate::camera::{Camera, Projection};
+
+// What the LOD thresholds of objects are compared against, see `Renderer::add_object_lod`.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LodMetric {
+    // Distance from the camera to the center of the object's bounding sphere, in world units.
+    // A level is used from its threshold outwards, so thresholds increase with each level.
+    Distance,
+    // Height of the object's bounding sphere on screen, as a fraction of the viewport height (1.0 fills it).
+    // A level is used from its threshold downwards, so thresholds decrease with each level.
+    ScreenSize,
+}
+
+// Picks the level for `value` given the thresholds of levels 1 and up (level 0 has none).
+// Thresholds of coarser levels than `current` are pushed out and those of finer levels pulled in by
+// `hysteresis` (a fraction, 0.1 = 10%), so an object sitting right on a threshold doesn't switch every frame.
+//
+// Should be exposed to the user.
+pub fn select_lod(metric: LodMetric, value: f32, thresholds: &[f32], current: usize, hysteresis: f32) -> usize {
+    thresholds.iter().enumerate().rev().find(|(i, threshold)| {
+        let level = i + 1;
+        match metric {
+            LodMetric::Distance if level > current => value >= *threshold * (1.0 + hysteresis),
+            LodMetric::Distance => value >= *threshold * (1.0 - hysteresis),
+            LodMetric::ScreenSize if level > current => value <= *threshold * (1.0 - hysteresis),
+            LodMetric::ScreenSize => value <= *threshold * (1.0 + hysteresis),
+        }
+    }).map(|(i, _)| i + 1).unwrap_or(0)
+}
+
+// The value `select_lod` compares against the thresholds for a sphere in world space.
+//
+// Should NOT be exposed to the user.
+pub(crate) fn lod_value(metric: LodMetric, camera: &Camera, sphere: &BoundingSphere) -> f32 {
+    let distance = (sphere.center - camera.position).length();
+
+    match (metric, camera.projection) {
+        (LodMetric::Distance, _) => distance,
+        (LodMetric::ScreenSize, Projection::Perspective { fov_y, .. }) => {
+            // Inside the sphere it covers the whole screen (and more).
+            if distance <= sphere.radius {
+                return f32::MAX;
+            }
+            sphere.radius / (distance * (fov_y * 0.5).tan())
+        },
+        (LodMetric::ScreenSize, Projection::Orthographic { height, .. }) => sphere.radius * 2.0 / height,
+    }
+}
@@ -1,8 +1,10 @@
-use std::ffi::CStr;
-use std::fs;
+use std::ffi::CString;
 
 use crate::texture::Texture;
-use crate::shader;
+use crate::shader::{self, ShaderError, ShaderVariants};
+use crate::pipeline::ProgramPipeline;
+use crate::render_state::RenderState;
+use crate::preprocessor;
 
 #[derive(Copy, Clone)]
 pub enum AttributeType {
@@ -39,83 +41,441 @@ pub enum AttributeType {
     Matrix4x2(*const f32),
     Matrix3x4(*const f32),
     Matrix4x3(*const f32),
-}
-
-pub(crate) fn c_str(value: &str) -> *const i8 {
-    let format = format!("{0}\0", value);
-    let as_str = format.as_str();
-    let as_bytes = as_str.as_bytes();
-    let cstr = unsafe { CStr::from_bytes_with_nul_unchecked(as_bytes) }.as_ptr();
-
-    cstr
+    // An array of 4x4 matrices, e.g. joint matrices for skinning.
+    VecMatrix4(i32, *const f32),
 }
 
 pub struct Material {
     program: u32,
+    // Programs that come from a `ShaderVariants` cache are deleted by the cache, not the material.
+    owns_program: bool,
+    // Set when the material draws with a separable program pipeline instead of a single program.
+    pipeline: Option<PipelineBinding>,
+    render_state: RenderState,
     pub textures: Vec<Texture>,
-    attributes: Vec<(String, AttributeType)>
+    attributes: Vec<(String, AttributeType)>,
+    // Locations of the built-in uniforms in the program, or in every stage of the pipeline.
+    builtin_locations: Vec<(u32, [i32; BUILTIN_UNIFORM_COUNT])>,
+}
+
+// The uniforms the renderer sets for every object. Their locations are looked up once when the material
+// is created instead of on every draw.
+//
+// Should NOT be exposed to the user.
+#[derive(Copy, Clone)]
+pub(crate) enum BuiltinUniform {
+    Model,
+    NormalMatrix,
+    View,
+    Projection,
+    JointMatrices,
+    MorphTargets,
+    MorphTargetCount,
+    MorphVertexCount,
+    MorphWeights,
+}
+
+const BUILTIN_UNIFORM_COUNT: usize = 9;
+static BUILTIN_UNIFORM_NAMES: [&str; BUILTIN_UNIFORM_COUNT] = ["u_model", "u_normal_matrix", "u_view", "u_projection",
+    "u_joint_matrices", "u_morph_targets", "u_morph_target_count", "u_morph_vertex_count", "u_morph_weights"];
+
+fn builtin_locations(programs: &[u32]) -> Vec<(u32, [i32; BUILTIN_UNIFORM_COUNT])> {
+    programs.iter().map(|program| {
+        let mut locations = [-1; BUILTIN_UNIFORM_COUNT];
+        for (location, name) in locations.iter_mut().zip(BUILTIN_UNIFORM_NAMES.iter()) {
+            let name = CString::new(*name).unwrap();
+            *location = unsafe { gl::GetUniformLocation(*program, name.as_ptr()) };
+        }
+
+        (*program, locations)
+    }).collect()
+}
+
+// The pipeline object and the separable programs of its stages. Uniforms are set on every stage.
+//
+// Should NOT be exposed to the user.
+struct PipelineBinding {
+    pipeline: u32,
+    stages: Vec<u32>,
 }
 
 impl Material {
     pub fn from_shaders(vertex_shader: &str, fragment_shader: &str, textures: Vec<Texture>,
         attributes: Vec<(&str, AttributeType)>) -> Self {
-        Material {
-            program: shader::compile_program(vertex_shader.to_string(), fragment_shader.to_string()),
+        Material::try_from_shaders(vertex_shader, fragment_shader, textures, attributes)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    // Same as `from_shaders`, but returns the compile error (with its parsed diagnostics) instead of panicking.
+    pub fn try_from_shaders(vertex_shader: &str, fragment_shader: &str, textures: Vec<Texture>,
+        attributes: Vec<(&str, AttributeType)>) -> Result<Self, ShaderError> {
+        let program = shader::try_compile_program(vertex_shader.to_string(), fragment_shader.to_string())?;
+
+        Ok(Material {
+            program,
+            owns_program: true,
+            pipeline: None,
+            render_state: RenderState::default(),
             attributes: attributes.into_iter().map(|(n, t)| (n.to_string(), t)).collect::<Vec<_>>(),
             textures,
-        }
+            builtin_locations: builtin_locations(&[program]),
+        })
     }
 
+    // `#include "..."` directives in the files are resolved relative to the file they appear in.
     pub fn from_shader_files(vertex_shader_path: &str, fragment_shader_path: &str, textures: Vec<Texture>,
         attributes: Vec<(&str, AttributeType)>) -> Self {
+        Material::try_from_shader_files(vertex_shader_path, fragment_shader_path, textures, attributes)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_from_shader_files(vertex_shader_path: &str, fragment_shader_path: &str, textures: Vec<Texture>,
+        attributes: Vec<(&str, AttributeType)>) -> Result<Self, ShaderError> {
+
+            let vertex_source = preprocessor::preprocess_file(vertex_shader_path, &[])?;
+            let fragment_source = preprocessor::preprocess_file(fragment_shader_path, &[])?;
 
-            let vertex_source = Material::get_shader_file(vertex_shader_path);
-            let fragment_source = Material::get_shader_file(fragment_shader_path);
+            let program = shader::try_compile_preprocessed_program(&vertex_source, &fragment_source)?;
 
-            Material {
-                program: shader::compile_program(vertex_source.to_string(), fragment_source.to_string()),
+            Ok(Material {
+                program,
+                owns_program: true,
+                pipeline: None,
+                render_state: RenderState::default(),
                 attributes: attributes.into_iter().map(|(n, t)| (n.to_string(), t)).collect::<Vec<_>>(),
                 textures,
-            }
+                builtin_locations: builtin_locations(&[program]),
+            })
     }
 
-    fn get_shader_file(path: &str) -> String {
-        let error = format!("The path `{0}` does not exist!", path);
-        let contents = fs::read_to_string(path).expect(error.as_str());
+    // Wraps a program that was compiled elsewhere (e.g. by a `ProgramCache`). The material takes ownership of it.
+    pub fn from_program(program: u32, textures: Vec<Texture>, attributes: Vec<(&str, AttributeType)>) -> Self {
+        Material {
+            program,
+            owns_program: true,
+            pipeline: None,
+            render_state: RenderState::default(),
+            attributes: attributes.into_iter().map(|(n, t)| (n.to_string(), t)).collect::<Vec<_>>(),
+            textures,
+            builtin_locations: builtin_locations(&[program]),
+        }
+    }
 
-        contents
+    // Uses the permutation of `variants` with the given defines, compiling it only if no other
+    // material has asked for it yet.
+    pub fn from_shader_variant(variants: &mut ShaderVariants, defines: &[(&str, &str)], textures: Vec<Texture>,
+        attributes: Vec<(&str, AttributeType)>) -> Self {
+        Material::try_from_shader_variant(variants, defines, textures, attributes)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_from_shader_variant(variants: &mut ShaderVariants, defines: &[(&str, &str)], textures: Vec<Texture>,
+        attributes: Vec<(&str, AttributeType)>) -> Result<Self, ShaderError> {
+        let program = variants.try_get_program(defines)?;
+
+        Ok(Material {
+            program,
+            owns_program: false,
+            pipeline: None,
+            render_state: RenderState::default(),
+            attributes: attributes.into_iter().map(|(n, t)| (n.to_string(), t)).collect::<Vec<_>>(),
+            textures,
+            builtin_locations: builtin_locations(&[program]),
+        })
+    }
+
+    // Draws with a pipeline of pre-linked separable stages. Many materials can share the same
+    // stages, the pipeline and its stages stay owned by the caller.
+    pub fn from_pipeline(pipeline: &ProgramPipeline, textures: Vec<Texture>, attributes: Vec<(&str, AttributeType)>) -> Self {
+        let stages = pipeline.get_stage_programs();
+
+        Material {
+            program: 0,
+            owns_program: false,
+            pipeline: Some(PipelineBinding {
+                pipeline: pipeline.get_pipeline_id(),
+                stages: stages.clone(),
+            }),
+            render_state: RenderState::default(),
+            attributes: attributes.into_iter().map(|(n, t)| (n.to_string(), t)).collect::<Vec<_>>(),
+            textures,
+            builtin_locations: builtin_locations(&stages),
+        }
     }
 
     pub fn use_material(&mut self) {
+        self.bind();
+        self.apply_attributes();
+    }
+
+    // Makes the program (or pipeline) current and binds the textures.
+    pub(crate) fn bind(&self) {
         unsafe {
-            gl::UseProgram(self.program);
+            match &self.pipeline {
+                // A bound program takes priority over the bound pipeline, so it has to be unbound first.
+                Some(binding) => {
+                    gl::UseProgram(0);
+                    gl::BindProgramPipeline(binding.pipeline);
+                }
+                None => gl::UseProgram(self.program),
+            }
 
             let mut i = 0usize;
             for texture in self.textures.iter() {
                 texture.use_texture(i);
                 i += 1;
             }
+        }
+    }
+
+    pub(crate) fn apply_attributes(&self) {
+        for (n, t) in self.attributes.iter() {
+            self.apply_attribute(n, *t);
+        }
+    }
+
+    // Blending, depth, stencil, culling, etc. used while drawing objects with this material.
+    pub fn set_render_state(&mut self, render_state: RenderState) {
+        self.render_state = render_state;
+    }
+
+    pub fn get_render_state(&self) -> &RenderState {
+        &self.render_state
+    }
+
+    // Changes (or adds) one of the attributes that are set every time the material is used.
+    pub fn set_attribute(&mut self, n: &str, t: AttributeType) {
+        upsert_attribute(&mut self.attributes, n, t);
+    }
+
+    pub fn get_attribute(&self, n: &str) -> Option<AttributeType> {
+        find_attribute(&self.attributes, n)
+    }
+
+    // Sets a uniform on the material's program, or on every stage of its pipeline.
+    // The material has to be in use.
+    pub(crate) fn apply_attribute(&self, n: &str, t: AttributeType) {
+        match &self.pipeline {
+            Some(binding) => {
+                for stage in binding.stages.iter() {
+                    unsafe { gl::ActiveShaderProgram(binding.pipeline, *stage); }
+                    set_attribute(*stage, n.to_string(), t);
+                }
+            }
+            None => set_attribute(self.program, n.to_string(), t),
+        }
+    }
 
-            for (n, t) in self.attributes.iter() {
-                set_attribute(self.program, (*n).clone(), (*t).clone());
+    // Same as `apply_attribute` for the uniforms the renderer sets on every draw, without looking up their locations.
+    pub(crate) fn apply_builtin(&self, uniform: BuiltinUniform, t: AttributeType) {
+        for (program, locations) in self.builtin_locations.iter() {
+            if let Some(binding) = &self.pipeline {
+                unsafe { gl::ActiveShaderProgram(binding.pipeline, *program); }
             }
+            set_uniform(locations[uniform as usize], t);
         }
     }
 
+    // Returns 0 for materials that use a program pipeline.
     pub fn get_program_id(&self) -> u32 {
         self.program.clone()
     }
 
+    pub fn get_pipeline_id(&self) -> Option<u32> {
+        self.pipeline.as_ref().map(|x| x.pipeline)
+    }
+
     pub fn delete(&mut self) {
+        if !self.owns_program {
+            return;
+        }
+
         unsafe {
-            gl::DeleteShader(self.program);
+            gl::DeleteProgram(self.program);
         }
     }
 }
 
+// Overrides attributes and textures of a base material stored in the `Renderer` while sharing
+// its program, so that many objects can look different without compiling anything.
+//
+// Should be exposed to the user.
+#[derive(Clone)]
+pub struct MaterialInstance {
+    base: u32,
+    textures: Vec<(usize, Texture)>,
+    attributes: Vec<(String, AttributeType)>,
+    render_state: Option<RenderState>,
+}
+
+impl MaterialInstance {
+    pub(crate) fn new(base: u32) -> Self {
+        MaterialInstance {
+            base,
+            textures: vec![],
+            attributes: vec![],
+            render_state: None,
+        }
+    }
+
+    // The index of the base material in the renderer.
+    pub fn get_base(&self) -> u32 {
+        self.base
+    }
+
+    // Overrides an attribute, replacing any earlier override with the same name.
+    pub fn set_attribute(&mut self, n: &str, t: AttributeType) {
+        upsert_attribute(&mut self.attributes, n, t);
+    }
+
+    // Replaces the base material's texture at the given texture unit.
+    pub fn set_texture(&mut self, index: usize, texture: Texture) {
+        match self.textures.iter_mut().find(|(i, _)| *i == index) {
+            Some(slot) => slot.1 = texture,
+            None => self.textures.push((index, texture)),
+        }
+    }
+
+    pub fn get_attribute(&self, n: &str) -> Option<AttributeType> {
+        find_attribute(&self.attributes, n)
+    }
+
+    // Draws with a different render state than the base material, e.g. a transparent variant of an opaque material.
+    pub fn set_render_state(&mut self, render_state: RenderState) {
+        self.render_state = Some(render_state);
+    }
+
+    // `None` if the instance uses the render state of its base material.
+    pub fn get_render_state(&self) -> Option<&RenderState> {
+        self.render_state.as_ref()
+    }
+
+    // Applies the overrides on top of the base material, which has to be in use.
+    pub(crate) fn apply(&self, material: &Material) {
+        for (index, texture) in self.textures.iter() {
+            texture.use_texture(*index);
+        }
+
+        for (n, t) in self.attributes.iter() {
+            material.apply_attribute(n, *t);
+        }
+    }
+
+    // Undoes the overrides after the instance was drawn, so they don't leak into the next objects that use
+    // the base material. Slots the base material declares get its value back, the others are reset to what
+    // an unused slot holds: no texture and zero.
+    pub(crate) fn restore(&self, material: &Material) {
+        for (index, _) in self.textures.iter() {
+            match material.textures.get(*index) {
+                Some(texture) => texture.use_texture(*index),
+                None => unsafe {
+                    gl::ActiveTexture(gl::TEXTURE0 + *index as u32);
+                    gl::BindTexture(gl::TEXTURE_2D, 0);
+                },
+            }
+        }
+
+        for (n, t) in self.attributes.iter() {
+            match material.get_attribute(n) {
+                Some(base) => material.apply_attribute(n, base),
+                None => {
+                    let (zero, _buffer) = zero_attribute(*t);
+                    material.apply_attribute(n, zero);
+                }
+            }
+        }
+    }
+}
+
+// A zero value with the same type and length as `t`. Arrays and matrices point into the returned buffer,
+// which has to live until the value is applied.
+fn zero_attribute(t: AttributeType) -> (AttributeType, Vec<f32>) {
+    let length = |count: i32, components: usize| count.max(0) as usize * components;
+    let buffer = vec![0.0f32; match t {
+        AttributeType::VecFloat1(n, _) | AttributeType::VecInt1(n, _) | AttributeType::VecUInt1(n, _) => length(n, 1),
+        AttributeType::VecFloat2(n, _) | AttributeType::VecInt2(n, _) | AttributeType::VecUInt2(n, _) => length(n, 2),
+        AttributeType::VecFloat3(n, _) | AttributeType::VecInt3(n, _) | AttributeType::VecUInt3(n, _) => length(n, 3),
+        AttributeType::VecFloat4(n, _) | AttributeType::VecInt4(n, _) | AttributeType::VecUInt4(n, _) => length(n, 4),
+        AttributeType::Matrix2(_) => 4,
+        AttributeType::Matrix3(_) => 9,
+        AttributeType::Matrix4(_) => 16,
+        AttributeType::Matrix2x3(_) | AttributeType::Matrix3x2(_) => 6,
+        AttributeType::Matrix2x4(_) | AttributeType::Matrix4x2(_) => 8,
+        AttributeType::Matrix3x4(_) | AttributeType::Matrix4x3(_) => 12,
+        AttributeType::VecMatrix4(n, _) => length(n, 16),
+        _ => 0,
+    }];
+
+    // Zero has the same bits for floats and integers.
+    let f = buffer.as_ptr();
+    let i = f as *const i32;
+    let u = f as *const u32;
+    let zero = match t {
+        AttributeType::Float1(_) => AttributeType::Float1(0.0),
+        AttributeType::Float2(..) => AttributeType::Float2(0.0, 0.0),
+        AttributeType::Float3(..) => AttributeType::Float3(0.0, 0.0, 0.0),
+        AttributeType::Float4(..) => AttributeType::Float4(0.0, 0.0, 0.0, 0.0),
+        AttributeType::Int1(_) => AttributeType::Int1(0),
+        AttributeType::Int2(..) => AttributeType::Int2(0, 0),
+        AttributeType::Int3(..) => AttributeType::Int3(0, 0, 0),
+        AttributeType::Int4(..) => AttributeType::Int4(0, 0, 0, 0),
+        AttributeType::UInt1(_) => AttributeType::UInt1(0),
+        AttributeType::UInt2(..) => AttributeType::UInt2(0, 0),
+        AttributeType::UInt3(..) => AttributeType::UInt3(0, 0, 0),
+        AttributeType::UInt4(..) => AttributeType::UInt4(0, 0, 0, 0),
+        AttributeType::VecFloat1(n, _) => AttributeType::VecFloat1(n, f),
+        AttributeType::VecFloat2(n, _) => AttributeType::VecFloat2(n, f),
+        AttributeType::VecFloat3(n, _) => AttributeType::VecFloat3(n, f),
+        AttributeType::VecFloat4(n, _) => AttributeType::VecFloat4(n, f),
+        AttributeType::VecInt1(n, _) => AttributeType::VecInt1(n, i),
+        AttributeType::VecInt2(n, _) => AttributeType::VecInt2(n, i),
+        AttributeType::VecInt3(n, _) => AttributeType::VecInt3(n, i),
+        AttributeType::VecInt4(n, _) => AttributeType::VecInt4(n, i),
+        AttributeType::VecUInt1(n, _) => AttributeType::VecUInt1(n, u),
+        AttributeType::VecUInt2(n, _) => AttributeType::VecUInt2(n, u),
+        AttributeType::VecUInt3(n, _) => AttributeType::VecUInt3(n, u),
+        AttributeType::VecUInt4(n, _) => AttributeType::VecUInt4(n, u),
+        AttributeType::Matrix2(_) => AttributeType::Matrix2(f),
+        AttributeType::Matrix3(_) => AttributeType::Matrix3(f),
+        AttributeType::Matrix4(_) => AttributeType::Matrix4(f),
+        AttributeType::Matrix2x3(_) => AttributeType::Matrix2x3(f),
+        AttributeType::Matrix3x2(_) => AttributeType::Matrix3x2(f),
+        AttributeType::Matrix2x4(_) => AttributeType::Matrix2x4(f),
+        AttributeType::Matrix4x2(_) => AttributeType::Matrix4x2(f),
+        AttributeType::Matrix3x4(_) => AttributeType::Matrix3x4(f),
+        AttributeType::Matrix4x3(_) => AttributeType::Matrix4x3(f),
+        AttributeType::VecMatrix4(n, _) => AttributeType::VecMatrix4(n, f),
+    };
+
+    (zero, buffer)
+}
+
+// Replaces the attribute with the same name, or adds it if there is none.
+pub(crate) fn upsert_attribute(attributes: &mut Vec<(String, AttributeType)>, n: &str, t: AttributeType) {
+    match attributes.iter_mut().find(|(name, _)| name == n) {
+        Some(attribute) => attribute.1 = t,
+        None => attributes.push((n.to_string(), t)),
+    }
+}
+
+pub(crate) fn find_attribute(attributes: &[(String, AttributeType)], n: &str) -> Option<AttributeType> {
+    attributes.iter().find(|(name, _)| name == n).map(|(_, t)| *t)
+}
+
+// Names with a nul byte can't be uniforms, so they are skipped.
 pub fn set_attribute(p: u32, n: String, t: AttributeType) {
+    let name = match CString::new(n) {
+        Ok(name) => name,
+        Err(_) => return,
+    };
+    // The name has to outlive the call, a pointer into a temporary would dangle.
+    let attrib_loc = unsafe { gl::GetUniformLocation(p, name.as_ptr()) };
+    set_uniform(attrib_loc, t);
+}
+
+// Sets a uniform of the program in use at an already looked up location.
+pub(crate) fn set_uniform(attrib_loc: i32, t: AttributeType) {
     unsafe {
-        let attrib_loc = gl::GetUniformLocation(p, c_str(n.as_str()));
         match t {
             AttributeType::Float1(a) => { gl::Uniform1f(attrib_loc, a) }
             AttributeType::Float2(a, b) => { gl::Uniform2f(attrib_loc, a, b) }
@@ -150,6 +510,7 @@ pub fn set_attribute(p: u32, n: String, t: AttributeType) {
             AttributeType::Matrix4x2(a) => { gl::UniformMatrix4x2fv(attrib_loc, 1, gl::FALSE, a) }
             AttributeType::Matrix3x4(a) => { gl::UniformMatrix3x4fv(attrib_loc, 1, gl::FALSE, a) }
             AttributeType::Matrix4x3(a) => { gl::UniformMatrix4x3fv(attrib_loc, 1, gl::FALSE, a) }
+            AttributeType::VecMatrix4(a, b) => { gl::UniformMatrix4fv(attrib_loc, a, gl::FALSE, b) }
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,526 @@
+use std::ops::{Add, Mul, Neg, Sub};
+
+// Small math types used by cameras, transforms and the mesh utilities.
+// Matrices are column-major, just like OpenGL expects them, so `as_ptr` can be handed
+// straight to `AttributeType::Matrix4`.
+
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub struct Vector3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Vector3 {
+    pub fn new(x: f32, y: f32, z: f32) -> Self {
+        Vector3 { x, y, z }
+    }
+
+    pub fn zero() -> Self {
+        Vector3::new(0.0, 0.0, 0.0)
+    }
+
+    pub fn one() -> Self {
+        Vector3::new(1.0, 1.0, 1.0)
+    }
+
+    pub fn up() -> Self {
+        Vector3::new(0.0, 1.0, 0.0)
+    }
+
+    pub fn dot(&self, other: Vector3) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(&self, other: Vector3) -> Vector3 {
+        Vector3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn length(&self) -> f32 {
+        self.dot(*self).sqrt()
+    }
+
+    // Returns the zero vector instead of NaNs for vectors without a length.
+    pub fn normalize(&self) -> Vector3 {
+        let length = self.length();
+        if length <= f32::EPSILON {
+            return Vector3::zero();
+        }
+
+        *self * (1.0 / length)
+    }
+
+    pub fn lerp(&self, other: Vector3, t: f32) -> Vector3 {
+        *self + (other - *self) * t
+    }
+
+    pub fn min(&self, other: Vector3) -> Vector3 {
+        Vector3::new(self.x.min(other.x), self.y.min(other.y), self.z.min(other.z))
+    }
+
+    pub fn max(&self, other: Vector3) -> Vector3 {
+        Vector3::new(self.x.max(other.x), self.y.max(other.y), self.z.max(other.z))
+    }
+
+    // Component-wise multiplication.
+    pub fn scale(&self, other: Vector3) -> Vector3 {
+        Vector3::new(self.x * other.x, self.y * other.y, self.z * other.z)
+    }
+}
+
+impl From<(f32, f32, f32)> for Vector3 {
+    fn from(value: (f32, f32, f32)) -> Self {
+        Vector3::new(value.0, value.1, value.2)
+    }
+}
+
+impl From<Vector3> for (f32, f32, f32) {
+    fn from(value: Vector3) -> Self {
+        (value.x, value.y, value.z)
+    }
+}
+
+impl Add for Vector3 {
+    type Output = Vector3;
+
+    fn add(self, rhs: Vector3) -> Vector3 {
+        Vector3::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl Sub for Vector3 {
+    type Output = Vector3;
+
+    fn sub(self, rhs: Vector3) -> Vector3 {
+        Vector3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl Mul<f32> for Vector3 {
+    type Output = Vector3;
+
+    fn mul(self, rhs: f32) -> Vector3 {
+        Vector3::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl Neg for Vector3 {
+    type Output = Vector3;
+
+    fn neg(self) -> Vector3 {
+        Vector3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+// A rotation. Always kept normalized by the functions that create one.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Quaternion {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Quaternion::identity()
+    }
+}
+
+impl Quaternion {
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Quaternion { x, y, z, w }
+    }
+
+    pub fn identity() -> Self {
+        Quaternion::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    // `angle` is in radians.
+    pub fn from_axis_angle(axis: Vector3, angle: f32) -> Self {
+        let axis = axis.normalize();
+        let (sin, cos) = (angle * 0.5).sin_cos();
+
+        Quaternion::new(axis.x * sin, axis.y * sin, axis.z * sin, cos)
+    }
+
+    // Yaw around Y, then pitch around X, then roll around Z, all in radians.
+    pub fn from_euler(yaw: f32, pitch: f32, roll: f32) -> Self {
+        Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), yaw)
+            * Quaternion::from_axis_angle(Vector3::new(1.0, 0.0, 0.0), pitch)
+            * Quaternion::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), roll)
+    }
+
+    // Builds a rotation from the columns of a pure rotation matrix.
+    pub fn from_rotation_axes(x_axis: Vector3, y_axis: Vector3, z_axis: Vector3) -> Self {
+        let trace = x_axis.x + y_axis.y + z_axis.z;
+
+        let quaternion = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quaternion::new((y_axis.z - z_axis.y) / s, (z_axis.x - x_axis.z) / s, (x_axis.y - y_axis.x) / s, 0.25 * s)
+        } else if x_axis.x > y_axis.y && x_axis.x > z_axis.z {
+            let s = (1.0 + x_axis.x - y_axis.y - z_axis.z).sqrt() * 2.0;
+            Quaternion::new(0.25 * s, (y_axis.x + x_axis.y) / s, (z_axis.x + x_axis.z) / s, (y_axis.z - z_axis.y) / s)
+        } else if y_axis.y > z_axis.z {
+            let s = (1.0 + y_axis.y - x_axis.x - z_axis.z).sqrt() * 2.0;
+            Quaternion::new((y_axis.x + x_axis.y) / s, 0.25 * s, (z_axis.y + y_axis.z) / s, (z_axis.x - x_axis.z) / s)
+        } else {
+            let s = (1.0 + z_axis.z - x_axis.x - y_axis.y).sqrt() * 2.0;
+            Quaternion::new((z_axis.x + x_axis.z) / s, (z_axis.y + y_axis.z) / s, 0.25 * s, (x_axis.y - y_axis.x) / s)
+        };
+
+        quaternion.normalize()
+    }
+
+    pub fn dot(&self, other: Quaternion) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn normalize(&self) -> Quaternion {
+        let length = self.dot(*self).sqrt();
+        if length <= f32::EPSILON {
+            return Quaternion::identity();
+        }
+
+        Quaternion::new(self.x / length, self.y / length, self.z / length, self.w / length)
+    }
+
+    pub fn conjugate(&self) -> Quaternion {
+        Quaternion::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    pub fn rotate(&self, vector: Vector3) -> Vector3 {
+        let q = Vector3::new(self.x, self.y, self.z);
+        let t = q.cross(vector) * 2.0;
+
+        vector + t * self.w + q.cross(t)
+    }
+
+    // Spherical interpolation along the shortest path.
+    pub fn slerp(&self, other: Quaternion, t: f32) -> Quaternion {
+        let mut other = other;
+        let mut cos = self.dot(other);
+        if cos < 0.0 {
+            other = Quaternion::new(-other.x, -other.y, -other.z, -other.w);
+            cos = -cos;
+        }
+
+        // Nearly identical rotations would divide by ~0, a normalized lerp is just as good there.
+        if cos > 0.9995 {
+            return Quaternion::new(
+                self.x + (other.x - self.x) * t,
+                self.y + (other.y - self.y) * t,
+                self.z + (other.z - self.z) * t,
+                self.w + (other.w - self.w) * t,
+            ).normalize();
+        }
+
+        let angle = cos.acos();
+        let sin = angle.sin();
+        let a = ((1.0 - t) * angle).sin() / sin;
+        let b = (t * angle).sin() / sin;
+
+        Quaternion::new(
+            self.x * a + other.x * b,
+            self.y * a + other.y * b,
+            self.z * a + other.z * b,
+            self.w * a + other.w * b,
+        )
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    // `a * b` rotates by `b` first, then by `a`.
+    fn mul(self, rhs: Quaternion) -> Quaternion {
+        Quaternion::new(
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        )
+    }
+}
+
+// Column-major 3x3 matrix, used for normal matrices.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Matrix3 {
+    pub data: [f32; 9],
+}
+
+impl Matrix3 {
+    pub fn identity() -> Self {
+        Matrix3 { data: [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0] }
+    }
+
+    // The inverse transpose of the upper 3x3 of `matrix`, which transforms normals correctly
+    // even when the matrix contains non-uniform scale.
+    pub fn normal_matrix(matrix: &Matrix4) -> Self {
+        let m = &matrix.data;
+        let (a, b, c) = (m[0], m[4], m[8]);
+        let (d, e, f) = (m[1], m[5], m[9]);
+        let (g, h, i) = (m[2], m[6], m[10]);
+
+        // Only exactly singular matrices are rejected, tiny scales (like millimetres) have tiny determinants too.
+        let determinant = a * (e * i - f * h) - b * (d * i - f * g) + c * (d * h - e * g);
+        let inv = 1.0 / determinant;
+        if determinant == 0.0 || !inv.is_finite() {
+            return Matrix3::identity();
+        }
+
+        // Cofactors of the row-major matrix, which are already the transpose of the inverse.
+        Matrix3 {
+            data: [
+                (e * i - f * h) * inv, -(b * i - c * h) * inv, (b * f - c * e) * inv,
+                -(d * i - f * g) * inv, (a * i - c * g) * inv, -(a * f - c * d) * inv,
+                (d * h - e * g) * inv, -(a * h - b * g) * inv, (a * e - b * d) * inv,
+            ],
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const f32 {
+        self.data.as_ptr()
+    }
+}
+
+// Column-major 4x4 matrix: `data[column * 4 + row]`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Matrix4 {
+    pub data: [f32; 16],
+}
+
+impl Default for Matrix4 {
+    fn default() -> Self {
+        Matrix4::identity()
+    }
+}
+
+impl Matrix4 {
+    pub fn identity() -> Self {
+        Matrix4 {
+            data: [
+                1.0, 0.0, 0.0, 0.0,
+                0.0, 1.0, 0.0, 0.0,
+                0.0, 0.0, 1.0, 0.0,
+                0.0, 0.0, 0.0, 1.0,
+            ],
+        }
+    }
+
+    pub fn translation(translation: Vector3) -> Self {
+        let mut matrix = Matrix4::identity();
+        matrix.data[12] = translation.x;
+        matrix.data[13] = translation.y;
+        matrix.data[14] = translation.z;
+
+        matrix
+    }
+
+    pub fn scale(scale: Vector3) -> Self {
+        let mut matrix = Matrix4::identity();
+        matrix.data[0] = scale.x;
+        matrix.data[5] = scale.y;
+        matrix.data[10] = scale.z;
+
+        matrix
+    }
+
+    pub fn rotation(rotation: Quaternion) -> Self {
+        let Quaternion { x, y, z, w } = rotation;
+        let (xx, yy, zz) = (x * x, y * y, z * z);
+        let (xy, xz, yz) = (x * y, x * z, y * z);
+        let (wx, wy, wz) = (w * x, w * y, w * z);
+
+        Matrix4 {
+            data: [
+                1.0 - 2.0 * (yy + zz), 2.0 * (xy + wz), 2.0 * (xz - wy), 0.0,
+                2.0 * (xy - wz), 1.0 - 2.0 * (xx + zz), 2.0 * (yz + wx), 0.0,
+                2.0 * (xz + wy), 2.0 * (yz - wx), 1.0 - 2.0 * (xx + yy), 0.0,
+                0.0, 0.0, 0.0, 1.0,
+            ],
+        }
+    }
+
+    // Translation * rotation * scale, i.e. scales first and translates last.
+    pub fn from_transform(translation: Vector3, rotation: Quaternion, scale: Vector3) -> Self {
+        let mut matrix = Matrix4::rotation(rotation);
+        for i in 0..3 {
+            matrix.data[i] *= scale.x;
+            matrix.data[4 + i] *= scale.y;
+            matrix.data[8 + i] *= scale.z;
+        }
+        matrix.data[12] = translation.x;
+        matrix.data[13] = translation.y;
+        matrix.data[14] = translation.z;
+
+        matrix
+    }
+
+    // OpenGL style perspective projection with depth in [-1, 1]. `fov_y` is in radians.
+    pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Self {
+        let f = 1.0 / (fov_y * 0.5).tan();
+        let mut matrix = Matrix4 { data: [0.0; 16] };
+
+        matrix.data[0] = f / aspect;
+        matrix.data[5] = f;
+        matrix.data[10] = (far + near) / (near - far);
+        matrix.data[11] = -1.0;
+        matrix.data[14] = (2.0 * far * near) / (near - far);
+
+        matrix
+    }
+
+    pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Self {
+        let mut matrix = Matrix4::identity();
+
+        matrix.data[0] = 2.0 / (right - left);
+        matrix.data[5] = 2.0 / (top - bottom);
+        matrix.data[10] = -2.0 / (far - near);
+        matrix.data[12] = -(right + left) / (right - left);
+        matrix.data[13] = -(top + bottom) / (top - bottom);
+        matrix.data[14] = -(far + near) / (far - near);
+
+        matrix
+    }
+
+    // Right-handed view matrix looking from `eye` towards `target`.
+    pub fn look_at(eye: Vector3, target: Vector3, up: Vector3) -> Self {
+        let forward = (target - eye).normalize();
+        let right = forward.cross(up).normalize();
+        let up = right.cross(forward);
+
+        Matrix4 {
+            data: [
+                right.x, up.x, -forward.x, 0.0,
+                right.y, up.y, -forward.y, 0.0,
+                right.z, up.z, -forward.z, 0.0,
+                -right.dot(eye), -up.dot(eye), forward.dot(eye), 1.0,
+            ],
+        }
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> f32 {
+        self.data[column * 4 + row]
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        let mut matrix = Matrix4 { data: [0.0; 16] };
+        for row in 0..4 {
+            for column in 0..4 {
+                matrix.data[row * 4 + column] = self.data[column * 4 + row];
+            }
+        }
+
+        matrix
+    }
+
+    // Returns `None` for matrices that can't be inverted.
+    pub fn inverse(&self) -> Option<Matrix4> {
+        let m = &self.data;
+        let mut inv = [0.0f32; 16];
+
+        inv[0] = m[5] * m[10] * m[15] - m[5] * m[11] * m[14] - m[9] * m[6] * m[15]
+            + m[9] * m[7] * m[14] + m[13] * m[6] * m[11] - m[13] * m[7] * m[10];
+        inv[4] = -m[4] * m[10] * m[15] + m[4] * m[11] * m[14] + m[8] * m[6] * m[15]
+            - m[8] * m[7] * m[14] - m[12] * m[6] * m[11] + m[12] * m[7] * m[10];
+        inv[8] = m[4] * m[9] * m[15] - m[4] * m[11] * m[13] - m[8] * m[5] * m[15]
+            + m[8] * m[7] * m[13] + m[12] * m[5] * m[11] - m[12] * m[7] * m[9];
+        inv[12] = -m[4] * m[9] * m[14] + m[4] * m[10] * m[13] + m[8] * m[5] * m[14]
+            - m[8] * m[6] * m[13] - m[12] * m[5] * m[10] + m[12] * m[6] * m[9];
+        inv[1] = -m[1] * m[10] * m[15] + m[1] * m[11] * m[14] + m[9] * m[2] * m[15]
+            - m[9] * m[3] * m[14] - m[13] * m[2] * m[11] + m[13] * m[3] * m[10];
+        inv[5] = m[0] * m[10] * m[15] - m[0] * m[11] * m[14] - m[8] * m[2] * m[15]
+            + m[8] * m[3] * m[14] + m[12] * m[2] * m[11] - m[12] * m[3] * m[10];
+        inv[9] = -m[0] * m[9] * m[15] + m[0] * m[11] * m[13] + m[8] * m[1] * m[15]
+            - m[8] * m[3] * m[13] - m[12] * m[1] * m[11] + m[12] * m[3] * m[9];
+        inv[13] = m[0] * m[9] * m[14] - m[0] * m[10] * m[13] - m[8] * m[1] * m[14]
+            + m[8] * m[2] * m[13] + m[12] * m[1] * m[10] - m[12] * m[2] * m[9];
+        inv[2] = m[1] * m[6] * m[15] - m[1] * m[7] * m[14] - m[5] * m[2] * m[15]
+            + m[5] * m[3] * m[14] + m[13] * m[2] * m[7] - m[13] * m[3] * m[6];
+        inv[6] = -m[0] * m[6] * m[15] + m[0] * m[7] * m[14] + m[4] * m[2] * m[15]
+            - m[4] * m[3] * m[14] - m[12] * m[2] * m[7] + m[12] * m[3] * m[6];
+        inv[10] = m[0] * m[5] * m[15] - m[0] * m[7] * m[13] - m[4] * m[1] * m[15]
+            + m[4] * m[3] * m[13] + m[12] * m[1] * m[7] - m[12] * m[3] * m[5];
+        inv[14] = -m[0] * m[5] * m[14] + m[0] * m[6] * m[13] + m[4] * m[1] * m[14]
+            - m[4] * m[2] * m[13] - m[12] * m[1] * m[6] + m[12] * m[2] * m[5];
+        inv[3] = -m[1] * m[6] * m[11] + m[1] * m[7] * m[10] + m[5] * m[2] * m[11]
+            - m[5] * m[3] * m[10] - m[9] * m[2] * m[7] + m[9] * m[3] * m[6];
+        inv[7] = m[0] * m[6] * m[11] - m[0] * m[7] * m[10] - m[4] * m[2] * m[11]
+            + m[4] * m[3] * m[10] + m[8] * m[2] * m[7] - m[8] * m[3] * m[6];
+        inv[11] = -m[0] * m[5] * m[11] + m[0] * m[7] * m[9] + m[4] * m[1] * m[11]
+            - m[4] * m[3] * m[9] - m[8] * m[1] * m[7] + m[8] * m[3] * m[5];
+        inv[15] = m[0] * m[5] * m[10] - m[0] * m[6] * m[9] - m[4] * m[1] * m[10]
+            + m[4] * m[2] * m[9] + m[8] * m[1] * m[6] - m[8] * m[2] * m[5];
+
+        // Same as in `Matrix3::normal_matrix`, small determinants are fine.
+        let determinant = m[0] * inv[0] + m[1] * inv[4] + m[2] * inv[8] + m[3] * inv[12];
+        let inv_determinant = 1.0 / determinant;
+        if determinant == 0.0 || !inv_determinant.is_finite() {
+            return None;
+        }
+
+        for value in inv.iter_mut() {
+            *value *= inv_determinant;
+        }
+
+        Some(Matrix4 { data: inv })
+    }
+
+    // Transforms a point (w = 1), dividing by w if the matrix is a projection.
+    pub fn transform_point(&self, point: Vector3) -> Vector3 {
+        let m = &self.data;
+        let x = m[0] * point.x + m[4] * point.y + m[8] * point.z + m[12];
+        let y = m[1] * point.x + m[5] * point.y + m[9] * point.z + m[13];
+        let z = m[2] * point.x + m[6] * point.y + m[10] * point.z + m[14];
+        let w = m[3] * point.x + m[7] * point.y + m[11] * point.z + m[15];
+
+        if w != 0.0 && w != 1.0 {
+            Vector3::new(x / w, y / w, z / w)
+        } else {
+            Vector3::new(x, y, z)
+        }
+    }
+
+    // Transforms a direction (w = 0), ignoring translation.
+    pub fn transform_vector(&self, vector: Vector3) -> Vector3 {
+        let m = &self.data;
+
+        Vector3::new(
+            m[0] * vector.x + m[4] * vector.y + m[8] * vector.z,
+            m[1] * vector.x + m[5] * vector.y + m[9] * vector.z,
+            m[2] * vector.x + m[6] * vector.y + m[10] * vector.z,
+        )
+    }
+
+    pub fn get_translation(&self) -> Vector3 {
+        Vector3::new(self.data[12], self.data[13], self.data[14])
+    }
+
+    pub fn as_ptr(&self) -> *const f32 {
+        self.data.as_ptr()
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, rhs: Matrix4) -> Matrix4 {
+        let mut matrix = Matrix4 { data: [0.0; 16] };
+        for column in 0..4 {
+            for row in 0..4 {
+                let mut sum = 0.0;
+                for k in 0..4 {
+                    sum += self.data[k * 4 + row] * rhs.data[column * 4 + k];
+                }
+                matrix.data[column * 4 + row] = sum;
+            }
+        }
+
+        matrix
+    }
+}
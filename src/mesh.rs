@@ -0,0 +1,329 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use crate::math::Vector3;
+use crate::render::Vertex;
+
+// Generators for common shapes, ready for `Renderer::create_object`:
+// let (vertices, indices) = mesh::uv_sphere(1.0, 32, 16);
+// renderer.create_object(Some(vertices), Some(indices), material);
+//
+// Every shape is centered on the origin with +Y up, has counter-clockwise front faces (so back face culling works),
+// normals pointing outwards and tex_coords from (0, 0) to (1, 1). Vertex colors are white.
+// Segment counts below their minimum are raised to it.
+
+pub fn vertex(position: Vector3, normal: Vector3, tex_coords: (f32, f32)) -> Vertex {
+    Vertex {
+        position: position.into(),
+        color: (1.0, 1.0, 1.0, 1.0),
+        tex_coords,
+        normals: normal.into(),
+        texture_id: 0.0,
+    }
+}
+
+// A rectangle in the XY plane, facing +Z.
+pub fn quad(width: f32, height: f32) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices = vec![];
+    let mut indices = vec![];
+
+    add_face(&mut vertices, &mut indices, Vector3::zero(),
+        Vector3::new(width * 0.5, 0.0, 0.0), Vector3::new(0.0, height * 0.5, 0.0));
+
+    (vertices, indices)
+}
+
+// A cube with 4 vertices per side, so every side has its own normal and the whole texture.
+pub fn cube(size: f32) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices = vec![];
+    let mut indices = vec![];
+    let half = size * 0.5;
+
+    // (normal, u axis, v axis), always with u x v = normal.
+    let sides = [
+        (Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0), Vector3::new(0.0, 1.0, 0.0)),
+        (Vector3::new(-1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.0, 1.0, 0.0)),
+        (Vector3::new(0.0, 1.0, 0.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0)),
+        (Vector3::new(0.0, -1.0, 0.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0)),
+        (Vector3::new(0.0, 0.0, 1.0), Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0)),
+        (Vector3::new(0.0, 0.0, -1.0), Vector3::new(-1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0)),
+    ];
+    for (normal, u, v) in sides.iter() {
+        add_face(&mut vertices, &mut indices, *normal * half, *u * half, *v * half);
+    }
+
+    (vertices, indices)
+}
+
+// A flat grid in the XZ plane, facing +Y, split into `subdivisions_x` by `subdivisions_z` cells (at least 1 each).
+pub fn plane(width: f32, depth: f32, subdivisions_x: u32, subdivisions_z: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices = vec![];
+    let mut indices = vec![];
+
+    add_surface(&mut vertices, &mut indices, subdivisions_x.max(1), &steps(subdivisions_z.max(1)), |u, v| {
+        (Vector3::new((u - 0.5) * width, 0.0, (0.5 - v) * depth), Vector3::up())
+    });
+
+    (vertices, indices)
+}
+
+// A sphere made of `segments` slices around the Y axis (at least 3) and `rings` stacks from pole to pole (at least 2).
+// The texture is wrapped around it like a world map.
+pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices = vec![];
+    let mut indices = vec![];
+
+    add_surface(&mut vertices, &mut indices, segments.max(3), &steps(rings.max(2)), |u, v| {
+        let normal = sphere_normal(u, v * PI);
+        (normal * radius, normal)
+    });
+
+    (vertices, indices)
+}
+
+// A sphere made of (nearly) equally sized triangles, starting from an icosahedron that is subdivided
+// `subdivisions` times. Every subdivision quadruples the triangle count (20 * 4^n).
+pub fn icosphere(radius: f32, subdivisions: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let t = (1.0 + 5f32.sqrt()) * 0.5;
+    let mut positions = vec![
+        Vector3::new(-1.0, t, 0.0), Vector3::new(1.0, t, 0.0), Vector3::new(-1.0, -t, 0.0), Vector3::new(1.0, -t, 0.0),
+        Vector3::new(0.0, -1.0, t), Vector3::new(0.0, 1.0, t), Vector3::new(0.0, -1.0, -t), Vector3::new(0.0, 1.0, -t),
+        Vector3::new(t, 0.0, -1.0), Vector3::new(t, 0.0, 1.0), Vector3::new(-t, 0.0, -1.0), Vector3::new(-t, 0.0, 1.0),
+    ].into_iter().map(|x| x.normalize()).collect::<Vec<_>>();
+
+    let mut triangles: Vec<[u32; 3]> = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        // Edges are shared by two triangles, so their midpoints are only created once.
+        let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut midpoint = |a: u32, b: u32, positions: &mut Vec<Vector3>| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                positions.push(((positions[a as usize] + positions[b as usize]) * 0.5).normalize());
+                positions.len() as u32 - 1
+            })
+        };
+
+        triangles = triangles.iter().flat_map(|[a, b, c]| {
+            let ab = midpoint(*a, *b, &mut positions);
+            let bc = midpoint(*b, *c, &mut positions);
+            let ca = midpoint(*c, *a, &mut positions);
+            vec![[*a, ab, ca], [*b, bc, ab], [*c, ca, bc], [ab, bc, ca]]
+        }).collect();
+    }
+
+    let tex_coords = |p: &Vector3| (0.5 + p.x.atan2(p.z) / (2.0 * PI), 0.5 + p.y.asin() / PI);
+    let mut vertices = positions.iter().map(|p| vertex(*p * radius, *p, tex_coords(p))).collect::<Vec<_>>();
+
+    // Triangles crossing the seam at the back (where u wraps from 1 back to 0) would stretch the whole texture
+    // across themselves, so they get copies of their vertices with u past 1.
+    let mut wrapped: HashMap<u32, u32> = HashMap::new();
+    for triangle in triangles.iter_mut() {
+        let us = triangle.iter().map(|x| vertices[*x as usize].tex_coords.0).collect::<Vec<_>>();
+        let max = us.iter().cloned().fold(f32::MIN, f32::max);
+        let min = us.iter().cloned().fold(f32::MAX, f32::min);
+        if max - min < 0.5 {
+            continue;
+        }
+
+        for index in triangle.iter_mut() {
+            if vertices[*index as usize].tex_coords.0 < 0.5 {
+                *index = *wrapped.entry(*index).or_insert_with(|| {
+                    let mut copy = vertices[*index as usize];
+                    copy.tex_coords.0 += 1.0;
+                    vertices.push(copy);
+                    vertices.len() as u32 - 1
+                });
+            }
+        }
+    }
+
+    // Any u fits a vertex on a pole, so every triangle touching one gets its own copy centered between the others.
+    for triangle in triangles.iter_mut() {
+        let pole = match triangle.iter().position(|x| vertices[*x as usize].normals.1.abs() > 1.0 - 1e-6) {
+            Some(pole) => pole,
+            None => continue,
+        };
+
+        let others = (triangle[(pole + 1) % 3] as usize, triangle[(pole + 2) % 3] as usize);
+        let mut copy = vertices[triangle[pole] as usize];
+        copy.tex_coords.0 = (vertices[others.0].tex_coords.0 + vertices[others.1].tex_coords.0) * 0.5;
+        vertices.push(copy);
+        triangle[pole] = vertices.len() as u32 - 1;
+    }
+
+    (vertices, triangles.concat())
+}
+
+// A cylinder along the Y axis with `segments` sides (at least 3) and closed caps.
+pub fn cylinder(radius: f32, height: f32, segments: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices = vec![];
+    let mut indices = vec![];
+    let segments = segments.max(3);
+
+    add_surface(&mut vertices, &mut indices, segments, &steps(1), |u, v| {
+        let normal = sphere_normal(u, PI * 0.5);
+        (normal * radius + Vector3::new(0.0, (v - 0.5) * height, 0.0), normal)
+    });
+    add_cap(&mut vertices, &mut indices, radius, height * 0.5, segments, true);
+    add_cap(&mut vertices, &mut indices, radius, -height * 0.5, segments, false);
+
+    (vertices, indices)
+}
+
+// A cone along the Y axis with its tip at the top, `segments` sides (at least 3) and a closed base.
+pub fn cone(radius: f32, height: f32, segments: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices = vec![];
+    let mut indices = vec![];
+    let segments = segments.max(3);
+
+    // The side normals lean upwards by the slope of the cone.
+    let slope = radius / height;
+    add_surface(&mut vertices, &mut indices, segments, &steps(1), |u, v| {
+        let outwards = sphere_normal(u, PI * 0.5);
+        let normal = Vector3::new(outwards.x, slope, outwards.z).normalize();
+        (outwards * (radius * (1.0 - v)) + Vector3::new(0.0, (v - 0.5) * height, 0.0), normal)
+    });
+    add_cap(&mut vertices, &mut indices, radius, -height * 0.5, segments, false);
+
+    (vertices, indices)
+}
+
+// A ring around the Y axis. `major_radius` is the distance from the center to the middle of the tube,
+// `minor_radius` the radius of the tube itself. Both segment counts are at least 3.
+pub fn torus(major_radius: f32, minor_radius: f32, major_segments: u32, minor_segments: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices = vec![];
+    let mut indices = vec![];
+
+    add_surface(&mut vertices, &mut indices, major_segments.max(3), &steps(minor_segments.max(3)), |u, v| {
+        let outwards = sphere_normal(u, PI * 0.5);
+        // Goes around the tube starting at the outside, then over the top.
+        let (sin, cos) = turn(v);
+        let normal = outwards * cos + Vector3::up() * sin;
+        (outwards * major_radius + normal * minor_radius, normal)
+    });
+
+    (vertices, indices)
+}
+
+// A cylinder with half spheres as caps, like a pill. `height` is the length of the cylinder part,
+// so the whole capsule is `height + 2 * radius` tall. `rings` is the number of stacks in each half sphere (at least 1).
+pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let mut vertices = vec![];
+    let mut indices = vec![];
+    let rings = rings.max(1);
+
+    // The texture is spread along the outline of the capsule, so every part of it is stretched the same.
+    let hemisphere = PI * 0.5 * radius;
+    let length = hemisphere * 2.0 + height;
+    let mut rows = (0..=rings).map(|i| i as f32 / rings as f32 * hemisphere / length).collect::<Vec<_>>();
+    rows.extend((0..=rings).map(|i| (hemisphere + height + i as f32 / rings as f32 * hemisphere) / length));
+
+    add_surface(&mut vertices, &mut indices, segments.max(3), &rows, |u, v| {
+        let distance = v * length;
+        let (angle, offset) = if distance <= hemisphere {
+            (distance / radius, -height * 0.5)
+        } else if distance < hemisphere + height {
+            (PI * 0.5, distance - hemisphere - height * 0.5)
+        } else {
+            (PI * 0.5 + (distance - hemisphere - height) / radius, height * 0.5)
+        };
+
+        let normal = sphere_normal(u, angle);
+        (normal * radius + Vector3::new(0.0, offset, 0.0), normal)
+    });
+
+    (vertices, indices)
+}
+
+// `count + 1` evenly spaced values from 0 to 1.
+fn steps(count: u32) -> Vec<f32> {
+    (0..=count).map(|i| i as f32 / count as f32).collect()
+}
+
+// The direction to a point on the unit sphere. `u` goes around the Y axis (0 and 1 at +Z, increasing towards +X)
+// and `angle` goes from the bottom pole (0) to the top pole (PI).
+fn sphere_normal(u: f32, angle: f32) -> Vector3 {
+    let (sin_u, cos_u) = turn(u);
+    let (sin_angle, cos_angle) = angle.sin_cos();
+    // sin(PI) isn't exactly 0, but all vertices on a pole should have the same position.
+    let sin_angle = if angle >= PI { 0.0 } else { sin_angle };
+
+    Vector3::new(sin_angle * sin_u, -cos_angle, sin_angle * cos_u)
+}
+
+// The sine and cosine of a fraction of a full turn. sin(2 * PI) isn't exactly 0 either, but the first and last
+// column of a closed surface should be in the same place, so that seam vertices can be matched up by position.
+fn turn(fraction: f32) -> (f32, f32) {
+    if fraction >= 1.0 {
+        return (0.0, 1.0);
+    }
+
+    (fraction * 2.0 * PI).sin_cos()
+}
+
+// A grid of `columns` cells along `u` and one row of cells between each value in `rows` along `v`.
+// `surface(u, v)` returns the position and normal; the surface has to turn counter-clockwise from u to v
+// when seen from the front. The first and last column are separate vertices so the texture doesn't wrap back.
+fn add_surface<F>(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, columns: u32, rows: &[f32], surface: F)
+    where F: Fn(f32, f32) -> (Vector3, Vector3) {
+    let start = vertices.len() as u32;
+
+    for v in rows.iter() {
+        for column in 0..=columns {
+            let u = column as f32 / columns as f32;
+            let (position, normal) = surface(u, *v);
+            vertices.push(vertex(position, normal, (u, *v)));
+        }
+    }
+
+    for row in 0..rows.len() as u32 - 1 {
+        for column in 0..columns {
+            let a = start + row * (columns + 1) + column;
+            let b = a + columns + 1;
+            indices.extend_from_slice(&[a, a + 1, b + 1, a, b + 1, b]);
+        }
+    }
+}
+
+// A rectangle around `center` spanning `u` and `v` in both directions, facing `u x v`.
+fn add_face(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, center: Vector3, u: Vector3, v: Vector3) {
+    let start = vertices.len() as u32;
+    let normal = u.cross(v).normalize();
+
+    vertices.push(vertex(center - u - v, normal, (0.0, 0.0)));
+    vertices.push(vertex(center + u - v, normal, (1.0, 0.0)));
+    vertices.push(vertex(center + u + v, normal, (1.0, 1.0)));
+    vertices.push(vertex(center - u + v, normal, (0.0, 1.0)));
+
+    indices.extend_from_slice(&[start, start + 1, start + 2, start, start + 2, start + 3]);
+}
+
+// A disc at height `y` facing up (the top of a cylinder) or down (the bottom of a cylinder or cone).
+fn add_cap(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, radius: f32, y: f32, segments: u32, up: bool) {
+    let start = vertices.len() as u32;
+    let normal = if up { Vector3::up() } else { -Vector3::up() };
+    // Seen from outside, the texture is upright with -Z (top) or +Z (bottom) at the top.
+    let flip = if up { -1.0 } else { 1.0 };
+
+    vertices.push(vertex(Vector3::new(0.0, y, 0.0), normal, (0.5, 0.5)));
+    for segment in 0..segments {
+        let outwards = sphere_normal(segment as f32 / segments as f32, PI * 0.5);
+        vertices.push(vertex(outwards * radius + Vector3::new(0.0, y, 0.0), normal,
+            (0.5 + outwards.x * 0.5, 0.5 + outwards.z * 0.5 * flip)));
+    }
+
+    for segment in 0..segments {
+        let current = start + 1 + segment;
+        let next = start + 1 + (segment + 1) % segments;
+        if up {
+            indices.extend_from_slice(&[start, current, next]);
+        } else {
+            indices.extend_from_slice(&[start, next, current]);
+        }
+    }
+}
@@ -0,0 +1,470 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::math::Vector3;
+use crate::render::Vertex;
+
+// Utilities working on vertex/index data before it is given to `Renderer::create_object`.
+// All of them expect indexed triangle lists (`PrimitiveMode::Triangles`).
+
+fn position(vertices: &[Vertex], index: u32) -> Vector3 {
+    Vector3::from(vertices[index as usize].position)
+}
+
+fn position_key(vertex: &Vertex) -> [u32; 3] {
+    let (x, y, z) = vertex.position;
+    // Adding 0.0 turns -0.0 into 0.0, which have different bits.
+    [(x + 0.0).to_bits(), (y + 0.0).to_bits(), (z + 0.0).to_bits()]
+}
+
+// Sets every normal to the average of the normals of the triangles around it, weighted by their area.
+// Vertices with the same position share their normal even when they are separate vertices
+// (like at texture seams), so the seams don't show in the lighting.
+pub fn compute_smooth_normals(vertices: &mut [Vertex], indices: &[u32]) {
+    let mut sums: HashMap<[u32; 3], Vector3> = HashMap::new();
+
+    for triangle in indices.chunks_exact(3) {
+        let (a, b, c) = (position(vertices, triangle[0]), position(vertices, triangle[1]), position(vertices, triangle[2]));
+        // The length of the cross product is twice the area, which is the weight.
+        let normal = (b - a).cross(c - a);
+        for index in triangle {
+            let sum = sums.entry(position_key(&vertices[*index as usize])).or_insert_with(Vector3::zero);
+            *sum = *sum + normal;
+        }
+    }
+
+    for vertex in vertices.iter_mut() {
+        // Vertices only used by degenerate triangles keep their normal.
+        match sums.get(&position_key(vertex)) {
+            Some(sum) if *sum != Vector3::zero() => vertex.normals = sum.normalize().into(),
+            _ => {}
+        }
+    }
+}
+
+// Gives every triangle its own three vertices with the triangle's normal, for a faceted look.
+// Returns the new vertices and indices.
+pub fn compute_flat_normals(vertices: &[Vertex], indices: &[u32]) -> (Vec<Vertex>, Vec<u32>) {
+    let mut flat_vertices = Vec::with_capacity(indices.len());
+
+    for triangle in indices.chunks_exact(3) {
+        let (a, b, c) = (position(vertices, triangle[0]), position(vertices, triangle[1]), position(vertices, triangle[2]));
+        let normal = (b - a).cross(c - a).normalize();
+        for index in triangle {
+            let mut vertex = vertices[*index as usize];
+            vertex.normals = normal.into();
+            flat_vertices.push(vertex);
+        }
+    }
+
+    let flat_indices = (0..flat_vertices.len() as u32).collect();
+    (flat_vertices, flat_indices)
+}
+
+// Computes a tangent for every vertex for normal mapping: the tangents of the triangles around it (the direction
+// of increasing u) are summed, weighted by their angle at the vertex, and made perpendicular to the normal.
+// This is not MikkTSpace, which also splits and groups vertices, so normal maps baked with it can look slightly
+// off where their tangents differ.
+// The fourth component is the handedness (1.0 or -1.0) of the bitangent:
+// bitangent = cross(normal, tangent.xyz) * tangent.w
+//
+// `Vertex` has no tangent attribute, so they are returned index-aligned with `vertices`. Give them to an object
+// with `Renderer::set_object_tangents`, which puts them in their own buffer as attribute 7.
+// Normals have to be set first. Vertices where the texture is mirrored should not be shared between the mirrored halves.
+pub fn compute_tangents(vertices: &[Vertex], indices: &[u32]) -> Vec<[f32; 4]> {
+    let mut tangents = vec![Vector3::zero(); vertices.len()];
+    let mut bitangents = vec![Vector3::zero(); vertices.len()];
+
+    for triangle in indices.chunks_exact(3) {
+        for corner in 0..3 {
+            let index = triangle[corner] as usize;
+            let next = triangle[(corner + 1) % 3] as usize;
+            let previous = triangle[(corner + 2) % 3] as usize;
+
+            let p = Vector3::from(vertices[index].position);
+            let edge1 = Vector3::from(vertices[next].position) - p;
+            let edge2 = Vector3::from(vertices[previous].position) - p;
+            let uv = vertices[index].tex_coords;
+            let (du1, dv1) = (vertices[next].tex_coords.0 - uv.0, vertices[next].tex_coords.1 - uv.1);
+            let (du2, dv2) = (vertices[previous].tex_coords.0 - uv.0, vertices[previous].tex_coords.1 - uv.1);
+
+            let determinant = du1 * dv2 - du2 * dv1;
+            if determinant.abs() <= f32::EPSILON {
+                continue;
+            }
+
+            let tangent = (edge1 * dv2 - edge2 * dv1) * (1.0 / determinant);
+            let bitangent = (edge2 * du1 - edge1 * du2) * (1.0 / determinant);
+
+            let angle = edge1.normalize().dot(edge2.normalize()).clamp(-1.0, 1.0).acos();
+            tangents[index] = tangents[index] + tangent.normalize() * angle;
+            bitangents[index] = bitangents[index] + bitangent.normalize() * angle;
+        }
+    }
+
+    vertices.iter().enumerate().map(|(i, vertex)| {
+        let normal = Vector3::from(vertex.normals);
+        let mut tangent = (tangents[i] - normal * normal.dot(tangents[i])).normalize();
+        if tangent == Vector3::zero() {
+            // Without usable texture coordinates any direction perpendicular to the normal will do.
+            let axis = if normal.x.abs() < 0.9 { Vector3::new(1.0, 0.0, 0.0) } else { Vector3::up() };
+            tangent = (axis - normal * normal.dot(axis)).normalize();
+        }
+        let handedness = if normal.cross(tangent).dot(bitangents[i]) < 0.0 { -1.0 } else { 1.0 };
+
+        [tangent.x, tangent.y, tangent.z, handedness]
+    }).collect()
+}
+
+// Merges vertices whose attributes are all within `epsilon` of each other (0.0 only merges exact copies).
+// Attributes are compared by snapping them to a grid of `epsilon`, so two values closer than `epsilon`
+// can still end up in neighbouring cells.
+// Returns the remaining vertices (in the order they are first used) and the new indices.
+pub fn weld_vertices(vertices: &[Vertex], indices: &[u32], epsilon: f32) -> (Vec<Vertex>, Vec<u32>) {
+    let quantize = |value: f32| if epsilon > 0.0 { (value / epsilon).round() as i64 } else { value.to_bits() as i64 };
+
+    let mut welded = vec![];
+    let mut lookup: HashMap<[i64; 13], u32> = HashMap::new();
+    let mut remap: Vec<Option<u32>> = vec![None; vertices.len()];
+
+    let new_indices = indices.iter().map(|index| {
+        *remap[*index as usize].get_or_insert_with(|| {
+            let v = vertices[*index as usize];
+            let key = [
+                quantize(v.position.0), quantize(v.position.1), quantize(v.position.2),
+                quantize(v.color.0), quantize(v.color.1), quantize(v.color.2), quantize(v.color.3),
+                quantize(v.tex_coords.0), quantize(v.tex_coords.1),
+                quantize(v.normals.0), quantize(v.normals.1), quantize(v.normals.2),
+                // Different textures never merge.
+                v.texture_id.to_bits() as i64,
+            ];
+            *lookup.entry(key).or_insert_with(|| {
+                welded.push(v);
+                welded.len() as u32 - 1
+            })
+        })
+    }).collect();
+
+    (welded, new_indices)
+}
+
+// Reorders the vertices in the order the indices first use them and drops unused ones, so the GPU reads the
+// vertex buffer front to back. Best done after `optimize_vertex_cache`.
+pub fn optimize_vertex_fetch(vertices: &[Vertex], indices: &[u32]) -> (Vec<Vertex>, Vec<u32>) {
+    let mut reordered = vec![];
+    let mut remap: Vec<Option<u32>> = vec![None; vertices.len()];
+
+    let new_indices = indices.iter().map(|index| {
+        *remap[*index as usize].get_or_insert_with(|| {
+            reordered.push(vertices[*index as usize]);
+            reordered.len() as u32 - 1
+        })
+    }).collect();
+
+    (reordered, new_indices)
+}
+
+static CACHE_SIZE: usize = 32;
+
+// Reorders the triangles so vertices are reused while they are still in the GPU's post-transform cache
+// (Tom Forsyth's "Linear-Speed Vertex Cache Optimisation"). The result draws the same triangles.
+pub fn optimize_vertex_cache(indices: &[u32], vertex_count: usize) -> Vec<u32> {
+    let triangle_count = indices.len() / 3;
+
+    let mut vertex_triangles: Vec<Vec<usize>> = vec![vec![]; vertex_count];
+    for (triangle, chunk) in indices.chunks_exact(3).enumerate() {
+        for index in chunk {
+            vertex_triangles[*index as usize].push(triangle);
+        }
+    }
+
+    let mut remaining = vertex_triangles.iter().map(|x| x.len()).collect::<Vec<_>>();
+    let mut cache_position: Vec<Option<usize>> = vec![None; vertex_count];
+    let mut vertex_scores = (0..vertex_count).map(|x| cache_score(None, remaining[x])).collect::<Vec<_>>();
+    let triangle_score = |triangle: usize, vertex_scores: &[f32]| {
+        indices[triangle * 3..triangle * 3 + 3].iter().map(|x| vertex_scores[*x as usize]).sum::<f32>()
+    };
+    let mut triangle_scores = (0..triangle_count).map(|x| triangle_score(x, &vertex_scores)).collect::<Vec<_>>();
+    let mut emitted = vec![false; triangle_count];
+
+    let mut result = Vec::with_capacity(triangle_count * 3);
+    let mut cache: Vec<u32> = vec![];
+    let mut next_unemitted = 0;
+
+    for _ in 0..triangle_count {
+        // The best triangle using a vertex in the cache, or the next one in the original order when none does.
+        let best = cache.iter()
+            .flat_map(|x| vertex_triangles[*x as usize].iter())
+            .filter(|x| !emitted[**x])
+            .max_by(|a, b| triangle_scores[**a].partial_cmp(&triangle_scores[**b]).unwrap_or(Ordering::Equal))
+            .copied()
+            .unwrap_or_else(|| {
+                while emitted[next_unemitted] {
+                    next_unemitted += 1;
+                }
+                next_unemitted
+            });
+
+        emitted[best] = true;
+        let triangle = &indices[best * 3..best * 3 + 3];
+        result.extend_from_slice(triangle);
+
+        // Used vertices move to the front of the cache.
+        let mut new_cache = triangle.to_vec();
+        for index in triangle {
+            remaining[*index as usize] -= 1;
+        }
+        new_cache.extend(cache.iter().filter(|x| !triangle.contains(x)));
+
+        for index in new_cache.iter().skip(CACHE_SIZE) {
+            cache_position[*index as usize] = None;
+        }
+        new_cache.truncate(CACHE_SIZE + 3);
+
+        let mut changed = vec![];
+        for (position, index) in new_cache.iter().enumerate() {
+            let index = *index as usize;
+            cache_position[index] = if position < CACHE_SIZE { Some(position) } else { None };
+            vertex_scores[index] = cache_score(cache_position[index], remaining[index]);
+            changed.push(index);
+        }
+        new_cache.truncate(CACHE_SIZE);
+        cache = new_cache;
+
+        for index in changed {
+            for triangle in vertex_triangles[index].iter() {
+                if !emitted[*triangle] {
+                    triangle_scores[*triangle] = triangle_score(*triangle, &vertex_scores);
+                }
+            }
+        }
+    }
+
+    result
+}
+
+fn cache_score(cache_position: Option<usize>, remaining_triangles: usize) -> f32 {
+    if remaining_triangles == 0 {
+        return -1.0;
+    }
+
+    let cache = match cache_position {
+        // The last triangle's vertices get a fixed score so the next triangle doesn't just reuse them.
+        Some(position) if position < 3 => 0.75,
+        Some(position) => (1.0 - (position - 3) as f32 / (CACHE_SIZE - 3) as f32).powf(1.5),
+        None => 0.0,
+    };
+    // Vertices with few triangles left are finished first, so they can leave the cache.
+    cache + 2.0 * (remaining_triangles as f32).powf(-0.5)
+}
+
+// The average number of vertices that have to be transformed per triangle with a FIFO cache of `cache_size`
+// vertices. Between 0.5 (ideal) and 3.0 (no reuse at all); useful to check `optimize_vertex_cache`.
+pub fn average_cache_miss_ratio(indices: &[u32], cache_size: usize) -> f32 {
+    if indices.len() < 3 {
+        return 0.0;
+    }
+
+    let mut cache: std::collections::VecDeque<u32> = std::collections::VecDeque::with_capacity(cache_size);
+    let mut misses = 0;
+    for index in indices {
+        if !cache.contains(index) {
+            misses += 1;
+            if cache.len() == cache_size {
+                cache.pop_front();
+            }
+            cache.push_back(*index);
+        }
+    }
+
+    misses as f32 / (indices.len() / 3) as f32
+}
+
+// A quadric error metric: the sum of squared distances to a set of planes.
+#[derive(Copy, Clone, Default)]
+struct Quadric {
+    // The upper triangle of the symmetric 4x4 matrix.
+    data: [f64; 10],
+}
+
+impl Quadric {
+    fn from_plane(normal: Vector3, distance: f32, weight: f32) -> Quadric {
+        let (a, b, c, d) = (normal.x as f64, normal.y as f64, normal.z as f64, distance as f64);
+        let w = weight as f64;
+
+        Quadric { data: [a * a * w, a * b * w, a * c * w, a * d * w, b * b * w, b * c * w, b * d * w, c * c * w, c * d * w, d * d * w] }
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (a, b) in self.data.iter_mut().zip(other.data.iter()) {
+            *a += b;
+        }
+    }
+
+    fn error(&self, point: Vector3) -> f64 {
+        let (x, y, z) = (point.x as f64, point.y as f64, point.z as f64);
+        let q = &self.data;
+
+        q[0] * x * x + 2.0 * q[1] * x * y + 2.0 * q[2] * x * z + 2.0 * q[3] * x
+            + q[4] * y * y + 2.0 * q[5] * y * z + 2.0 * q[6] * y
+            + q[7] * z * z + 2.0 * q[8] * z
+            + q[9]
+    }
+}
+
+// A possible collapse of the vertex `from` into `to`, with the versions of both vertices when it was found.
+#[derive(PartialEq)]
+struct Collapse {
+    error: f64,
+    from: u32,
+    to: u32,
+    versions: (u32, u32),
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    // Reversed, so the `BinaryHeap` returns the cheapest collapse first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.error.partial_cmp(&self.error).unwrap_or(Ordering::Equal)
+    }
+}
+
+// Reduces the mesh to at most `target_index_count` indices (or as close as it gets) by collapsing edges
+// where it changes the shape the least (quadric error metrics).
+// Vertices are only moved onto existing vertices, so `vertices` stays valid and only new indices are returned.
+// Vertices on borders (including texture seams, where vertices are split) are kept in place so
+// no holes open up. Triangles are never flipped.
+pub fn simplify(vertices: &[Vertex], indices: &[u32], target_index_count: usize) -> Vec<u32> {
+    let mut triangles = indices.chunks_exact(3).map(|x| [x[0], x[1], x[2]]).collect::<Vec<_>>();
+    let mut live = vec![true; triangles.len()];
+    let mut live_count = triangles.len();
+    let target_triangles = target_index_count / 3;
+
+    let mut quadrics = vec![Quadric::default(); vertices.len()];
+    let mut vertex_triangles: Vec<Vec<usize>> = vec![vec![]; vertices.len()];
+    let mut edge_uses: HashMap<(u32, u32), u32> = HashMap::new();
+
+    for (i, [a, b, c]) in triangles.iter().enumerate() {
+        let (pa, pb, pc) = (position(vertices, *a), position(vertices, *b), position(vertices, *c));
+        let cross = (pb - pa).cross(pc - pa);
+        let normal = cross.normalize();
+        let quadric = Quadric::from_plane(normal, -normal.dot(pa), cross.length() * 0.5);
+
+        for (index, next) in [(*a, *b), (*b, *c), (*c, *a)].iter() {
+            quadrics[*index as usize].add(&quadric);
+            vertex_triangles[*index as usize].push(i);
+            *edge_uses.entry((*index.min(next), *index.max(next))).or_insert(0) += 1;
+        }
+    }
+
+    let mut locked = vec![false; vertices.len()];
+    for ((a, b), uses) in edge_uses.iter() {
+        if *uses == 1 {
+            locked[*a as usize] = true;
+            locked[*b as usize] = true;
+        }
+    }
+
+    let mut versions = vec![0u32; vertices.len()];
+    let mut heap = BinaryHeap::new();
+    let push = |heap: &mut BinaryHeap<Collapse>, quadrics: &[Quadric], versions: &[u32], from: u32, to: u32| {
+        let mut quadric = quadrics[from as usize];
+        quadric.add(&quadrics[to as usize]);
+        heap.push(Collapse {
+            error: quadric.error(position(vertices, to)),
+            from,
+            to,
+            versions: (versions[from as usize], versions[to as usize]),
+        });
+    };
+
+    for (a, b) in edge_uses.keys() {
+        if !locked[*a as usize] {
+            push(&mut heap, &quadrics, &versions, *a, *b);
+        }
+        if !locked[*b as usize] {
+            push(&mut heap, &quadrics, &versions, *b, *a);
+        }
+    }
+
+    while live_count > target_triangles {
+        let collapse = match heap.pop() {
+            Some(collapse) => collapse,
+            None => break,
+        };
+        let (from, to) = (collapse.from as usize, collapse.to as usize);
+        if collapse.versions != (versions[from], versions[to]) {
+            continue;
+        }
+
+        let around = vertex_triangles[from].iter().copied().filter(|x| live[*x]).collect::<Vec<_>>();
+        if !around.iter().any(|x| triangles[*x].contains(&collapse.to)) {
+            continue;
+        }
+
+        // Moving `from` must not flip, squash or sharply turn any triangle that stays.
+        let flips = around.iter().filter(|x| !triangles[**x].contains(&collapse.to)).any(|x| {
+            let triangle = triangles[*x];
+            let moved = triangle.map(|i| if i == collapse.from { collapse.to } else { i });
+            let normal = |t: [u32; 3]| {
+                (position(vertices, t[1]) - position(vertices, t[0])).cross(position(vertices, t[2]) - position(vertices, t[0]))
+            };
+            let (before, after) = (normal(triangle), normal(moved));
+            after.length() <= f32::EPSILON || before.normalize().dot(after.normalize()) < 0.25
+        });
+        if flips {
+            continue;
+        }
+
+        for triangle in around {
+            if triangles[triangle].contains(&collapse.to) {
+                live[triangle] = false;
+                live_count -= 1;
+            } else {
+                for index in triangles[triangle].iter_mut() {
+                    if *index == collapse.from {
+                        *index = collapse.to;
+                    }
+                }
+                vertex_triangles[to].push(triangle);
+            }
+        }
+
+        let quadric = quadrics[from];
+        quadrics[to].add(&quadric);
+        versions[from] += 1;
+        versions[to] += 1;
+
+        let mut neighbours = vertex_triangles[to].iter()
+            .filter(|x| live[**x])
+            .flat_map(|x| triangles[*x].iter().copied())
+            .filter(|x| *x != collapse.to)
+            .collect::<Vec<_>>();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+
+        for neighbour in neighbours {
+            if !locked[neighbour as usize] {
+                push(&mut heap, &quadrics, &versions, neighbour, collapse.to);
+            }
+            if !locked[to] {
+                push(&mut heap, &quadrics, &versions, collapse.to, neighbour);
+            }
+        }
+    }
+
+    triangles.iter().zip(live.iter()).filter(|(_, live)| **live).flat_map(|(x, _)| x.iter().copied()).collect()
+}
+
+// Simplified index lists for levels of detail, one for each ratio of the original index count
+// (0.5 keeps about half the triangles). All of them use the original `vertices`.
+pub fn generate_lods(vertices: &[Vertex], indices: &[u32], ratios: &[f32]) -> Vec<Vec<u32>> {
+    ratios.iter().map(|ratio| simplify(vertices, indices, (indices.len() as f32 * ratio) as usize)).collect()
+}
@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::material::Material;
+use crate::render_state::RenderState;
+use crate::render::{MaterialHandle, Renderer, Vertex};
+use crate::texture::{FilteringType, Texture, TextureData, WrappingType};
+
+// A material from an `.mtl` file. Only the parts that map onto `Vertex` data and textures are kept.
+//
+// Should be exposed to the user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjMaterial {
+    pub name: String,
+    pub ambient_color: (f32, f32, f32),
+    pub diffuse_color: (f32, f32, f32),
+    pub specular_color: (f32, f32, f32),
+    pub shininess: f32,
+    // 1.0 is fully opaque.
+    pub opacity: f32,
+    // Already resolved relative to the `.mtl` file.
+    pub diffuse_texture: Option<String>,
+}
+
+impl Default for ObjMaterial {
+    fn default() -> Self {
+        DEFAULT_MATERIAL.clone()
+    }
+}
+
+impl ObjMaterial {
+    // Creates an instance of `base` (see `create_base_material`) with the diffuse texture at index 0.
+    // Materials without a texture, or whose texture can't be loaded, keep the base material's white one.
+    // Materials that aren't fully opaque get alpha blending.
+    pub fn create_instance(&self, renderer: &mut Renderer, base: MaterialHandle) -> MaterialHandle {
+        let instance = renderer.create_material_instance(base);
+        if let Some(texture) = self.diffuse_texture.as_ref().and_then(|x| load_texture(x)) {
+            renderer.set_instance_texture(instance, 0, texture);
+        }
+        if self.opacity < 1.0 {
+            renderer.set_instance_render_state(instance, RenderState::transparent());
+        }
+
+        instance
+    }
+}
+
+// Compiles the given shaders into the material every `ObjMaterial` is an instance of, with a white texture at index 0.
+pub fn create_base_material(vertex_shader_path: &str, fragment_shader_path: &str) -> Material {
+    let white = Texture::from_rgba(
+        WrappingType::Repeat,
+        WrappingType::Repeat,
+        FilteringType::Nearest,
+        FilteringType::Nearest,
+        TextureData { width: 1, height: 1, data: vec![255, 255, 255, 255] },
+    );
+
+    Material::from_shader_files(vertex_shader_path, fragment_shader_path, vec![white], vec![])
+}
+
+// Images are converted to RGBA, so textures with and without alpha both load correctly.
+fn load_texture(path: &str) -> Option<Texture> {
+    let image = image::open(path).ok()?.into_rgba8();
+
+    Some(Texture::from_rgba(
+        WrappingType::Repeat,
+        WrappingType::Repeat,
+        FilteringType::Linear,
+        FilteringType::Linear,
+        TextureData { width: image.width() as i32, height: image.height() as i32, data: image.into_raw() },
+    ))
+}
+
+// All faces of an `.obj` file that use the same material, ready for `Renderer::create_object`.
+// The vertex color holds the material's diffuse color and opacity.
+//
+// Should be exposed to the user.
+#[derive(Clone)]
+pub struct ObjMesh {
+    // Index into `ObjModel::materials`, or `None` for faces without a (known) material.
+    pub material: Option<usize>,
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+// Should be exposed to the user.
+#[derive(Clone)]
+pub struct ObjModel {
+    pub meshes: Vec<ObjMesh>,
+    pub materials: Vec<ObjMaterial>,
+}
+
+impl ObjModel {
+    // Compiles the shaders into one base material and creates an instance of it per `ObjMaterial`
+    // (faces without a material use the base material) and one object per mesh.
+    // Returns the created objects in the order of `meshes`.
+    pub fn create_objects(&self, renderer: &mut Renderer, vertex_shader_path: &str, fragment_shader_path: &str) -> Vec<u32> {
+        let base = renderer.add_material(create_base_material(vertex_shader_path, fragment_shader_path));
+        let mut handles: HashMap<Option<usize>, MaterialHandle> = HashMap::new();
+        handles.insert(None, base);
+        let mut objects = vec![];
+
+        for mesh in self.meshes.iter() {
+            let material = *handles.entry(mesh.material)
+                .or_insert_with(|| self.materials[mesh.material.unwrap()].create_instance(renderer, base));
+
+            objects.push(renderer.create_object(Some(mesh.vertices.clone()), Some(mesh.indices.clone()), material));
+        }
+
+        objects
+    }
+}
+
+// Used for faces without `usemtl` and as the starting point of every `newmtl`.
+static DEFAULT_MATERIAL: ObjMaterial = ObjMaterial {
+    name: String::new(),
+    ambient_color: (0.0, 0.0, 0.0),
+    diffuse_color: (1.0, 1.0, 1.0),
+    specular_color: (0.0, 0.0, 0.0),
+    shininess: 0.0,
+    opacity: 1.0,
+    diffuse_texture: None,
+};
+
+#[derive(Debug)]
+pub enum ObjError {
+    // The `.obj` file could not be read.
+    FileNotFound { path: String },
+    // A statement that couldn't be understood, e.g. a face referencing a vertex that doesn't exist.
+    Parse { file: String, line: usize, message: String },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::FileNotFound { path } => write!(f, "The path `{}` does not exist!", path),
+            ObjError::Parse { file, line, message } => write!(f, "{}:{}: {}", file, line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+// Loads an `.obj` file together with the `.mtl` files it references.
+// Polygons are triangulated as fans, so they should be convex (which almost every exporter ensures).
+// Faces whose material is missing (an unknown `usemtl` name, e.g. because its `.mtl` file couldn't be read)
+// use the default material, like faces without `usemtl`.
+pub fn load_obj(path: &str) -> ObjModel {
+    try_load_obj(path).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_load_obj(path: &str) -> Result<ObjModel, ObjError> {
+    let source = fs::read_to_string(path).map_err(|_| ObjError::FileNotFound { path: path.to_string() })?;
+    let directory = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+
+    parse_obj(&source, path, directory)
+}
+
+// Parses the contents of an `.obj` file. `mtllib` paths are resolved relative to `directory`.
+pub fn parse_obj(source: &str, file: &str, directory: &Path) -> Result<ObjModel, ObjError> {
+    let mut positions: Vec<(f32, f32, f32)> = vec![];
+    let mut colors: Vec<(f32, f32, f32)> = vec![];
+    let mut tex_coords: Vec<(f32, f32)> = vec![];
+    let mut normals: Vec<(f32, f32, f32)> = vec![];
+
+    let mut materials: Vec<ObjMaterial> = vec![];
+    let mut builders: Vec<MeshBuilder> = vec![];
+    let mut current_material: Option<usize> = None;
+
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let error = |message: String| ObjError::Parse { file: file.to_string(), line: line_number, message };
+
+        let line = line.split('#').next().unwrap().trim();
+        let mut parts = line.split_whitespace();
+        let keyword = match parts.next() {
+            Some(x) => x,
+            None => continue,
+        };
+        let arguments = parts.collect::<Vec<_>>();
+
+        match keyword {
+            "v" => {
+                let values = parse_floats(&arguments).map_err(error)?;
+                if values.len() < 3 {
+                    return Err(error("A vertex needs at least 3 coordinates".to_string()));
+                }
+                positions.push((values[0], values[1], values[2]));
+                // Some exporters write a vertex color after the position.
+                colors.push(if values.len() >= 6 { (values[3], values[4], values[5]) } else { (1.0, 1.0, 1.0) });
+            }
+            "vt" => {
+                let values = parse_floats(&arguments).map_err(error)?;
+                if values.is_empty() {
+                    return Err(error("A texture coordinate needs at least 1 value".to_string()));
+                }
+                tex_coords.push((values[0], values.get(1).copied().unwrap_or(0.0)));
+            }
+            "vn" => {
+                let values = parse_floats(&arguments).map_err(error)?;
+                if values.len() < 3 {
+                    return Err(error("A normal needs 3 values".to_string()));
+                }
+                normals.push((values[0], values[1], values[2]));
+            }
+            "f" => {
+                if arguments.len() < 3 {
+                    return Err(error("A face needs at least 3 vertices".to_string()));
+                }
+
+                let counts = (positions.len(), tex_coords.len(), normals.len());
+                let corners = arguments.iter()
+                    .map(|x| parse_face_vertex(x, counts))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(error)?;
+
+                let builder = match builders.iter().position(|x| x.material == current_material) {
+                    Some(x) => x,
+                    None => {
+                        builders.push(MeshBuilder::new(current_material));
+                        builders.len() - 1
+                    }
+                };
+                let material = current_material.map(|x| &materials[x]).unwrap_or(&DEFAULT_MATERIAL);
+                let color = material.diffuse_color;
+                let builder = &mut builders[builder];
+
+                let indices = corners.iter().map(|corner| builder.index(*corner, || {
+                    let vertex_color = colors[corner.0];
+                    Vertex {
+                        position: positions[corner.0],
+                        color: (vertex_color.0 * color.0, vertex_color.1 * color.1, vertex_color.2 * color.2, material.opacity),
+                        tex_coords: corner.1.map(|x| tex_coords[x]).unwrap_or((0.0, 0.0)),
+                        normals: corner.2.map(|x| normals[x]).unwrap_or((0.0, 0.0, 0.0)),
+                        texture_id: 0.0,
+                    }
+                })).collect::<Vec<_>>();
+
+                for j in 1..indices.len() - 1 {
+                    builder.indices.extend_from_slice(&[indices[0], indices[j], indices[j + 1]]);
+                }
+            }
+            "mtllib" => {
+                // File names can contain spaces.
+                let path = directory.join(arguments.join(" "));
+                let path = path.to_string_lossy().into_owned();
+                let source = match fs::read_to_string(&path) {
+                    Ok(source) => source,
+                    Err(_) => continue,
+                };
+                let mtl_directory = Path::new(&path).parent().unwrap_or_else(|| Path::new("")).to_path_buf();
+
+                for material in parse_mtl(&source, &path, &mtl_directory)? {
+                    match materials.iter().position(|x| x.name == material.name) {
+                        Some(existing) => materials[existing] = material,
+                        None => materials.push(material),
+                    }
+                }
+            }
+            "usemtl" => {
+                let name = arguments.join(" ");
+                current_material = materials.iter().position(|x| x.name == name);
+            }
+            // Objects, groups, smoothing groups, lines, etc. don't change the output.
+            _ => {}
+        }
+    }
+
+    Ok(ObjModel {
+        meshes: builders.into_iter()
+            .filter(|x| !x.indices.is_empty())
+            .map(|x| ObjMesh { material: x.material, vertices: x.vertices, indices: x.indices })
+            .collect(),
+        materials,
+    })
+}
+
+// Parses the contents of an `.mtl` file. Texture paths are resolved relative to `directory`.
+pub fn parse_mtl(source: &str, file: &str, directory: &Path) -> Result<Vec<ObjMaterial>, ObjError> {
+    let mut materials: Vec<ObjMaterial> = vec![];
+
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let error = |message: String| ObjError::Parse { file: file.to_string(), line: line_number, message };
+
+        let line = line.split('#').next().unwrap().trim();
+        let mut parts = line.split_whitespace();
+        let keyword = match parts.next() {
+            Some(x) => x,
+            None => continue,
+        };
+        let arguments = parts.collect::<Vec<_>>();
+
+        if keyword == "newmtl" {
+            materials.push(ObjMaterial { name: arguments.join(" "), ..ObjMaterial::default() });
+            continue;
+        }
+
+        let material = match materials.last_mut() {
+            Some(x) => x,
+            None => return Err(error(format!("`{}` before any `newmtl`", keyword))),
+        };
+        let color = |arguments: &[&str]| -> Result<(f32, f32, f32), ObjError> {
+            let values = parse_floats(arguments).map_err(error)?;
+            match values.len() {
+                0 => Err(error("Expected a color".to_string())),
+                1 | 2 => Ok((values[0], values[0], values[0])),
+                _ => Ok((values[0], values[1], values[2])),
+            }
+        };
+        let float = |arguments: &[&str]| -> Result<f32, ObjError> {
+            parse_floats(arguments).map_err(error)?.first().copied().ok_or_else(|| error("Expected a number".to_string()))
+        };
+
+        match keyword {
+            "Ka" => material.ambient_color = color(&arguments)?,
+            "Kd" => material.diffuse_color = color(&arguments)?,
+            "Ks" => material.specular_color = color(&arguments)?,
+            "Ns" => material.shininess = float(&arguments)?,
+            "d" => material.opacity = float(&arguments)?,
+            "Tr" => material.opacity = 1.0 - float(&arguments)?,
+            // Options like `-s 1 1 1` come before the file name, which is always last.
+            "map_Kd" => match arguments.last() {
+                Some(path) => material.diffuse_texture = Some(directory.join(path).to_string_lossy().into_owned()),
+                None => return Err(error("Expected a texture path".to_string())),
+            },
+            _ => {}
+        }
+    }
+
+    Ok(materials)
+}
+
+// De-duplicates vertices of one mesh by their position/texture coordinate/normal indices.
+//
+// Should NOT be exposed to the user.
+struct MeshBuilder {
+    material: Option<usize>,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    lookup: HashMap<FaceVertex, u32>,
+}
+
+type FaceVertex = (usize, Option<usize>, Option<usize>);
+
+impl MeshBuilder {
+    fn new(material: Option<usize>) -> Self {
+        MeshBuilder { material, vertices: vec![], indices: vec![], lookup: HashMap::new() }
+    }
+
+    fn index<F>(&mut self, corner: FaceVertex, vertex: F) -> u32
+        where F: FnOnce() -> Vertex {
+        let vertices = &mut self.vertices;
+
+        *self.lookup.entry(corner).or_insert_with(|| {
+            vertices.push(vertex());
+            vertices.len() as u32 - 1
+        })
+    }
+}
+
+fn parse_floats(arguments: &[&str]) -> Result<Vec<f32>, String> {
+    arguments.iter()
+        .map(|x| x.parse::<f32>().map_err(|_| format!("`{}` is not a number", x)))
+        .collect()
+}
+
+// Parses `v`, `v/vt`, `v//vn` or `v/vt/vn` into zero-based indices. Negative indices count back from the end.
+fn parse_face_vertex(text: &str, counts: (usize, usize, usize)) -> Result<FaceVertex, String> {
+    let mut parts = text.split('/');
+    let index = |part: Option<&str>, count: usize, kind: &str| -> Result<Option<usize>, String> {
+        let part = match part {
+            Some(x) if !x.is_empty() => x,
+            _ => return Ok(None),
+        };
+        let value = part.parse::<i64>().map_err(|_| format!("`{}` is not a valid face vertex", text))?;
+
+        let resolved = if value < 0 { count as i64 + value } else { value - 1 };
+        if resolved < 0 || resolved >= count as i64 {
+            return Err(format!("The {} index {} does not exist", kind, value));
+        }
+
+        Ok(Some(resolved as usize))
+    };
+
+    let position = index(parts.next(), counts.0, "vertex")?
+        .ok_or_else(|| format!("`{}` is missing a vertex index", text))?;
+    let tex_coord = index(parts.next(), counts.1, "texture coordinate")?;
+    let normal = index(parts.next(), counts.2, "normal")?;
+
+    Ok((position, tex_coord, normal))
+}
@@ -0,0 +1,128 @@
+use crate::preprocessor::{self, PreprocessedShader};
+use crate::shader::{self, ShaderError, ShaderType};
+
+// A single shader stage compiled into its own separable program (`GL_ARB_separate_shader_objects`).
+// One stage can be combined with any number of other stages through `ProgramPipeline`s,
+// so a vertex shader shared by many materials is only compiled once.
+//
+// NOTE: Drivers may require the vertex stage to redeclare `gl_PerVertex` when it is used in a pipeline:
+// out gl_PerVertex { vec4 gl_Position; };
+//
+// Should be exposed to the user.
+pub struct ShaderStage {
+    program: u32,
+    shader_type: ShaderType,
+}
+
+impl ShaderStage {
+    pub fn new(shader_type: ShaderType, source: &str) -> Self {
+        ShaderStage::try_new(shader_type, source).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(shader_type: ShaderType, source: &str) -> Result<Self, ShaderError> {
+        Ok(ShaderStage {
+            program: link_separable(shader_type, source, &[])?,
+            shader_type,
+        })
+    }
+
+    // `#include "..."` directives are resolved just like in `Material::from_shader_files`.
+    pub fn from_file(shader_type: ShaderType, path: &str, defines: &[(&str, &str)]) -> Self {
+        ShaderStage::try_from_file(shader_type, path, defines).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_from_file(shader_type: ShaderType, path: &str, defines: &[(&str, &str)]) -> Result<Self, ShaderError> {
+        let source: PreprocessedShader = preprocessor::preprocess_file(path, defines)?;
+
+        Ok(ShaderStage {
+            program: link_separable(shader_type, &source.source, &source.files)?,
+            shader_type,
+        })
+    }
+
+    pub fn get_program_id(&self) -> u32 {
+        self.program
+    }
+
+    pub fn get_shader_type(&self) -> ShaderType {
+        self.shader_type
+    }
+
+    // Pipelines that still use this stage stop working once it is deleted.
+    pub fn delete(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.program);
+        }
+    }
+}
+
+// Combines a vertex and a fragment `ShaderStage` into a program pipeline object.
+// Pipelines are cheap to create since nothing gets compiled or linked.
+//
+// Should be exposed to the user.
+pub struct ProgramPipeline {
+    pipeline: u32,
+    vertex_program: u32,
+    fragment_program: u32,
+}
+
+impl ProgramPipeline {
+    // Whether the driver supports separable programs (GL 4.1 or `GL_ARB_separate_shader_objects`).
+    // Requires the GL functions to be loaded, i.e. a `Renderer` to exist.
+    pub fn is_supported() -> bool {
+        gl::GenProgramPipelines::is_loaded() && gl::UseProgramStages::is_loaded()
+    }
+
+    pub fn new(vertex_stage: &ShaderStage, fragment_stage: &ShaderStage) -> Self {
+        if vertex_stage.shader_type != ShaderType::Vertex || fragment_stage.shader_type != ShaderType::Fragment {
+            panic!("A program pipeline needs a vertex stage followed by a fragment stage!");
+        }
+
+        let mut pipeline = 0u32;
+        unsafe {
+            gl::GenProgramPipelines(1, &mut pipeline);
+            gl::UseProgramStages(pipeline, gl::VERTEX_SHADER_BIT, vertex_stage.program);
+            gl::UseProgramStages(pipeline, gl::FRAGMENT_SHADER_BIT, fragment_stage.program);
+        }
+
+        ProgramPipeline {
+            pipeline,
+            vertex_program: vertex_stage.program,
+            fragment_program: fragment_stage.program,
+        }
+    }
+
+    pub fn get_pipeline_id(&self) -> u32 {
+        self.pipeline
+    }
+
+    // The separable programs bound to each stage, in pipeline order.
+    pub fn get_stage_programs(&self) -> Vec<u32> {
+        vec![self.vertex_program, self.fragment_program]
+    }
+
+    // Only deletes the pipeline object, the stages are owned by their `ShaderStage`s.
+    pub fn delete(&mut self) {
+        unsafe {
+            gl::DeleteProgramPipelines(1, &self.pipeline);
+        }
+    }
+}
+
+fn link_separable(shader_type: ShaderType, source: &str, files: &[String]) -> Result<u32, ShaderError> {
+    let shader = shader::compile_shader(shader_type, source, files)?;
+
+    unsafe {
+        let program = gl::CreateProgram();
+        gl::ProgramParameteri(program, gl::PROGRAM_SEPARABLE, gl::TRUE as i32);
+
+        gl::AttachShader(program, shader);
+        gl::LinkProgram(program);
+        gl::DetachShader(program, shader);
+        gl::DeleteShader(shader);
+
+        shader::check_link_status(program)?;
+
+        Ok(program)
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The result of running a shader through the preprocessor.
+// `files` holds every file that ended up in `source`, where the index of a file is the
+// GLSL "source string number" written into the `#line` directives. Drivers report errors
+// as `<source string number>:<line>`, so the lines always point back into the original files.
+//
+// Should be exposed to the user.
+pub struct PreprocessedShader {
+    pub source: String,
+    pub files: Vec<String>,
+    pub defines: Vec<(String, String)>,
+}
+
+impl PreprocessedShader {
+    // Returns the file name for a source string number reported by the driver.
+    pub fn file_name(&self, source_string: usize) -> Option<&str> {
+        self.files.get(source_string).map(|x| x.as_str())
+    }
+}
+
+#[derive(Debug)]
+pub enum PreprocessError {
+    // A shader or one of its includes could not be read.
+    FileNotFound { path: String, included_from: Option<(String, usize)> },
+    // `#include` was not followed by a quoted path.
+    MalformedInclude { file: String, line: usize },
+    // A file (directly or indirectly) includes itself. Contains the include chain.
+    IncludeCycle(Vec<String>),
+}
+
+impl fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PreprocessError::FileNotFound { path, included_from: Some((file, line)) } => {
+                write!(f, "{}:{}: The included file `{}` does not exist!", file, line, path)
+            }
+            PreprocessError::FileNotFound { path, included_from: None } => {
+                write!(f, "The path `{}` does not exist!", path)
+            }
+            PreprocessError::MalformedInclude { file, line } => {
+                write!(f, "{}:{}: Expected `#include \"path\"`", file, line)
+            }
+            PreprocessError::IncludeCycle(chain) => {
+                write!(f, "Include cycle detected: {}", chain.join(" -> "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for PreprocessError {}
+
+// Keeps track of the state while walking through the include tree.
+//
+// Should NOT be exposed to the user.
+struct Context {
+    files: Vec<PathBuf>,
+    // The paths as they were written, used for error messages.
+    names: Vec<String>,
+    stack: Vec<PathBuf>,
+    pragma_once: HashSet<PathBuf>,
+    // Include guard macro of each file that uses the `#ifndef X / #define X` pattern.
+    guards: Vec<(PathBuf, String)>,
+    defined_guards: HashSet<String>,
+    // Before GLSL 3.30 (and in GLSL ES 1.00) `#line N` gives the line after it the number N + 1 instead of N.
+    legacy_line: bool,
+}
+
+// Reads the shader at `path`, resolves its `#include "..."` directives relative to the including
+// file and injects the given defines right after the `#version` line.
+pub fn preprocess_file(path: &str, defines: &[(&str, &str)]) -> Result<PreprocessedShader, PreprocessError> {
+    let source = fs::read_to_string(path).map_err(|_| PreprocessError::FileNotFound {
+        path: path.to_string(),
+        included_from: None,
+    })?;
+
+    preprocess(&source, Path::new(path), defines)
+}
+
+// Same as `preprocess_file`, but for source code that is already in memory.
+// Includes are resolved relative to the current working directory.
+pub fn preprocess_source(source: &str, defines: &[(&str, &str)]) -> Result<PreprocessedShader, PreprocessError> {
+    preprocess(source, Path::new("<source>"), defines)
+}
+
+fn preprocess(source: &str, path: &Path, defines: &[(&str, &str)]) -> Result<PreprocessedShader, PreprocessError> {
+    let root = canonical(path);
+    let mut context = Context {
+        files: vec![root.clone()],
+        names: vec![path.to_string_lossy().into_owned()],
+        stack: vec![root],
+        pragma_once: HashSet::new(),
+        guards: vec![],
+        defined_guards: HashSet::new(),
+        legacy_line: uses_legacy_line(source),
+    };
+
+    if let Some(guard) = include_guard(source) {
+        context.guards.push((context.files[0].clone(), guard.clone()));
+        context.defined_guards.insert(guard);
+    }
+
+    let mut output = String::new();
+    let mut lines = source.lines().enumerate();
+
+    // Everything up to and including `#version` has to stay at the top of the shader.
+    let has_version = source.lines().any(|x| x.trim_start().starts_with("#version"));
+    let mut first_line = 1;
+    if has_version {
+        for (i, line) in lines.by_ref() {
+            output.push_str(line);
+            output.push('\n');
+            if line.trim_start().starts_with("#version") {
+                first_line = i + 2;
+                break;
+            }
+        }
+    }
+
+    for (name, value) in defines.iter() {
+        output.push_str(&format!("#define {} {}\n", name, value));
+    }
+    output.push_str(&line_directive(first_line, 0, &context));
+
+    let remaining = lines.map(|(_, x)| x).collect::<Vec<_>>();
+    process_lines(&remaining, first_line, 0, path, &mut context, &mut output)?;
+
+    Ok(PreprocessedShader {
+        source: output,
+        files: context.names,
+        defines: defines.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect(),
+    })
+}
+
+fn process_lines(lines: &[&str], first_line: usize, file_index: usize, path: &Path,
+    context: &mut Context, output: &mut String) -> Result<(), PreprocessError> {
+
+    for (i, line) in lines.iter().enumerate() {
+        let line_number = first_line + i;
+        let trimmed = line.trim_start();
+
+        if let Some(rest) = directive(trimmed, "include") {
+            let include = parse_include_path(rest).ok_or_else(|| PreprocessError::MalformedInclude {
+                file: path.to_string_lossy().into_owned(),
+                line: line_number,
+            })?;
+            let include_path = path.parent().unwrap_or_else(|| Path::new("")).join(include);
+
+            include_file(&include_path, path, line_number, context, output)?;
+            output.push_str(&line_directive(line_number + 1, file_index, context));
+        } else if let Some(rest) = directive(trimmed, "pragma") {
+            if rest.trim() == "once" {
+                context.pragma_once.insert(canonical(path));
+                output.push('\n');
+            } else {
+                output.push_str(line);
+                output.push('\n');
+            }
+        } else if file_index != 0 && directive(trimmed, "version").is_some() {
+            // Included files may declare a version for editor tooling, but only the root one counts.
+            output.push('\n');
+        } else {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+
+    Ok(())
+}
+
+fn include_file(include_path: &Path, parent: &Path, line: usize, context: &mut Context,
+    output: &mut String) -> Result<(), PreprocessError> {
+
+    let canonical_path = canonical(include_path);
+
+    if context.pragma_once.contains(&canonical_path) {
+        return Ok(());
+    }
+    if let Some((_, guard)) = context.guards.iter().find(|(p, _)| *p == canonical_path) {
+        if context.defined_guards.contains(guard) {
+            return Ok(());
+        }
+    }
+    if context.stack.contains(&canonical_path) {
+        let mut chain = context.stack.iter().map(|x| x.to_string_lossy().into_owned()).collect::<Vec<_>>();
+        chain.push(canonical_path.to_string_lossy().into_owned());
+        return Err(PreprocessError::IncludeCycle(chain));
+    }
+
+    let source = fs::read_to_string(include_path).map_err(|_| PreprocessError::FileNotFound {
+        path: include_path.to_string_lossy().into_owned(),
+        included_from: Some((parent.to_string_lossy().into_owned(), line)),
+    })?;
+
+    if let Some(guard) = include_guard(&source) {
+        context.guards.push((canonical_path.clone(), guard.clone()));
+        context.defined_guards.insert(guard);
+    }
+
+    let file_index = match context.files.iter().position(|x| *x == canonical_path) {
+        Some(index) => index,
+        None => {
+            context.files.push(canonical_path.clone());
+            context.names.push(include_path.to_string_lossy().into_owned());
+            context.files.len() - 1
+        }
+    };
+
+    output.push_str(&line_directive(1, file_index, context));
+
+    context.stack.push(canonical_path);
+    let lines = source.lines().collect::<Vec<_>>();
+    process_lines(&lines, 1, file_index, include_path, context, output)?;
+    context.stack.pop();
+
+    Ok(())
+}
+
+// A `#line` directive that makes the next line `line` of source string `file_index`.
+fn line_directive(line: usize, file_index: usize, context: &Context) -> String {
+    if context.legacy_line {
+        format!("#line {} {}\n", line - 1, file_index)
+    } else {
+        format!("#line {} {}\n", line, file_index)
+    }
+}
+
+// Shaders without `#version` are GLSL 1.10. Everything below 300 (desktop 1.10 - 1.50, GLSL ES 1.00) uses the
+// old numbering; GLSL ES 3.00 and up number lines like desktop GLSL 3.30.
+pub(crate) fn uses_legacy_line(source: &str) -> bool {
+    let version = source.lines()
+        .filter_map(|x| directive(x.trim_start(), "version"))
+        .next()
+        .and_then(|x| x.split_whitespace().next())
+        .and_then(|x| x.parse::<u32>().ok())
+        .unwrap_or(110);
+
+    version < 300
+}
+
+// Returns whatever follows `#<name>` if the line is that directive.
+fn directive<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix('#')?.trim_start();
+    let rest = rest.strip_prefix(name)?;
+
+    if rest.is_empty() || rest.starts_with(char::is_whitespace) || rest.starts_with('"') {
+        Some(rest)
+    } else {
+        None
+    }
+}
+
+fn parse_include_path(rest: &str) -> Option<&str> {
+    let rest = rest.trim().strip_prefix('"')?;
+    let end = rest.find('"')?;
+
+    if end == 0 { None } else { Some(&rest[..end]) }
+}
+
+// Detects the classic `#ifndef NAME` / `#define NAME` guard at the top of a file.
+fn include_guard(source: &str) -> Option<String> {
+    let mut directives = source.lines()
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty() && !x.starts_with("//"))
+        .filter(|x| directive(x, "version").is_none());
+
+    let name = directive(directives.next()?, "ifndef")?.trim().to_string();
+    let defined = directive(directives.next()?, "define")?.trim().to_string();
+
+    if name == defined && !name.is_empty() { Some(name) } else { None }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,228 @@
+use std::ffi::{c_void, CStr};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::preprocessor::PreprocessedShader;
+use crate::shader::{self, ShaderError};
+
+// Written at the start of every cache file so that foreign or truncated files are ignored.
+//
+// Should NOT be exposed to the user.
+static CACHE_MAGIC: &[u8; 4] = b"MGPB";
+static CACHE_VERSION: u32 = 1;
+
+// An on-disk cache of linked program binaries (`glGetProgramBinary`/`glProgramBinary`).
+// Entries are keyed by a hash of the shader sources, their defines and the driver's
+// vendor/renderer/version strings, so updating the driver or editing a shader simply
+// misses the cache. A binary the driver refuses to load is deleted and recompiled.
+//
+// Requires a current context since the driver strings are read when the cache is created.
+//
+// Should be exposed to the user.
+#[derive(Clone)]
+pub struct ProgramCache {
+    directory: PathBuf,
+    driver: String,
+    supported: bool,
+}
+
+impl ProgramCache {
+    pub fn new(directory: &str) -> Self {
+        let mut formats = 0i32;
+        let driver = unsafe {
+            gl::GetIntegerv(gl::NUM_PROGRAM_BINARY_FORMATS, &mut formats);
+
+            format!("{}\n{}\n{}",
+                gl_string(gl::VENDOR),
+                gl_string(gl::RENDERER),
+                gl_string(gl::VERSION))
+        };
+
+        // The cache is only an optimization, so failing to create the directory just disables it.
+        let supported = formats > 0 && fs::create_dir_all(directory).is_ok();
+
+        ProgramCache {
+            directory: PathBuf::from(directory),
+            driver,
+            supported,
+        }
+    }
+
+    // Whether the driver supports at least one program binary format. If not, every call compiles.
+    pub fn is_supported(&self) -> bool {
+        self.supported
+    }
+
+    pub fn compile_program(&self, vertex_shader: &str, fragment_shader: &str) -> u32 {
+        self.try_compile_program(vertex_shader, fragment_shader).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_compile_program(&self, vertex_shader: &str, fragment_shader: &str) -> Result<u32, ShaderError> {
+        let key = self.key(vertex_shader, fragment_shader, &[]);
+
+        self.load_or_compile(key, || shader::link_program(vertex_shader, fragment_shader, &[], &[], true))
+    }
+
+    pub fn compile_preprocessed_program(&self, vertex_shader: &PreprocessedShader, fragment_shader: &PreprocessedShader) -> u32 {
+        self.try_compile_preprocessed_program(vertex_shader, fragment_shader).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_compile_preprocessed_program(&self, vertex_shader: &PreprocessedShader, fragment_shader: &PreprocessedShader)
+        -> Result<u32, ShaderError> {
+        let mut defines = vertex_shader.defines.clone();
+        defines.extend(fragment_shader.defines.iter().cloned());
+        let key = self.key(&vertex_shader.source, &fragment_shader.source, &defines);
+
+        self.load_or_compile(key, || shader::link_program(&vertex_shader.source, &fragment_shader.source,
+            &vertex_shader.files, &fragment_shader.files, true))
+    }
+
+    // Deletes every cached binary.
+    pub fn clear(&self) {
+        if let Ok(entries) = fs::read_dir(&self.directory) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().map(|x| x == "bin").unwrap_or(false) {
+                    let _ = fs::remove_file(path);
+                }
+            }
+        }
+    }
+
+    fn load_or_compile<F>(&self, key: u64, compile: F) -> Result<u32, ShaderError>
+        where F: FnOnce() -> Result<u32, ShaderError> {
+        if !self.supported {
+            return compile();
+        }
+
+        let path = self.directory.join(format!("{:016x}.bin", key));
+        if let Some(program) = load_binary(&path) {
+            return Ok(program);
+        }
+
+        let program = compile()?;
+        store_binary(&path, program);
+
+        Ok(program)
+    }
+
+    fn key(&self, vertex_shader: &str, fragment_shader: &str, defines: &[(String, String)]) -> u64 {
+        let mut defines = defines.to_vec();
+        defines.sort();
+
+        let mut hash = fnv1a(FNV_OFFSET, self.driver.as_bytes());
+        for part in [vertex_shader, fragment_shader].iter() {
+            hash = fnv1a(hash, part.as_bytes());
+            hash = fnv1a(hash, &[0]);
+        }
+        for (name, value) in defines.iter() {
+            hash = fnv1a(hash, name.as_bytes());
+            hash = fnv1a(hash, b"=");
+            hash = fnv1a(hash, value.as_bytes());
+            hash = fnv1a(hash, &[0]);
+        }
+
+        hash
+    }
+}
+
+// Loads the cached binary at `path` into a new program. Returns `None` (and removes the file
+// if it exists) when the file is unusable or the driver rejects the binary.
+fn load_binary(path: &Path) -> Option<u32> {
+    let data = fs::read(path).ok()?;
+
+    let parsed = parse_cache_file(&data);
+    let (format, binary) = match parsed {
+        Some(x) => x,
+        None => {
+            let _ = fs::remove_file(path);
+            return None;
+        }
+    };
+
+    unsafe {
+        let program = gl::CreateProgram();
+        gl::ProgramBinary(program, format, binary.as_ptr() as *const c_void, binary.len() as i32);
+
+        let mut success: i32 = 0;
+        gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
+
+        if success != (gl::TRUE as i32) {
+            gl::DeleteProgram(program);
+            let _ = fs::remove_file(path);
+            return None;
+        }
+
+        Some(program)
+    }
+}
+
+fn store_binary(path: &Path, program: u32) {
+    let (format, binary) = unsafe {
+        let mut len: i32 = 0;
+        gl::GetProgramiv(program, gl::PROGRAM_BINARY_LENGTH, &mut len);
+        if len <= 0 {
+            return;
+        }
+
+        let mut binary = vec![0u8; len as usize];
+        let mut written: i32 = 0;
+        let mut format: u32 = 0;
+        gl::GetProgramBinary(program, len, &mut written, &mut format, binary.as_mut_ptr() as *mut c_void);
+        binary.truncate(written.max(0) as usize);
+
+        (format, binary)
+    };
+
+    let mut data = Vec::with_capacity(binary.len() + 16);
+    data.extend_from_slice(CACHE_MAGIC);
+    data.extend_from_slice(&CACHE_VERSION.to_le_bytes());
+    data.extend_from_slice(&format.to_le_bytes());
+    data.extend_from_slice(&(binary.len() as u32).to_le_bytes());
+    data.extend_from_slice(&binary);
+
+    // Write to a temporary file first so a crash never leaves half a binary behind.
+    let temporary = path.with_extension("tmp");
+    if fs::write(&temporary, &data).is_ok() && fs::rename(&temporary, path).is_err() {
+        let _ = fs::remove_file(&temporary);
+    }
+}
+
+fn parse_cache_file(data: &[u8]) -> Option<(u32, &[u8])> {
+    if data.len() < 16 || &data[0..4] != CACHE_MAGIC {
+        return None;
+    }
+
+    let read_u32 = |offset: usize| u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]);
+    if read_u32(4) != CACHE_VERSION {
+        return None;
+    }
+
+    let format = read_u32(8);
+    let len = read_u32(12) as usize;
+    let binary = data.get(16..16 + len)?;
+
+    Some((format, binary))
+}
+
+unsafe fn gl_string(name: u32) -> String {
+    let string = gl::GetString(name);
+    if string.is_null() {
+        return String::new();
+    }
+
+    CStr::from_ptr(string as *const i8).to_string_lossy().into_owned()
+}
+
+// FNV-1a is used instead of `DefaultHasher` since the key has to stay the same between builds.
+static FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+static FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes.iter() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+
+    hash
+}
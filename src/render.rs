@@ -2,11 +2,21 @@ use std::ffi::{c_void, CStr};
 use std::ptr;
 use memoffset::offset_of;
 
-use crate::material::{Material, AttributeType, set_attribute};
+use crate::material::{Material, MaterialInstance, AttributeType, BuiltinUniform, upsert_attribute, find_attribute};
+use crate::texture::Texture;
+use crate::render_state::{BlendState, CompareFunction, DepthState, RenderState, StateCache};
+use crate::render_queue::{DrawItem, RenderQueue};
+use crate::render_pass::{ClearFlags, DrawFilter, Rect, RenderPass, RenderTarget};
+use crate::math::{Matrix3, Matrix4, Vector3};
+use crate::bounds::{Aabb, BoundingSphere, Frustum};
+use crate::camera::Camera;
+use crate::transform::Transform;
+use crate::lod::{self, LodMetric};
+use crate::debug_draw::{DebugDraw, DEBUG_FRAGMENT_SHADER, DEBUG_VERTEX_DATA_SIZE, DEBUG_VERTEX_SHADER};
 
 // The renderer takes in an array of DebugFilters to filter out one or more
 // specific types of messages from the OpenGL callbacks.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum DebugFilter {
     None,
     Info,
@@ -17,6 +27,7 @@ pub enum DebugFilter {
 
 // In the init process, one of the parameters is a `FaceCulling` enum
 // to give the user more control
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum FaceCulling {
     None,
     Front,
@@ -24,6 +35,41 @@ pub enum FaceCulling {
     FrontAndBack,
 }
 
+// How the indices of an object are turned into primitives. Every object starts out as `Triangles`.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PrimitiveMode {
+    Points,
+    Lines,
+    LineLoop,
+    LineStrip,
+    Triangles,
+    TriangleStrip,
+    TriangleFan,
+}
+
+impl PrimitiveMode {
+    pub(crate) fn to_gl(self) -> u32 {
+        match self {
+            PrimitiveMode::Points => gl::POINTS,
+            PrimitiveMode::Lines => gl::LINES,
+            PrimitiveMode::LineLoop => gl::LINE_LOOP,
+            PrimitiveMode::LineStrip => gl::LINE_STRIP,
+            PrimitiveMode::Triangles => gl::TRIANGLES,
+            PrimitiveMode::TriangleStrip => gl::TRIANGLE_STRIP,
+            PrimitiveMode::TriangleFan => gl::TRIANGLE_FAN,
+        }
+    }
+}
+
+// Layer bitmasks for `set_object_layers` and `render_layers`. Every object starts out on `DEFAULT_LAYER`,
+// any other bits can be used however the application wants (e.g. world = 1, UI = 2, debug = 4).
+//
+// Should be exposed to the user.
+pub static DEFAULT_LAYER: u32 = 1;
+pub static ALL_LAYERS: u32 = u32::MAX;
+
 // Used for readability instead of using just `4`
 //
 // Should NOT be exposed to the user.
@@ -34,6 +80,7 @@ static FOUR_BYTES: usize = 4;
 // VBO (Vertex Buffer Object): Contains the position, color, etc. data.
 // IBO (Index Buffer Object): Contains the indices (the order in which the vertices are rendered).
 // Index Size: The length of the IBO array data.
+// Vertex Count: How many vertices fit in the VBO, which per-vertex data in other buffers has to match.
 //
 // Should NOT be exposed to the user.
 struct Buffers {
@@ -41,6 +88,7 @@ struct Buffers {
     pub vbo: u32,
     pub ibo: u32,
     pub index_size: i32,
+    pub vertex_count: usize,
 }
 
 // Public types used for the `Vertex` struct.
@@ -66,224 +114,1563 @@ pub struct Vertex {
     pub texture_id: TextureID,
 }
 
+// The texture unit the morph target deltas of an object are bound to (see `set_object_morph_targets`).
+// It is far above the textures of any material, which start at unit 0.
+//
+// Should be exposed to the user.
+pub static MORPH_TARGET_TEXTURE_UNIT: u32 = 15;
+
+// A deformed version of an object, like a smile on a face, stored as the difference to every vertex.
+// `normal_deltas` may be empty when the normals don't change.
+//
+// Should be exposed to the user.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MorphTarget {
+    pub position_deltas: Vec<Position>,
+    pub normal_deltas: Vec<Normals>,
+}
+
+// The GPU side of an object's morph targets: a texture buffer with two texels (position and normal delta)
+// per vertex and target, target by target.
+//
+// Should NOT be exposed to the user.
+struct MorphTargetBuffers {
+    buffer: u32,
+    texture: u32,
+    target_count: i32,
+    vertex_count: i32,
+    // How far the targets can move vertices out of the object's bounds, along each axis and in any direction.
+    growth: (Vector3, f32),
+}
+
+// The line buffer and material of `draw_debug`, created the first time there is something to draw.
+//
+// Should NOT be exposed to the user.
+struct DebugBuffers {
+    vao: u32,
+    vbo: u32,
+    material: Material,
+}
+
+// Used for the `SkinVertex` struct to get the amount of floats in the entire struct
+static SKIN_VERTEX_DATA_SIZE: isize = 8;
+
+// Floats per tangent in the buffers of `set_object_tangents`.
+static TANGENT_DATA_SIZE: isize = 4;
+
+// The joints that move a vertex and how much each of them does, for skinning (see `set_object_skin`).
+// They are in their own buffer next to the vertices, as attribute 5 (`joints`) and 6 (`weights`).
+//
+// Should be exposed to the user.
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct SkinVertex {
+    // Indices into the joint matrices. Floats, just like `texture_id`.
+    pub joints: (f32, f32, f32, f32),
+    // Should add up to 1.0.
+    pub weights: (f32, f32, f32, f32),
+}
+
+// Settings used to create a `Renderer`. All of them can be changed later with the `set_*` functions on the renderer.
+//
+// Should be exposed to the user.
+#[derive(Clone, Debug)]
+pub struct RendererConfig {
+    pub multisample: bool,
+    pub depth_test: bool,
+    pub face_culling: FaceCulling,
+    pub debug_output: bool,
+    pub debug_filters: Vec<DebugFilter>,
+    // The renderer can't change the swap interval itself since that belongs to the context library
+    // (glfw-rs, glutin, etc.), it only keeps track of what the application asked for.
+    pub vsync_hint: bool,
+    pub clear_depth: f64,
+    pub clear_stencil: i32,
+}
+
+impl Default for RendererConfig {
+    fn default() -> Self {
+        RendererConfig {
+            multisample: false,
+            depth_test: true,
+            face_culling: FaceCulling::None,
+            debug_output: true,
+            debug_filters: vec![],
+            vsync_hint: true,
+            clear_depth: 1.0,
+            clear_stencil: 0,
+        }
+    }
+}
+
+impl RendererConfig {
+    pub fn new() -> Self {
+        RendererConfig::default()
+    }
+
+    pub fn multisample(mut self, multisample: bool) -> Self {
+        self.multisample = multisample;
+        self
+    }
+
+    pub fn depth_test(mut self, depth_test: bool) -> Self {
+        self.depth_test = depth_test;
+        self
+    }
+
+    pub fn face_culling(mut self, face_culling: FaceCulling) -> Self {
+        self.face_culling = face_culling;
+        self
+    }
+
+    pub fn debug_output(mut self, debug_output: bool) -> Self {
+        self.debug_output = debug_output;
+        self
+    }
+
+    pub fn debug_filters(mut self, debug_filters: Vec<DebugFilter>) -> Self {
+        self.debug_filters = debug_filters;
+        self
+    }
+
+    pub fn vsync_hint(mut self, vsync_hint: bool) -> Self {
+        self.vsync_hint = vsync_hint;
+        self
+    }
+
+    pub fn clear_depth(mut self, clear_depth: f64) -> Self {
+        self.clear_depth = clear_depth;
+        self
+    }
+
+    pub fn clear_stencil(mut self, clear_stencil: i32) -> Self {
+        self.clear_stencil = clear_stencil;
+        self
+    }
+}
+
+// Refers to a material stored in the renderer, either a full material (with its own program)
+// or a lightweight instance that overrides some attributes/textures of a base material.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MaterialHandle {
+    Material(u32),
+    Instance(u32),
+}
+
+// How many objects the frustum culling looked at and skipped since `begin_frame`.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct CullingStats {
+    // Objects that were tested against the frustum: visible, on the drawn layers and with bounds,
+    // while there is a camera and frustum culling is on.
+    pub tested: u32,
+    pub culled: u32,
+    // Objects that were drawn (including the ones without bounds, which are never culled).
+    pub drawn: u32,
+}
+
 // Data-Oriented struct that controls what happens with each created object and renders them.
 //
 // Should be exposed to the user.
 pub struct Renderer {
     buffers: Vec<Buffers>,
     materials: Vec<Material>,
+    material_instances: Vec<MaterialInstance>,
+    object_materials: Vec<MaterialHandle>,
+    // Per-object uniforms that are kept until they are changed.
+    object_uniforms: Vec<Vec<(String, AttributeType)>>,
+    // Per-object uniforms that only apply to the next frame, on top of everything else.
     attribute_queue: Vec<Vec<(String, AttributeType)>>,
+    // Depth testing and face culling are used by materials whose render state doesn't set them.
+    config: RendererConfig,
+    // Read by the debug callback. It is intentionally leaked since the callback can outlive the renderer.
+    debug_filters: *mut Vec<DebugFilter>,
+    state_cache: StateCache,
+    // Lower priorities are drawn first, before any sorting by material or depth.
+    object_priorities: Vec<i32>,
+    // Explicit sort keys. Objects without one use their view-space depth when there is a camera.
+    object_sort_keys: Vec<Option<f32>>,
+    render_queue: RenderQueue,
+    sorting: bool,
+    object_visible: Vec<bool>,
+    // Whether the scene graph node the object is attached to is enabled, separate from `object_visible`
+    // so that neither overrides the other.
+    object_node_enabled: Vec<bool>,
+    object_layers: Vec<u32>,
+    clear_color: (f32, f32, f32, f32),
+    // The viewport set with `set_viewport`/`resize`.
+    viewport: Option<Rect>,
+    // Window size in screen coordinates and framebuffer size in pixels. They differ on HiDPI displays.
+    window_size: (i32, i32),
+    framebuffer_size: (i32, i32),
+    scissor_stack: Vec<Rect>,
+    object_transforms: Vec<Transform>,
+    // World matrices of the scene graph nodes objects are attached to.
+    object_node_matrices: Vec<Option<Matrix4>>,
+    // World matrices of the objects in the render queue, updated every `draw`.
+    world_matrices: Vec<Matrix4>,
+    camera: Option<Camera>,
+    // Local bounds computed from the vertex data. `None` for objects that can't be culled.
+    object_bounds: Vec<Option<(Aabb, BoundingSphere)>>,
+    // Whether the bounds were set with `set_object_bounds`, in which case `change_object` keeps them.
+    object_manual_bounds: Vec<bool>,
+    frustum_culling: bool,
+    culling_stats: CullingStats,
+    object_primitive_modes: Vec<PrimitiveMode>,
+    // Coarser versions of each object (level 1 and up) with the threshold they start at. Level 0 is in `buffers`.
+    object_lods: Vec<Vec<(Buffers, f32)>>,
+    // The level each object was last drawn with, which the hysteresis starts from.
+    object_current_lods: Vec<usize>,
+    object_forced_lods: Vec<Option<usize>>,
+    lod_metric: LodMetric,
+    lod_hysteresis: f32,
+    // Buffers with the `SkinVertex` data of skinned objects, 0 for the others.
+    object_skin_buffers: Vec<u32>,
+    // Buffers with the tangents of objects that have them, 0 for the others.
+    object_tangent_buffers: Vec<u32>,
+    // Flattened joint matrices, uploaded as `u_joint_matrices` whenever the object is drawn.
+    object_joint_matrices: Vec<Vec<f32>>,
+    object_morph_targets: Vec<Option<MorphTargetBuffers>>,
+    // One weight per morph target, uploaded as `u_morph_weights`.
+    object_morph_weights: Vec<Vec<f32>>,
+    debug_draw: DebugDraw,
+    debug_buffers: Option<DebugBuffers>,
 }
 
 #[allow(unused_assignments)]
 impl Renderer {
     // Loads the GL functions, therefore requiring a context to load their proc address
-    pub fn new<F>(mut address: F, multisample: bool, depth_test: bool, cull_face: FaceCulling, debug_filters:  Vec<DebugFilter>) -> Self
+    pub fn new<F>(address: F, multisample: bool, depth_test: bool, cull_face: FaceCulling, debug_filters:  Vec<DebugFilter>) -> Self
+        where F: FnMut(&'static str) -> *const c_void {
+        Renderer::with_config(address, RendererConfig::new()
+            .multisample(multisample)
+            .depth_test(depth_test)
+            .face_culling(cull_face)
+            .debug_filters(debug_filters))
+    }
+
+    // Same as `new`, but takes every setting from a `RendererConfig`.
+    pub fn with_config<F>(mut address: F, config: RendererConfig) -> Self
         where F: FnMut(&'static str) -> *const c_void {
         gl::load_with(|symbol| address(symbol));
+
+        let debug_filters = Box::into_raw(Box::new(config.debug_filters.clone()));
         unsafe {
-            gl::Enable(gl::DEBUG_OUTPUT);
             gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
-            gl::DebugMessageCallback(Some(message_callback), Box::into_raw(Box::new(debug_filters)).cast());
-
-            if multisample { gl::Enable(gl::MULTISAMPLE) }
-            if depth_test { gl::Enable(gl::DEPTH_TEST) }
-            match cull_face {
-                FaceCulling::Front => {
-                    gl::Enable(gl::CULL_FACE);
-                    gl::CullFace(gl::FRONT);
-                },
-                FaceCulling::Back => {
-                    gl::Enable(gl::CULL_FACE);
-                    gl::CullFace(gl::BACK);
-                },
-                FaceCulling::FrontAndBack => {
-                    gl::Enable(gl::CULL_FACE);
-                    gl::CullFace(gl::FRONT_AND_BACK);
-                }
-                _ => {}
-            }
+            gl::DebugMessageCallback(Some(message_callback), debug_filters.cast());
         }
 
-
-        Renderer {
+        let mut renderer = Renderer {
             buffers: vec![],
             materials: vec![],
+            material_instances: vec![],
+            object_materials: vec![],
+            object_uniforms: vec![],
             attribute_queue: vec![],
+            config: config.clone(),
+            debug_filters,
+            state_cache: StateCache::default(),
+            object_priorities: vec![],
+            object_sort_keys: vec![],
+            render_queue: RenderQueue::default(),
+            sorting: true,
+            object_visible: vec![],
+            object_node_enabled: vec![],
+            object_layers: vec![],
+            clear_color: (0.0, 0.0, 0.0, 1.0),
+            viewport: None,
+            window_size: (0, 0),
+            framebuffer_size: (0, 0),
+            scissor_stack: vec![],
+            object_transforms: vec![],
+            object_node_matrices: vec![],
+            world_matrices: vec![],
+            camera: None,
+            object_bounds: vec![],
+            object_manual_bounds: vec![],
+            frustum_culling: true,
+            culling_stats: CullingStats::default(),
+            object_primitive_modes: vec![],
+            object_lods: vec![],
+            object_current_lods: vec![],
+            object_forced_lods: vec![],
+            lod_metric: LodMetric::Distance,
+            lod_hysteresis: 0.0,
+            object_skin_buffers: vec![],
+            object_tangent_buffers: vec![],
+            object_joint_matrices: vec![],
+            object_morph_targets: vec![],
+            object_morph_weights: vec![],
+            debug_draw: DebugDraw::new(),
+            debug_buffers: None,
+        };
+
+        // Depth testing and face culling are applied per material in `render`.
+        renderer.set_multisample(config.multisample);
+        renderer.set_debug_output(config.debug_output);
+        renderer.set_clear_depth(config.clear_depth);
+        renderer.set_clear_stencil(config.clear_stencil);
+
+        renderer
+    }
+
+    pub fn get_config(&self) -> &RendererConfig {
+        &self.config
+    }
+
+    pub fn set_multisample(&mut self, multisample: bool) {
+        unsafe {
+            if multisample { gl::Enable(gl::MULTISAMPLE) } else { gl::Disable(gl::MULTISAMPLE) }
         }
+        self.config.multisample = multisample;
     }
 
-    // Creates an object with the given vertex count, positions, colors, indices, and material.
-    // The Object Manager will draw the object when given the chance using the `render` function.
-    pub fn create_object(&mut self,
-        vertices: Option<Vec<Vertex>>,
-        indices: Option<Vec<u32>>,
-        material: Material) -> u32
-    {
+    // Used by every material whose render state doesn't set its own depth state.
+    pub fn set_depth_test(&mut self, depth_test: bool) {
+        self.config.depth_test = depth_test;
+    }
 
-        let (vao, vbo, ibo, index_size) = unsafe {
-            let mut vao = 0u32;
-            gl::GenVertexArrays(1, &mut vao);
-            gl::BindVertexArray(vao);
+    // Used by every material whose render state doesn't set its own culling.
+    pub fn set_face_culling(&mut self, face_culling: FaceCulling) {
+        self.config.face_culling = face_culling;
+    }
 
-            let mut vbo = 0u32;
-            match vertices {
-                Some(vertex_data) => {
-                    let mut buffer_data: Vec<f32> = vec![];
-                    for vertex in vertex_data.iter() {
-                        buffer_data.extend(vec![
-                            vertex.position.0,
-                            vertex.position.1,
-                            vertex.position.2,
-                            vertex.color.0,
-                            vertex.color.1,
-                            vertex.color.2,
-                            vertex.color.3,
-                            vertex.tex_coords.0,
-                            vertex.tex_coords.1,
-                            vertex.normals.0,
-                            vertex.normals.1,
-                            vertex.normals.2,
-                            vertex.texture_id
-                        ]);
-                    }
+    // Enables or disables the OpenGL debug callback entirely.
+    pub fn set_debug_output(&mut self, debug_output: bool) {
+        unsafe {
+            if debug_output { gl::Enable(gl::DEBUG_OUTPUT) } else { gl::Disable(gl::DEBUG_OUTPUT) }
+        }
+        self.config.debug_output = debug_output;
+    }
 
-                    gl::GenBuffers(1, &mut vbo);
-                    gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-                    gl::BufferData(
-                        gl::ARRAY_BUFFER,
-                        (FOUR_BYTES * buffer_data.len()) as isize,
-                        buffer_data.as_ptr() as *const c_void,
-                        gl::DYNAMIC_DRAW,
-                    );
-                },
-                None => {}
-            }
+    pub fn set_debug_filters(&mut self, debug_filters: Vec<DebugFilter>) {
+        // The callback only runs synchronously during GL calls on this thread, so nothing reads it right now.
+        unsafe { *self.debug_filters = debug_filters.clone(); }
+        self.config.debug_filters = debug_filters;
+    }
 
-            let stride: i32 = (VERTEX_DATA_SIZE * FOUR_BYTES as isize) as i32;
-            Renderer::enable_vertex_attrib_ptr(0, 3, stride, offset_of!(Vertex, position));
-            Renderer::enable_vertex_attrib_ptr(1, 4, stride, offset_of!(Vertex, color));
-            Renderer::enable_vertex_attrib_ptr(2, 2, stride, offset_of!(Vertex, tex_coords));
-            Renderer::enable_vertex_attrib_ptr(3, 3, stride, offset_of!(Vertex, normals));
-            Renderer::enable_vertex_attrib_ptr(4, 1, stride, offset_of!(Vertex, texture_id));
+    // Only stored for the application to pass on to its context library, see `RendererConfig::vsync_hint`.
+    pub fn set_vsync_hint(&mut self, vsync_hint: bool) {
+        self.config.vsync_hint = vsync_hint;
+    }
 
-            let index_size;
-            let mut ibo = 0u32;
-            match indices {
-                Some(index_data) => {
-                    gl::GenBuffers(1, &mut ibo);
-                    gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ibo);
-                    gl::BufferData(
-                        gl::ELEMENT_ARRAY_BUFFER,
-                        (FOUR_BYTES * index_data.len()) as isize,
-                        index_data.as_ptr() as *const c_void,
-                        gl::DYNAMIC_DRAW,
-                    );
+    pub fn get_vsync_hint(&self) -> bool {
+        self.config.vsync_hint
+    }
 
-                    index_size = index_data.len() as i32;
-                },
-                None => { index_size = 0i32; }
-            }
+    // The value the depth buffer is cleared to in `render`.
+    pub fn set_clear_depth(&mut self, clear_depth: f64) {
+        unsafe { gl::ClearDepth(clear_depth); }
+        self.config.clear_depth = clear_depth;
+    }
 
-            (vao, vbo, ibo, index_size)
-        };
+    // The value the stencil buffer is cleared to in `render`.
+    pub fn set_clear_stencil(&mut self, clear_stencil: i32) {
+        unsafe { gl::ClearStencil(clear_stencil); }
+        self.config.clear_stencil = clear_stencil;
+    }
 
-        self.buffers.push(Buffers {
-            vao,
-            vbo,
-            ibo,
-            index_size,
-        });
+    fn default_depth(&self) -> DepthState {
+        DepthState { test: self.config.depth_test, ..DepthState::default() }
+    }
 
+    // Sets the render state through the state cache, for drawing done outside of `draw` (e.g. sprite batches).
+    pub(crate) fn apply_render_state(&mut self, state: &RenderState) {
+        let default_depth = self.default_depth();
+        self.state_cache.apply(state, default_depth, self.config.face_culling);
+    }
+
+    // Stores a material in the renderer so that any number of objects can use it.
+    // The material's program is only deleted once, in `terminate`.
+    pub fn add_material(&mut self, material: Material) -> MaterialHandle {
         self.materials.push(material);
+
+        MaterialHandle::Material(self.materials.len() as u32 - 1u32)
+    }
+
+    // Creates an instance of a stored material that shares its program but can override
+    // attributes and textures. Instancing an instance copies its overrides.
+    pub fn create_material_instance(&mut self, base: MaterialHandle) -> MaterialHandle {
+        let instance = match base {
+            MaterialHandle::Material(material) => MaterialInstance::new(material),
+            MaterialHandle::Instance(instance) => self.material_instances[instance as usize].clone(),
+        };
+        self.material_instances.push(instance);
+
+        MaterialHandle::Instance(self.material_instances.len() as u32 - 1u32)
+    }
+
+    // Sets an attribute on a material instance that is applied every frame after the base material's attributes.
+    pub fn set_instance_attribute(&mut self, instance: MaterialHandle, n: &str, t: AttributeType) {
+        self.get_instance_mut(instance).set_attribute(n, t);
+    }
+
+    // Replaces the base material's texture at `index` for this instance only.
+    pub fn set_instance_texture(&mut self, instance: MaterialHandle, index: usize, texture: Texture) {
+        self.get_instance_mut(instance).set_texture(index, texture);
+    }
+
+    // Replaces the base material's render state for this instance only.
+    pub fn set_instance_render_state(&mut self, instance: MaterialHandle, render_state: RenderState) {
+        self.get_instance_mut(instance).set_render_state(render_state);
+    }
+
+    pub fn get_material(&self, material: MaterialHandle) -> &Material {
+        &self.materials[self.base_material(material) as usize]
+    }
+
+    pub fn get_material_mut(&mut self, material: MaterialHandle) -> &mut Material {
+        let base = self.base_material(material);
+        &mut self.materials[base as usize]
+    }
+
+    fn get_instance_mut(&mut self, instance: MaterialHandle) -> &mut MaterialInstance {
+        match instance {
+            MaterialHandle::Instance(i) => &mut self.material_instances[i as usize],
+            MaterialHandle::Material(_) => panic!("Expected a material instance, got a material!"),
+        }
+    }
+
+    // The render state of the object's instance if it overrides it, otherwise the one of its material.
+    fn object_render_state(&self, object: usize) -> &RenderState {
+        let instance = match self.object_materials[object] {
+            MaterialHandle::Instance(m) => self.material_instances[m as usize].get_render_state(),
+            MaterialHandle::Material(_) => None,
+        };
+
+        instance.unwrap_or_else(|| self.materials[self.base_material(self.object_materials[object]) as usize].get_render_state())
+    }
+
+    fn base_material(&self, material: MaterialHandle) -> u32 {
+        match material {
+            MaterialHandle::Material(i) => i,
+            MaterialHandle::Instance(i) => self.material_instances[i as usize].get_base(),
+        }
+    }
+
+    // Creates an object with the given vertex count, positions, colors, indices, and material.
+    // The material has to be stored in the renderer first, see `add_material`.
+    // The Object Manager will draw the object when given the chance using the `render` function.
+    pub fn create_object(&mut self,
+        vertices: Option<Vec<Vertex>>,
+        indices: Option<Vec<u32>>,
+        material: MaterialHandle) -> u32
+    {
+        let bounds = vertices.as_ref().and_then(|x| compute_bounds(x));
+        let buffers = Renderer::create_buffers(vertices, indices, None);
+        self.buffers.push(buffers);
+
+        self.object_materials.push(material);
+        self.object_uniforms.push(vec![]);
         self.attribute_queue.push(vec![]);
+        self.object_priorities.push(0);
+        self.object_sort_keys.push(None);
+        self.object_visible.push(true);
+        self.object_node_enabled.push(true);
+        self.object_layers.push(DEFAULT_LAYER);
+        self.object_transforms.push(Transform::identity());
+        self.object_node_matrices.push(None);
+        self.object_bounds.push(bounds);
+        self.object_manual_bounds.push(false);
+        self.object_primitive_modes.push(PrimitiveMode::Triangles);
+        self.object_lods.push(vec![]);
+        self.object_current_lods.push(0);
+        self.object_forced_lods.push(None);
+        self.object_skin_buffers.push(0);
+        self.object_tangent_buffers.push(0);
+        self.object_joint_matrices.push(vec![]);
+        self.object_morph_targets.push(None);
+        self.object_morph_weights.push(vec![]);
+        self.world_matrices.push(Matrix4::identity());
 
         self.buffers.len() as u32 - 1u32
     }
 
-    pub fn set_material_attribute(&mut self, object: u32, n: &str, t: AttributeType) {
-        self.attribute_queue[object as usize].push((n.to_string(), t));
+    pub fn set_object_primitive_mode(&mut self, object: u32, mode: PrimitiveMode) {
+        self.object_primitive_modes[object as usize] = mode;
     }
 
-    // Changes the vertex/index data of a given object.
-    pub fn change_object(&mut self, object: u32, vertices: Option<Vec<Vertex>>, indices: Option<Vec<u32>>) {
-        unsafe {
-            match vertices {
-                Some(vertices) => {
-                    gl::BindBuffer(gl::ARRAY_BUFFER, self.buffers[object as usize].vbo);
-                    gl::BufferSubData(gl::ARRAY_BUFFER, 0, vertices.len() as isize * FOUR_BYTES as isize * VERTEX_DATA_SIZE,
-                                      vertices.as_ptr() as *const c_void);
-                }
-                None => {}
-            }
+    pub fn get_object_primitive_mode(&self, object: u32) -> PrimitiveMode {
+        self.object_primitive_modes[object as usize]
+    }
 
-            match indices {
-                Some(indices) => {
-                    gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.buffers[object as usize].ibo);
-                    gl::BufferSubData(gl::ELEMENT_ARRAY_BUFFER, 0, indices.len() as isize * FOUR_BYTES as isize,
-                                      indices.as_ptr() as * const c_void);
+    // Hidden objects keep all of their data, they are just skipped while rendering.
+    pub fn set_object_visible(&mut self, object: u32, visible: bool) {
+        self.object_visible[object as usize] = visible;
+    }
 
-                    self.buffers[object as usize].index_size = indices.len() as i32;
-                }
-                None => {}
-            }
-        }
+    pub fn is_object_visible(&self, object: u32) -> bool {
+        self.object_visible[object as usize]
     }
 
-    // Draws all created objects using their buffers
-    pub fn render(&mut self, bg_color: (f32, f32, f32, f32)) {
-        unsafe {
-            gl::ClearColor(bg_color.0, bg_color.1, bg_color.2, bg_color.3);
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+    // The layers (bitmask) an object is on. It is drawn by `render_layers` if any of its layers are in the mask.
+    pub fn set_object_layers(&mut self, object: u32, layers: u32) {
+        self.object_layers[object as usize] = layers;
+    }
 
-            let mut i = 0usize;
-            for object in self.buffers.iter() {
-                gl::BindVertexArray(object.vao);
-                self.materials[i].use_material();
+    pub fn get_object_layers(&self, object: u32) -> u32 {
+        self.object_layers[object as usize]
+    }
 
-                for (attribute_name, attribute_type) in self.attribute_queue[i].iter() {
-                    set_attribute(self.materials[i].get_program_id(), (*attribute_name).clone(), (*attribute_type).clone());
-                }
-                self.attribute_queue[i] = Vec::new();
+    // Objects with a lower priority are always drawn before objects with a higher one, which can be used
+    // to layer things explicitly (skybox first, UI last, ...). Every object starts at 0.
+    pub fn set_object_priority(&mut self, object: u32, priority: i32) {
+        self.object_priorities[object as usize] = priority;
+    }
 
-                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, object.ibo);
-                gl::DrawElements(
-                    gl::TRIANGLES,
-                    object.index_size,
-                    gl::UNSIGNED_INT,
-                    ptr::null(),
-                );
+    pub fn get_object_priority(&self, object: u32) -> i32 {
+        self.object_priorities[object as usize]
+    }
 
-                i += 1;
-            }
-        }
+    // Sets the value objects are sorted by within the same priority, normally their view-space depth.
+    // Opaque objects are drawn with the smallest key first (front-to-back) and objects with a
+    // transparent render state with the largest key first (back-to-front).
+    // Without a key, the distance along the camera's view direction is used (or 0 if there is no camera).
+    pub fn set_object_sort_key(&mut self, object: u32, key: f32) {
+        self.object_sort_keys[object as usize] = Some(key);
     }
 
-    pub fn resize_viewport(&mut self, x: i32, y: i32) {
-        unsafe {
-            gl::Viewport(0, 0, x, y);
-        }
+    // Goes back to sorting the object by its view-space depth.
+    pub fn clear_object_sort_key(&mut self, object: u32) {
+        self.object_sort_keys[object as usize] = None;
     }
 
-    // This will destroy all buffers and free the occupied memory.
-    pub fn terminate(&mut self) {
-        let mut i = 0;
-        for buffers in self.buffers.iter() {
-            unsafe {
-                gl::DeleteVertexArrays(1, &buffers.vao);
-                gl::DeleteBuffers(1, &buffers.vbo);
-                gl::DeleteBuffers(1, &buffers.ibo);
+    pub fn get_object_sort_key(&self, object: u32) -> Option<f32> {
+        self.object_sort_keys[object as usize]
+    }
 
-                self.materials[i].delete();
-            }
+    // When sorting is disabled, objects are drawn in the order they were created in.
+    pub fn set_sorting(&mut self, sorting: bool) {
+        self.sorting = sorting;
+    }
 
-            i += 1;
+    // Places an object in the world. Its world matrix is supplied to its material as `u_model`
+    // (and the matching normal matrix as `u_normal_matrix`) if the shader declares them.
+    pub fn set_object_transform(&mut self, object: u32, transform: Transform) {
+        if transform.parent == Some(object) {
+            panic!("Object {} can't be its own parent!", object);
         }
+
+        self.object_transforms[object as usize] = transform;
     }
 
-    pub fn enable_vertex_attrib_ptr(index: u32, size: i32, stride: i32, offset: usize) {
-        unsafe {
-            gl::EnableVertexAttribArray(index);
+    pub fn get_object_transform(&self, object: u32) -> &Transform {
+        &self.object_transforms[object as usize]
+    }
+
+    pub fn get_object_transform_mut(&mut self, object: u32) -> &mut Transform {
+        &mut self.object_transforms[object as usize]
+    }
+
+    // Places the object relative to a world matrix instead of its transform's parent. This is how a `SceneGraph`
+    // moves the objects attached to its nodes, `None` goes back to using the parent.
+    pub fn set_object_node_matrix(&mut self, object: u32, matrix: Option<Matrix4>) {
+        self.object_node_matrices[object as usize] = matrix;
+    }
+
+    // Hides the object while the `SceneGraph` node it is attached to is disabled. Objects are only drawn when
+    // this and `set_object_visible` are both true.
+    pub fn set_object_node_enabled(&mut self, object: u32, enabled: bool) {
+        self.object_node_enabled[object as usize] = enabled;
+    }
+
+    pub fn is_object_node_enabled(&self, object: u32) -> bool {
+        self.object_node_enabled[object as usize]
+    }
+
+    // The object's transform combined with the transforms of all of its parents.
+    pub fn get_object_world_matrix(&self, object: u32) -> Matrix4 {
+        let mut current = object as usize;
+        let mut matrix = Matrix4::identity();
+
+        let mut depth = 0;
+        loop {
+            depth += 1;
+            if depth > self.object_transforms.len() {
+                panic!("The parents of object {} form a cycle!", object);
+            }
+
+            let transform = &self.object_transforms[current];
+            matrix = transform.get_local_matrix() * matrix;
+
+            match (self.object_node_matrices[current], transform.parent) {
+                (Some(node), _) => return node * matrix,
+                (None, Some(parent)) => current = parent as usize,
+                (None, None) => return matrix,
+            }
+        }
+    }
+
+    // Replaces the bounds computed from the vertex data, e.g. for objects that are animated in their shader
+    // and move outside of them. Objects with `None` are never culled. The bounds are kept when `change_object`
+    // changes the vertices.
+    pub fn set_object_bounds(&mut self, object: u32, bounds: Option<Aabb>) {
+        self.object_manual_bounds[object as usize] = true;
+        self.object_bounds[object as usize] = bounds.map(|aabb| {
+            let corners = [aabb.min, aabb.max];
+            (aabb, BoundingSphere::from_points(&corners).unwrap())
+        });
+    }
+
+    // The bounds in the object's local space, grown to fit its morph targets.
+    pub fn get_object_bounds(&self, object: u32) -> Option<Aabb> {
+        self.culling_bounds(object as usize).map(|(aabb, _)| aabb)
+    }
+
+    // The bounds transformed by the object's world matrix.
+    pub fn get_object_world_bounds(&self, object: u32) -> Option<Aabb> {
+        self.get_object_bounds(object).map(|aabb| aabb.transform(&self.get_object_world_matrix(object)))
+    }
+
+    // Objects completely outside of the camera's view are skipped while drawing. Only works with a camera.
+    pub fn set_frustum_culling(&mut self, frustum_culling: bool) {
+        self.frustum_culling = frustum_culling;
+    }
+
+    // Reset by `begin_frame`, so it covers every `draw` of the current (or, after `end_frame`, the last) frame.
+    pub fn get_culling_stats(&self) -> CullingStats {
+        self.culling_stats
+    }
+
+    // Adds a coarser version of an object that is drawn instead of it from `threshold` on (see `set_lod_metric`).
+    // Levels have to be added from fine to coarse; returns the new level (the object itself is level 0).
+    // Without `vertices` the level uses the object's vertices, e.g. for indices from `mesh_processing::generate_lods`.
+    // Everything except the vertex/index data (material, transform, bounds...) is shared with the object.
+    pub fn add_object_lod(&mut self, object: u32, vertices: Option<Vec<Vertex>>, indices: Option<Vec<u32>>, threshold: f32) -> usize {
+        let shared_vbo = self.buffers[object as usize].vbo;
+        let shares_vertices = vertices.is_none();
+        let mut buffers = Renderer::create_buffers(vertices, indices, Some(shared_vbo));
+        if shares_vertices {
+            buffers.vertex_count = self.buffers[object as usize].vertex_count;
+        }
+        let skin = self.object_skin_buffers[object as usize];
+        if shares_vertices && skin != 0 {
+            Renderer::bind_skin(buffers.vao, skin);
+        }
+        let tangents = self.object_tangent_buffers[object as usize];
+        if shares_vertices && tangents != 0 {
+            Renderer::bind_tangents(buffers.vao, tangents);
+        }
+        self.object_lods[object as usize].push((buffers, threshold));
+
+        self.object_lods[object as usize].len()
+    }
+
+    pub fn set_object_lod_threshold(&mut self, object: u32, level: usize, threshold: f32) {
+        if level == 0 {
+            panic!("Level 0 is the object itself and has no threshold!");
+        }
+        self.object_lods[object as usize][level - 1].1 = threshold;
+    }
+
+    // Number of levels including the object itself, so always at least 1.
+    pub fn get_object_lod_count(&self, object: u32) -> usize {
+        self.object_lods[object as usize].len() + 1
+    }
+
+    // The level the object was drawn with last.
+    pub fn get_object_lod(&self, object: u32) -> usize {
+        self.object_current_lods[object as usize]
+    }
+
+    // Always draws the given level instead of selecting one, e.g. to check how a level looks. `None` selects again.
+    pub fn force_object_lod(&mut self, object: u32, level: Option<usize>) {
+        if let Some(level) = level {
+            if level >= self.get_object_lod_count(object) {
+                panic!("Object {} has no level {}!", object, level);
+            }
+        }
+        self.object_forced_lods[object as usize] = level;
+    }
+
+    // Deletes all levels of detail of an object, leaving only the object itself.
+    pub fn remove_object_lods(&mut self, object: u32) {
+        for (buffers, _) in self.object_lods[object as usize].drain(..) {
+            Renderer::delete_buffers(&buffers);
+        }
+        self.object_current_lods[object as usize] = 0;
+        self.object_forced_lods[object as usize] = None;
+    }
+
+    // Applies to the thresholds of all objects. Defaults to `LodMetric::Distance`.
+    pub fn set_lod_metric(&mut self, metric: LodMetric) {
+        self.lod_metric = metric;
+    }
+
+    pub fn get_lod_metric(&self) -> LodMetric {
+        self.lod_metric
+    }
+
+    // How far (as a fraction of the threshold, e.g. 0.1) an object has to move past a threshold before its level
+    // changes, so it doesn't switch back and forth when it is right on it. Defaults to 0.0.
+    pub fn set_lod_hysteresis(&mut self, hysteresis: f32) {
+        self.lod_hysteresis = hysteresis.max(0.0);
+    }
+
+    pub fn get_lod_hysteresis(&self) -> f32 {
+        self.lod_hysteresis
+    }
+
+    // Makes an object skinned, with exactly one `SkinVertex` per vertex. Replaces the skin it had before.
+    // Levels of detail that use the object's vertices are skinned too.
+    // The object's bounds stay those of the vertices as they are, so objects that move far from them
+    // should get bounds that fit all of their poses with `set_object_bounds`.
+    pub fn set_object_skin(&mut self, object: u32, skin: Vec<SkinVertex>) {
+        let i = object as usize;
+        if skin.len() != self.buffers[i].vertex_count {
+            panic!("A skin needs one SkinVertex for each vertex of the object!");
+        }
+
+        unsafe {
+            if self.object_skin_buffers[i] == 0 {
+                gl::GenBuffers(1, &mut self.object_skin_buffers[i]);
+            }
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.object_skin_buffers[i]);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                skin.len() as isize * FOUR_BYTES as isize * SKIN_VERTEX_DATA_SIZE,
+                skin.as_ptr() as *const c_void,
+                gl::DYNAMIC_DRAW,
+            );
+        }
+
+        Renderer::bind_skin(self.buffers[i].vao, self.object_skin_buffers[i]);
+        for (buffers, _) in self.object_lods[i].iter().filter(|(x, _)| x.vbo == 0) {
+            Renderer::bind_skin(buffers.vao, self.object_skin_buffers[i]);
+        }
+    }
+
+    pub fn is_object_skinned(&self, object: u32) -> bool {
+        self.object_skin_buffers[object as usize] != 0
+    }
+
+    // Gives an object exactly one tangent per vertex for normal mapping, usually from `mesh_processing::compute_tangents`.
+    // They are in their own buffer next to the vertices, as attribute 7 (`vec4`, with the bitangent sign in `w`).
+    // Replaces the tangents it had before; levels of detail that use the object's vertices get them too.
+    pub fn set_object_tangents(&mut self, object: u32, tangents: &[[f32; 4]]) {
+        let i = object as usize;
+        if tangents.len() != self.buffers[i].vertex_count {
+            panic!("An object needs one tangent for each of its vertices!");
+        }
+
+        unsafe {
+            if self.object_tangent_buffers[i] == 0 {
+                gl::GenBuffers(1, &mut self.object_tangent_buffers[i]);
+            }
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.object_tangent_buffers[i]);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                tangents.len() as isize * FOUR_BYTES as isize * TANGENT_DATA_SIZE,
+                tangents.as_ptr() as *const c_void,
+                gl::STATIC_DRAW,
+            );
+        }
+
+        Renderer::bind_tangents(self.buffers[i].vao, self.object_tangent_buffers[i]);
+        for (buffers, _) in self.object_lods[i].iter().filter(|(x, _)| x.vbo == 0) {
+            Renderer::bind_tangents(buffers.vao, self.object_tangent_buffers[i]);
+        }
+    }
+
+    pub fn has_object_tangents(&self, object: u32) -> bool {
+        self.object_tangent_buffers[object as usize] != 0
+    }
+
+    // Sets the matrices skinned vertices are moved with, usually `Skeleton::joint_matrices` every frame.
+    // They are uploaded as `uniform mat4 u_joint_matrices[N]` whenever the object is drawn; `N` has to be at least
+    // the number of matrices. Objects without any get identity matrices, so they can share a skinning shader.
+    pub fn set_object_joint_matrices(&mut self, object: u32, matrices: &[Matrix4]) {
+        let data = &mut self.object_joint_matrices[object as usize];
+        data.clear();
+        for matrix in matrices {
+            data.extend_from_slice(&matrix.data);
+        }
+    }
+
+    // Gives an object morph targets, replacing the ones it had. Every target needs a delta for each vertex of the object.
+    // All weights start at 0.0, so the object looks the same until `set_object_morph_weights`.
+    // The bounds grow to fit every combination of targets with weights between 0.0 and 1.0.
+    // Levels of detail that use the object's vertices are morphed too, levels with their own vertices are drawn unmorphed.
+    pub fn set_object_morph_targets(&mut self, object: u32, targets: Vec<MorphTarget>) {
+        let i = object as usize;
+        self.delete_morph_targets(i);
+        self.object_morph_weights[i] = vec![0.0; targets.len()];
+        if targets.is_empty() {
+            return;
+        }
+
+        let vertex_count = targets[0].position_deltas.len();
+        for target in targets.iter() {
+            if target.position_deltas.len() != vertex_count
+                || (!target.normal_deltas.is_empty() && target.normal_deltas.len() != vertex_count) {
+                panic!("Every morph target of an object needs a delta for each vertex!");
+            }
+        }
+
+        // RGB32F texture buffers need OpenGL 4.0, so every delta is padded to four floats.
+        let mut data: Vec<f32> = Vec::with_capacity(targets.len() * vertex_count * 8);
+        let mut growth = (Vector3::zero(), 0.0);
+        for target in targets.iter() {
+            let mut largest = (Vector3::zero(), 0.0f32);
+            for (v, position) in target.position_deltas.iter().enumerate() {
+                let normal = target.normal_deltas.get(v).copied().unwrap_or((0.0, 0.0, 0.0));
+                data.extend_from_slice(&[position.0, position.1, position.2, 0.0, normal.0, normal.1, normal.2, 0.0]);
+
+                let delta = Vector3::from(*position);
+                largest.0 = largest.0.max(Vector3::new(delta.x.abs(), delta.y.abs(), delta.z.abs()));
+                largest.1 = largest.1.max(delta.length());
+            }
+            growth = (growth.0 + largest.0, growth.1 + largest.1);
+        }
+
+        let (mut buffer, mut texture) = (0u32, 0u32);
+        unsafe {
+            gl::GenBuffers(1, &mut buffer);
+            gl::BindBuffer(gl::TEXTURE_BUFFER, buffer);
+            gl::BufferData(
+                gl::TEXTURE_BUFFER,
+                (FOUR_BYTES * data.len()) as isize,
+                data.as_ptr() as *const c_void,
+                gl::STATIC_DRAW,
+            );
+
+            gl::GenTextures(1, &mut texture);
+            gl::BindTexture(gl::TEXTURE_BUFFER, texture);
+            gl::TexBuffer(gl::TEXTURE_BUFFER, gl::RGBA32F, buffer);
+        }
+
+        self.object_morph_targets[i] = Some(MorphTargetBuffers {
+            buffer,
+            texture,
+            target_count: targets.len() as i32,
+            vertex_count: vertex_count as i32,
+            growth,
+        });
+    }
+
+    pub fn get_object_morph_target_count(&self, object: u32) -> usize {
+        self.object_morph_weights[object as usize].len()
+    }
+
+    // Sets how much of each morph target is applied, usually every frame while animating.
+    // Targets without a weight in `weights` are set to 0.0.
+    pub fn set_object_morph_weights(&mut self, object: u32, weights: &[f32]) {
+        let current = &mut self.object_morph_weights[object as usize];
+        if weights.len() > current.len() {
+            panic!("Object {} has {} morph targets, got {} weights!", object, current.len(), weights.len());
+        }
+
+        current.iter_mut().for_each(|x| *x = 0.0);
+        current[..weights.len()].copy_from_slice(weights);
+    }
+
+    pub fn get_object_morph_weights(&self, object: u32) -> &[f32] {
+        &self.object_morph_weights[object as usize]
+    }
+
+    // The bounds of the vertex data (or the ones set with `set_object_bounds`) are kept as they are,
+    // so replacing the morph targets or changing the vertices doesn't grow them again.
+    fn culling_bounds(&self, object: usize) -> Option<(Aabb, BoundingSphere)> {
+        let (aabb, sphere) = self.object_bounds[object]?;
+
+        Some(match self.object_morph_targets[object].as_ref() {
+            Some(MorphTargetBuffers { growth, .. }) => (
+                Aabb::new(aabb.min - growth.0, aabb.max + growth.0),
+                BoundingSphere { radius: sphere.radius + growth.1, ..sphere },
+            ),
+            None => (aabb, sphere),
+        })
+    }
+
+    fn delete_morph_targets(&mut self, object: usize) {
+        if let Some(morph_targets) = self.object_morph_targets[object].take() {
+            unsafe {
+                gl::DeleteTextures(1, &morph_targets.texture);
+                gl::DeleteBuffers(1, &morph_targets.buffer);
+            }
+        }
+    }
+
+    // The camera used for `u_view` and `u_projection`. Without one, those uniforms are left to the application.
+    pub fn set_camera(&mut self, camera: Camera) {
+        self.camera = Some(camera);
+    }
+
+    pub fn remove_camera(&mut self) {
+        self.camera = None;
+    }
+
+    pub fn get_camera(&self) -> Option<&Camera> {
+        self.camera.as_ref()
+    }
+
+    pub fn get_camera_mut(&mut self) -> Option<&mut Camera> {
+        self.camera.as_mut()
+    }
+
+    // Uniforms are applied in this order, each layer overriding the previous one:
+    // built-in uniforms (`u_model`, `u_view`, ...) -> material attributes -> material instance
+    // -> object uniforms -> frame uniforms.
+
+    // Sets a uniform for one object that is kept (and set every frame) until it is changed or removed.
+    // Pointer-based attribute types (`Matrix4`, `VecFloat3`, ...) must stay valid for as long as they are set.
+    pub fn set_object_uniform(&mut self, object: u32, n: &str, t: AttributeType) {
+        upsert_attribute(&mut self.object_uniforms[object as usize], n, t);
+    }
+
+    pub fn remove_object_uniform(&mut self, object: u32, n: &str) {
+        self.object_uniforms[object as usize].retain(|(name, _)| name != n);
+    }
+
+    // Returns the persistent uniform set with `set_object_uniform`.
+    pub fn get_object_uniform(&self, object: u32, n: &str) -> Option<AttributeType> {
+        find_attribute(&self.object_uniforms[object as usize], n)
+    }
+
+    // Sets a uniform for one object for the next frame only. It is cleared after `render`.
+    pub fn set_frame_uniform(&mut self, object: u32, n: &str, t: AttributeType) {
+        upsert_attribute(&mut self.attribute_queue[object as usize], n, t);
+    }
+
+    // Same as `set_frame_uniform`.
+    pub fn set_material_attribute(&mut self, object: u32, n: &str, t: AttributeType) {
+        self.set_frame_uniform(object, n, t);
+    }
+
+    // Returns the value the uniform will have when the object is drawn next, looking through
+    // every layer from the frame uniforms down to the base material.
+    pub fn get_current_uniform(&self, object: u32, n: &str) -> Option<AttributeType> {
+        let material = self.object_materials[object as usize];
+        let instance = match material {
+            MaterialHandle::Instance(i) => self.material_instances[i as usize].get_attribute(n),
+            MaterialHandle::Material(_) => None,
+        };
+
+        find_attribute(&self.attribute_queue[object as usize], n)
+            .or_else(|| find_attribute(&self.object_uniforms[object as usize], n))
+            .or(instance)
+            .or_else(|| self.get_material(material).get_attribute(n))
+    }
+
+    // Changes the vertex/index data of a given object. The bounds are computed again, unless they were set with `set_object_bounds`.
+    pub fn change_object(&mut self, object: u32, vertices: Option<Vec<Vertex>>, indices: Option<Vec<u32>>) {
+        unsafe {
+            match vertices {
+                Some(vertices) => {
+                    if !self.object_manual_bounds[object as usize] {
+                        self.object_bounds[object as usize] = compute_bounds(&vertices);
+                    }
+
+                    gl::BindBuffer(gl::ARRAY_BUFFER, self.buffers[object as usize].vbo);
+                    gl::BufferSubData(gl::ARRAY_BUFFER, 0, vertices.len() as isize * FOUR_BYTES as isize * VERTEX_DATA_SIZE,
+                                      vertices.as_ptr() as *const c_void);
+                }
+                None => {}
+            }
+
+            match indices {
+                Some(indices) => {
+                    gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.buffers[object as usize].ibo);
+                    gl::BufferSubData(gl::ELEMENT_ARRAY_BUFFER, 0, indices.len() as isize * FOUR_BYTES as isize,
+                                      indices.as_ptr() as * const c_void);
+
+                    self.buffers[object as usize].index_size = indices.len() as i32;
+                }
+                None => {}
+            }
+        }
+    }
+
+    // Draws all created objects using their buffers
+    pub fn render(&mut self, bg_color: (f32, f32, f32, f32)) {
+        self.render_layers(bg_color, ALL_LAYERS);
+    }
+
+    // Draws every visible object that is on at least one of the layers in `layer_mask`.
+    pub fn render_layers(&mut self, bg_color: (f32, f32, f32, f32), layer_mask: u32) {
+        self.begin_frame();
+        self.set_clear_color(bg_color);
+        self.clear(ClearFlags::ALL);
+        self.draw(&DrawFilter::Layers(layer_mask));
+        self.end_frame();
+    }
+
+    // `render` does all of the following in one go. Calling them separately allows drawing several
+    // passes per frame, e.g. the scene and then the UI on top of it with a different projection:
+    //
+    // renderer.begin_frame();
+    // renderer.clear(ClearFlags::COLOR | ClearFlags::DEPTH);
+    // renderer.draw(&DrawFilter::Layers(WORLD));
+    // renderer.clear(ClearFlags::DEPTH);
+    // renderer.draw(&DrawFilter::Layers(UI));
+    // renderer.end_frame();
+
+    // Starts a new frame. Frame uniforms are kept until `end_frame`, so they apply to every draw in between.
+    // Draws go to whatever framebuffer is bound, so applications can bind their own before rendering.
+    pub fn begin_frame(&mut self) {
+        self.culling_stats = CullingStats::default();
+    }
+
+    // Clears the given buffers of the current target. The color is set with `set_clear_color`,
+    // depth and stencil with `set_clear_depth` and `set_clear_stencil`.
+    pub fn clear(&mut self, flags: ClearFlags) {
+        if flags.is_empty() {
+            return;
+        }
+
+        self.state_cache.prepare_clear();
+        unsafe {
+            gl::Clear(flags.bits());
+        }
+    }
+
+    pub fn set_clear_color(&mut self, color: (f32, f32, f32, f32)) {
+        unsafe {
+            gl::ClearColor(color.0, color.1, color.2, color.3);
+        }
+        self.clear_color = color;
+    }
+
+    // Ends the frame and forgets the frame uniforms, even those of objects that weren't drawn.
+    pub fn end_frame(&mut self) {
+        for queue in self.attribute_queue.iter_mut() {
+            queue.clear();
+        }
+    }
+
+    // Draws one pass: binds its target, sets its viewport and scissor, clears and draws its objects.
+    // The framebuffer and viewport that were current before the pass are restored afterwards.
+    // Has to be called between `begin_frame` and `end_frame`.
+    pub fn render_pass(&mut self, pass: &RenderPass) {
+        let mut previous_framebuffer = 0i32;
+        let mut previous_viewport = [0i32; 4];
+        unsafe {
+            gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut previous_framebuffer);
+            gl::GetIntegerv(gl::VIEWPORT, previous_viewport.as_mut_ptr());
+
+            if let RenderTarget::Framebuffer(framebuffer) = pass.target {
+                gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+            }
+            if let Some(viewport) = pass.viewport {
+                gl::Viewport(viewport.x, viewport.y, viewport.width, viewport.height);
+            }
+        }
+        if let Some(scissor) = pass.scissor {
+            self.push_scissor(scissor);
+        }
+
+        if !pass.clear.is_empty() {
+            let clear_color = self.clear_color;
+            self.set_clear_color(pass.clear_color);
+            self.clear(pass.clear);
+            self.set_clear_color(clear_color);
+        }
+        self.draw(&pass.objects);
+
+        if pass.scissor.is_some() {
+            self.pop_scissor();
+        }
+        unsafe {
+            if pass.viewport.is_some() {
+                let [x, y, width, height] = previous_viewport;
+                gl::Viewport(x, y, width, height);
+            }
+            if pass.target != RenderTarget::Default {
+                gl::BindFramebuffer(gl::FRAMEBUFFER, previous_framebuffer as u32);
+            }
+        }
+    }
+
+    pub fn get_debug_draw(&self) -> &DebugDraw {
+        &self.debug_draw
+    }
+
+    // Lines, boxes, spheres, etc. added here are drawn by the next `draw_debug`.
+    pub fn get_debug_draw_mut(&mut self) -> &mut DebugDraw {
+        &mut self.debug_draw
+    }
+
+    // Draws the lines of the debug draw with the camera and then counts their durations down by `delta_time`
+    // seconds. Usually called once per frame after the scene, so that depth tested lines are hidden behind it.
+    // Without a camera the lines are in clip space.
+    pub fn draw_debug(&mut self, delta_time: f32) {
+        let (data, depth_tested) = self.debug_draw.vertex_data();
+        if !data.is_empty() {
+            let buffers = self.debug_buffers.get_or_insert_with(|| unsafe {
+                let mut vao = 0u32;
+                gl::GenVertexArrays(1, &mut vao);
+                gl::BindVertexArray(vao);
+
+                let mut vbo = 0u32;
+                gl::GenBuffers(1, &mut vbo);
+                gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+                let stride = (DEBUG_VERTEX_DATA_SIZE * FOUR_BYTES) as i32;
+                Renderer::enable_vertex_attrib_ptr(0, 3, stride, 0);
+                Renderer::enable_vertex_attrib_ptr(1, 4, stride, 3 * FOUR_BYTES);
+
+                let material = Material::from_shaders(DEBUG_VERTEX_SHADER, DEBUG_FRAGMENT_SHADER, vec![], vec![]);
+
+                DebugBuffers { vao, vbo, material }
+            });
+
+            let view = self.camera.map(|camera| camera.view_matrix()).unwrap_or_else(Matrix4::identity);
+            let projection = self.camera.map(|camera| camera.projection_matrix()).unwrap_or_else(Matrix4::identity);
+            buffers.material.bind();
+            buffers.material.apply_builtin(BuiltinUniform::View, AttributeType::Matrix4(view.as_ptr()));
+            buffers.material.apply_builtin(BuiltinUniform::Projection, AttributeType::Matrix4(projection.as_ptr()));
+
+            unsafe {
+                gl::BindVertexArray(buffers.vao);
+                gl::BindBuffer(gl::ARRAY_BUFFER, buffers.vbo);
+                gl::BufferData(
+                    gl::ARRAY_BUFFER,
+                    (FOUR_BYTES * data.len()) as isize,
+                    data.as_ptr() as *const c_void,
+                    gl::STREAM_DRAW,
+                );
+            }
+
+            // Depth tested lines don't write depth, so they can't hide each other or anything drawn later.
+            let vertex_count = (data.len() / DEBUG_VERTEX_DATA_SIZE) as i32;
+            let depth_tested = depth_tested as i32 * 2;
+            let states = [
+                (0, depth_tested, DepthState::new(true, false, CompareFunction::LessEqual)),
+                (depth_tested, vertex_count - depth_tested, DepthState::new(false, false, CompareFunction::Always)),
+            ];
+            for (first, count, depth) in states.iter() {
+                if *count == 0 {
+                    continue;
+                }
+
+                self.state_cache.apply(&RenderState {
+                    blend: Some(BlendState::alpha()),
+                    depth: Some(*depth),
+                    ..RenderState::default()
+                }, self.default_depth(), self.config.face_culling);
+                unsafe {
+                    gl::DrawArrays(gl::LINES, *first, *count);
+                }
+            }
+        }
+
+        self.debug_draw.update(delta_time);
+    }
+
+    // Draws the objects selected by `filter` without clearing anything.
+    pub fn draw(&mut self, filter: &DrawFilter) {
+        unsafe {
+            let default_depth = self.default_depth();
+            let view = self.camera.map(|camera| camera.view_matrix());
+            let projection = self.camera.map(|camera| camera.projection_matrix());
+            // Objects without a skin get the default attribute values (0, 0, 0, 1) for their joints and weights,
+            // so these keep them in place instead of moving them with the joints of the last skinned object.
+            let unskinned_joints = [Matrix4::identity().data; 2];
+
+            self.build_render_queue(filter);
+
+            // The program and textures only have to be bound again when the material changes. Instances
+            // restore the base material's textures and uniforms after their draw.
+            let mut bound_material: Option<u32> = None;
+            for item in self.render_queue.items.iter() {
+                let i = item.object;
+                let object = match self.object_current_lods[i] {
+                    0 => &self.buffers[i],
+                    level => &self.object_lods[i][level - 1].0,
+                };
+                gl::BindVertexArray(object.vao);
+
+                let material = &self.materials[item.material as usize];
+                let render_state = *self.object_render_state(i);
+                self.state_cache.apply(&render_state, default_depth, self.config.face_culling);
+                if bound_material != Some(item.material) {
+                    material.bind();
+                }
+
+                // The matrices only have to live until the uniforms are uploaded.
+                let model = self.world_matrices[i];
+                let normal = Matrix3::normal_matrix(&model);
+                material.apply_builtin(BuiltinUniform::Model, AttributeType::Matrix4(model.as_ptr()));
+                material.apply_builtin(BuiltinUniform::NormalMatrix, AttributeType::Matrix3(normal.as_ptr()));
+                let joint_matrices = &self.object_joint_matrices[i];
+                if !joint_matrices.is_empty() {
+                    material.apply_builtin(BuiltinUniform::JointMatrices,
+                        AttributeType::VecMatrix4((joint_matrices.len() / 16) as i32, joint_matrices.as_ptr()));
+                } else {
+                    material.apply_builtin(BuiltinUniform::JointMatrices,
+                        AttributeType::VecMatrix4(unskinned_joints.len() as i32, unskinned_joints.as_ptr() as *const f32));
+                }
+                // The deltas are per vertex of the object, so levels of detail with their own vertices can't use them.
+                let morph_targets = self.object_morph_targets[i].as_ref()
+                    .filter(|_| self.object_current_lods[i] == 0 || object.vbo == 0);
+                if let Some(morph_targets) = morph_targets {
+                    gl::ActiveTexture(gl::TEXTURE0 + MORPH_TARGET_TEXTURE_UNIT);
+                    gl::BindTexture(gl::TEXTURE_BUFFER, morph_targets.texture);
+                    let weights = &self.object_morph_weights[i];
+                    material.apply_builtin(BuiltinUniform::MorphTargets, AttributeType::Int1(MORPH_TARGET_TEXTURE_UNIT as i32));
+                    material.apply_builtin(BuiltinUniform::MorphTargetCount, AttributeType::Int1(morph_targets.target_count));
+                    material.apply_builtin(BuiltinUniform::MorphVertexCount, AttributeType::Int1(morph_targets.vertex_count));
+                    material.apply_builtin(BuiltinUniform::MorphWeights,
+                        AttributeType::VecFloat1(weights.len() as i32, weights.as_ptr()));
+                } else {
+                    // Otherwise a shared morphing shader would keep using the targets of the last morphed object.
+                    gl::ActiveTexture(gl::TEXTURE0 + MORPH_TARGET_TEXTURE_UNIT);
+                    gl::BindTexture(gl::TEXTURE_BUFFER, 0);
+                    material.apply_builtin(BuiltinUniform::MorphTargetCount, AttributeType::Int1(0));
+                }
+                if let (Some(view), Some(projection)) = (view.as_ref(), projection.as_ref()) {
+                    material.apply_builtin(BuiltinUniform::View, AttributeType::Matrix4(view.as_ptr()));
+                    material.apply_builtin(BuiltinUniform::Projection, AttributeType::Matrix4(projection.as_ptr()));
+                }
+
+                material.apply_attributes();
+                bound_material = Some(item.material);
+
+                if let MaterialHandle::Instance(m) = self.object_materials[i] {
+                    self.material_instances[m as usize].apply(material);
+                }
+
+                for (attribute_name, attribute_type) in self.object_uniforms[i].iter() {
+                    material.apply_attribute(attribute_name, *attribute_type);
+                }
+                for (attribute_name, attribute_type) in self.attribute_queue[i].iter() {
+                    material.apply_attribute(attribute_name, *attribute_type);
+                }
+
+                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, object.ibo);
+                gl::DrawElements(
+                    self.object_primitive_modes[i].to_gl(),
+                    object.index_size,
+                    gl::UNSIGNED_INT,
+                    ptr::null(),
+                );
+
+                if let MaterialHandle::Instance(m) = self.object_materials[i] {
+                    self.material_instances[m as usize].restore(material);
+                }
+            }
+        }
+    }
+
+    fn build_render_queue(&mut self, filter: &DrawFilter) {
+        self.render_queue.clear();
+        let view = self.camera.map(|camera| camera.view_matrix());
+        let frustum = match self.camera {
+            Some(camera) if self.frustum_culling => Some(Frustum::from_matrix(&camera.view_projection_matrix())),
+            _ => None,
+        };
+
+        let objects = match filter {
+            DrawFilter::Objects(objects) => objects.iter().map(|x| *x as usize).collect::<Vec<_>>(),
+            _ => (0..self.buffers.len()).collect::<Vec<_>>(),
+        };
+
+        for i in objects {
+            if !self.object_visible[i] || !self.object_node_enabled[i] {
+                continue;
+            }
+            if let DrawFilter::Layers(layer_mask) = filter {
+                if self.object_layers[i] & layer_mask == 0 {
+                    continue;
+                }
+            }
+
+            self.world_matrices[i] = self.get_object_world_matrix(i as u32);
+            let world_bounds = self.culling_bounds(i).map(|(aabb, sphere)|
+                (aabb.transform(&self.world_matrices[i]), sphere.transform(&self.world_matrices[i])));
+
+            if let (Some(frustum), Some((aabb, sphere))) = (frustum.as_ref(), world_bounds.as_ref()) {
+                self.culling_stats.tested += 1;
+                // The sphere is cheaper to test, the box is tighter for long and thin objects.
+                if !frustum.intersects_sphere(sphere) || !frustum.intersects_aabb(aabb) {
+                    self.culling_stats.culled += 1;
+                    continue;
+                }
+            }
+            self.culling_stats.drawn += 1;
+
+            // Objects without bounds can't be measured and without a camera there's nothing to measure from.
+            self.object_current_lods[i] = match (self.object_forced_lods[i], self.camera, world_bounds) {
+                (Some(level), _, _) => level,
+                (None, _, _) if self.object_lods[i].is_empty() => 0,
+                (None, Some(camera), Some((_, sphere))) => {
+                    let thresholds = self.object_lods[i].iter().map(|(_, x)| *x).collect::<Vec<_>>();
+                    let value = lod::lod_value(self.lod_metric, &camera, &sphere);
+                    lod::select_lod(self.lod_metric, value, &thresholds, self.object_current_lods[i], self.lod_hysteresis)
+                },
+                (None, _, _) => 0,
+            };
+
+            // The camera looks down -Z, so the depth in front of it is the negated view-space z.
+            let center = match world_bounds {
+                Some((_, sphere)) => sphere.center,
+                None => self.world_matrices[i].get_translation(),
+            };
+            let depth = self.object_sort_keys[i].unwrap_or_else(|| match view {
+                Some(view) => -view.transform_point(center).z,
+                None => 0.0,
+            });
+
+            let base = self.base_material(self.object_materials[i]);
+            let material = &self.materials[base as usize];
+
+            self.render_queue.push(DrawItem {
+                object: i,
+                priority: self.object_priorities[i],
+                transparent: self.object_render_state(i).is_transparent(),
+                program: material.get_pipeline_id().unwrap_or_else(|| material.get_program_id()),
+                material: base,
+                depth,
+            });
+        }
+
+        if self.sorting {
+            self.render_queue.sort();
+        }
+    }
+
+    // The renderer skips state changes it thinks are redundant. If you change blending, depth, stencil, etc.
+    // with your own OpenGL calls, call this so that everything is set again on the next draw.
+    pub fn reset_state_cache(&mut self) {
+        self.state_cache.invalidate();
+    }
+
+    // Sets the viewport to the whole window. Assumes the window and framebuffer are the same size,
+    // use `resize` on HiDPI displays where they aren't.
+    pub fn resize_viewport(&mut self, x: i32, y: i32) {
+        self.resize((x, y), (x, y));
+    }
+
+    // Should be called whenever the window is resized, with the window size in screen coordinates and
+    // the framebuffer size in pixels (e.g. `window.get_size()` and `window.get_framebuffer_size()` in glfw-rs).
+    // The viewport is set to cover the whole framebuffer and the camera's aspect ratio is updated to match it.
+    pub fn resize(&mut self, window_size: (i32, i32), framebuffer_size: (i32, i32)) {
+        self.window_size = window_size;
+        self.framebuffer_size = framebuffer_size;
+        if let Some(camera) = self.camera.as_mut() {
+            if framebuffer_size.1 > 0 {
+                camera.set_aspect(framebuffer_size.0 as f32 / framebuffer_size.1 as f32);
+            }
+        }
+        self.set_viewport(Rect::new(0, 0, framebuffer_size.0, framebuffer_size.1));
+    }
+
+    pub fn get_window_size(&self) -> (i32, i32) {
+        self.window_size
+    }
+
+    pub fn get_framebuffer_size(&self) -> (i32, i32) {
+        self.framebuffer_size
+    }
+
+    // How many framebuffer pixels there are per screen coordinate on each axis (2.0 on most HiDPI displays).
+    pub fn get_content_scale(&self) -> (f32, f32) {
+        if self.window_size.0 == 0 || self.window_size.1 == 0 {
+            return (1.0, 1.0);
+        }
+
+        (self.framebuffer_size.0 as f32 / self.window_size.0 as f32,
+         self.framebuffer_size.1 as f32 / self.window_size.1 as f32)
+    }
+
+    // Converts a rectangle in screen coordinates (like mouse positions or UI layouts) into framebuffer pixels.
+    pub fn to_framebuffer_rect(&self, rect: Rect) -> Rect {
+        let (x, y) = self.get_content_scale();
+
+        Rect::new(
+            (rect.x as f32 * x).round() as i32,
+            (rect.y as f32 * y).round() as i32,
+            (rect.width as f32 * x).round() as i32,
+            (rect.height as f32 * y).round() as i32,
+        )
+    }
+
+    // Sets the viewport (in framebuffer pixels) used by every following draw, e.g. one half of the window for split-screen.
+    pub fn set_viewport(&mut self, viewport: Rect) {
+        unsafe {
+            gl::Viewport(viewport.x, viewport.y, viewport.width, viewport.height);
+        }
+        self.viewport = Some(viewport);
+    }
+
+    pub fn get_viewport(&self) -> Option<Rect> {
+        self.viewport
+    }
+
+    // Restricts every following draw and clear to the rectangle (in framebuffer pixels). Scissors nest:
+    // the pushed rectangle is intersected with the current one, so UI elements can't draw outside their parents.
+    pub fn push_scissor(&mut self, scissor: Rect) {
+        let scissor = match self.scissor_stack.last() {
+            Some(current) => current.intersect(&scissor),
+            None => scissor,
+        };
+        self.scissor_stack.push(scissor);
+        self.apply_scissor();
+    }
+
+    // Goes back to the previous scissor rectangle, or disables scissoring if there is none.
+    pub fn pop_scissor(&mut self) {
+        self.scissor_stack.pop();
+        self.apply_scissor();
+    }
+
+    pub fn get_scissor(&self) -> Option<Rect> {
+        self.scissor_stack.last().copied()
+    }
+
+    fn apply_scissor(&self) {
+        unsafe {
+            match self.scissor_stack.last() {
+                Some(scissor) => {
+                    gl::Enable(gl::SCISSOR_TEST);
+                    gl::Scissor(scissor.x, scissor.y, scissor.width, scissor.height);
+                }
+                None => gl::Disable(gl::SCISSOR_TEST),
+            }
+        }
+    }
+
+    // This will destroy all buffers and free the occupied memory.
+    pub fn terminate(&mut self) {
+        for buffers in self.buffers.iter().chain(self.object_lods.iter().flatten().map(|(x, _)| x)) {
+            Renderer::delete_buffers(buffers);
+        }
+        for buffer in self.object_skin_buffers.iter().chain(self.object_tangent_buffers.iter()).filter(|x| **x != 0) {
+            unsafe {
+                gl::DeleteBuffers(1, buffer);
+            }
+        }
+        for object in 0..self.object_morph_targets.len() {
+            self.delete_morph_targets(object);
+        }
+        if let Some(buffers) = self.debug_buffers.as_mut() {
+            unsafe {
+                gl::DeleteVertexArrays(1, &buffers.vao);
+                gl::DeleteBuffers(1, &buffers.vbo);
+            }
+            buffers.material.delete();
+        }
+
+        // Materials can be shared by many objects, so they are deleted separately.
+        for material in self.materials.iter_mut() {
+            material.delete();
+        }
+    }
+
+    // Points the skin attributes of a VAO at a buffer of `SkinVertex`.
+    fn bind_skin(vao: u32, skin: u32) {
+        unsafe {
+            gl::BindVertexArray(vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, skin);
+        }
+
+        let stride: i32 = (SKIN_VERTEX_DATA_SIZE * FOUR_BYTES as isize) as i32;
+        Renderer::enable_vertex_attrib_ptr(5, 4, stride, offset_of!(SkinVertex, joints));
+        Renderer::enable_vertex_attrib_ptr(6, 4, stride, offset_of!(SkinVertex, weights));
+    }
+
+    // Points the tangent attribute of a VAO at a buffer of `[f32; 4]`.
+    fn bind_tangents(vao: u32, tangents: u32) {
+        unsafe {
+            gl::BindVertexArray(vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, tangents);
+        }
+
+        Renderer::enable_vertex_attrib_ptr(7, 4, (TANGENT_DATA_SIZE * FOUR_BYTES as isize) as i32, 0);
+    }
+
+    // Buffers with a `vbo` of 0 (levels of detail sharing another object's vertices) only delete the rest.
+    fn delete_buffers(buffers: &Buffers) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &buffers.vao);
+            if buffers.vbo != 0 {
+                gl::DeleteBuffers(1, &buffers.vbo);
+            }
+            gl::DeleteBuffers(1, &buffers.ibo);
+        }
+    }
+
+    // Uploads vertices and indices into a new VAO. Without vertices it reads them from `shared_vbo` instead.
+    fn create_buffers(vertices: Option<Vec<Vertex>>, indices: Option<Vec<u32>>, shared_vbo: Option<u32>) -> Buffers {
+        unsafe {
+            let mut vao = 0u32;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+
+            let mut vbo = 0u32;
+            let mut vertex_count = 0;
+            match vertices {
+                Some(vertex_data) => {
+                    vertex_count = vertex_data.len();
+                    let mut buffer_data: Vec<f32> = vec![];
+                    for vertex in vertex_data.iter() {
+                        buffer_data.extend(vec![
+                            vertex.position.0,
+                            vertex.position.1,
+                            vertex.position.2,
+                            vertex.color.0,
+                            vertex.color.1,
+                            vertex.color.2,
+                            vertex.color.3,
+                            vertex.tex_coords.0,
+                            vertex.tex_coords.1,
+                            vertex.normals.0,
+                            vertex.normals.1,
+                            vertex.normals.2,
+                            vertex.texture_id
+                        ]);
+                    }
+
+                    gl::GenBuffers(1, &mut vbo);
+                    gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+                    gl::BufferData(
+                        gl::ARRAY_BUFFER,
+                        (FOUR_BYTES * buffer_data.len()) as isize,
+                        buffer_data.as_ptr() as *const c_void,
+                        gl::DYNAMIC_DRAW,
+                    );
+                },
+                // Levels of detail can use the vertices of another object. The buffer isn't theirs to delete.
+                None => if let Some(shared_vbo) = shared_vbo {
+                    gl::BindBuffer(gl::ARRAY_BUFFER, shared_vbo);
+                },
+            }
+
+            let stride: i32 = (VERTEX_DATA_SIZE * FOUR_BYTES as isize) as i32;
+            Renderer::enable_vertex_attrib_ptr(0, 3, stride, offset_of!(Vertex, position));
+            Renderer::enable_vertex_attrib_ptr(1, 4, stride, offset_of!(Vertex, color));
+            Renderer::enable_vertex_attrib_ptr(2, 2, stride, offset_of!(Vertex, tex_coords));
+            Renderer::enable_vertex_attrib_ptr(3, 3, stride, offset_of!(Vertex, normals));
+            Renderer::enable_vertex_attrib_ptr(4, 1, stride, offset_of!(Vertex, texture_id));
+
+            let index_size;
+            let mut ibo = 0u32;
+            match indices {
+                Some(index_data) => {
+                    gl::GenBuffers(1, &mut ibo);
+                    gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ibo);
+                    gl::BufferData(
+                        gl::ELEMENT_ARRAY_BUFFER,
+                        (FOUR_BYTES * index_data.len()) as isize,
+                        index_data.as_ptr() as *const c_void,
+                        gl::DYNAMIC_DRAW,
+                    );
+
+                    index_size = index_data.len() as i32;
+                },
+                None => { index_size = 0i32; }
+            }
+
+            Buffers { vao, vbo, ibo, index_size, vertex_count }
+        }
+    }
+
+    pub fn enable_vertex_attrib_ptr(index: u32, size: i32, stride: i32, offset: usize) {
+        unsafe {
+            gl::EnableVertexAttribArray(index);
             gl::VertexAttribPointer(
                 index,
                 size,
@@ -296,6 +1683,13 @@ impl Renderer {
     }
 }
 
+// The box and sphere around the vertex positions. `None` for objects without vertices.
+fn compute_bounds(vertices: &[Vertex]) -> Option<(Aabb, BoundingSphere)> {
+    let positions = vertices.iter().map(|x| Vector3::from(x.position)).collect::<Vec<_>>();
+
+    Some((Aabb::from_points(positions.iter().copied())?, BoundingSphere::from_points(&positions)?))
+}
+
 extern "system" fn message_callback(_: u32, _:  u32, _: u32, severity: u32, _: i32, message: *const i8, user_param: *mut c_void) {
     let debug_filters = unsafe { user_param.cast::<Vec<DebugFilter>>().as_ref().unwrap().clone() };
     let mut severity_str = String::new();
@@ -0,0 +1,139 @@
+use std::ops::BitOr;
+
+// Which buffers `Renderer::clear` clears. Combine them with `|`:
+// `ClearFlags::COLOR | ClearFlags::DEPTH`
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ClearFlags(u32);
+
+impl ClearFlags {
+    pub const NONE: ClearFlags = ClearFlags(0);
+    pub const COLOR: ClearFlags = ClearFlags(gl::COLOR_BUFFER_BIT);
+    pub const DEPTH: ClearFlags = ClearFlags(gl::DEPTH_BUFFER_BIT);
+    pub const STENCIL: ClearFlags = ClearFlags(gl::STENCIL_BUFFER_BIT);
+    pub const ALL: ClearFlags = ClearFlags(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT);
+
+    pub fn contains(&self, other: ClearFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub(crate) fn bits(&self) -> u32 {
+        self.0
+    }
+}
+
+impl BitOr for ClearFlags {
+    type Output = ClearFlags;
+
+    fn bitor(self, rhs: ClearFlags) -> ClearFlags {
+        ClearFlags(self.0 | rhs.0)
+    }
+}
+
+// A rectangle in framebuffer pixels, with the origin in the bottom left corner like OpenGL.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Rect { x, y, width, height }
+    }
+
+    // Converts a rectangle measured from the top left corner (like most UI and window coordinates)
+    // into OpenGL's bottom left origin.
+    pub fn from_top_left(x: i32, y: i32, width: i32, height: i32, target_height: i32) -> Self {
+        Rect::new(x, target_height - y - height, width, height)
+    }
+
+    // The overlapping part of both rectangles. Rectangles that don't overlap give an empty one.
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let top = (self.y + self.height).min(other.y + other.height);
+
+        Rect::new(x, y, (right - x).max(0), (top - y).max(0))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0 || self.height <= 0
+    }
+}
+
+// Selects the objects `Renderer::draw` draws. Hidden objects are never drawn.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawFilter {
+    All,
+    // Objects that are on at least one of the layers in the mask.
+    Layers(u32),
+    // Exactly these objects.
+    Objects(Vec<u32>),
+}
+
+// Where a pass draws to. `Default` keeps the framebuffer that is bound, which is the window unless the
+// application bound its own. `Framebuffer` takes the id of a framebuffer object created by the application.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RenderTarget {
+    Default,
+    Framebuffer(u32),
+}
+
+// Everything needed to draw one pass of a frame: where to draw, what to clear,
+// which part of the target to use and which objects to draw.
+//
+// Should be exposed to the user.
+#[derive(Clone, Debug)]
+pub struct RenderPass {
+    pub target: RenderTarget,
+    pub clear: ClearFlags,
+    pub clear_color: (f32, f32, f32, f32),
+    // `None` keeps the renderer's viewport (see `Renderer::set_viewport`).
+    pub viewport: Option<Rect>,
+    pub scissor: Option<Rect>,
+    pub objects: DrawFilter,
+}
+
+impl RenderPass {
+    // A pass that draws the given objects to the current framebuffer (usually the window) without clearing anything.
+    pub fn new(objects: DrawFilter) -> Self {
+        RenderPass {
+            target: RenderTarget::Default,
+            clear: ClearFlags::NONE,
+            clear_color: (0.0, 0.0, 0.0, 1.0),
+            viewport: None,
+            scissor: None,
+            objects,
+        }
+    }
+
+    pub fn with_clear(mut self, clear: ClearFlags, clear_color: (f32, f32, f32, f32)) -> Self {
+        self.clear = clear;
+        self.clear_color = clear_color;
+        self
+    }
+
+    pub fn with_target(mut self, target: RenderTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn with_viewport(mut self, viewport: Rect) -> Self {
+        self.viewport = Some(viewport);
+        self
+    }
+
+    pub fn with_scissor(mut self, scissor: Rect) -> Self {
+        self.scissor = Some(scissor);
+        self
+    }
+}
@@ -0,0 +1,140 @@
+use std::cmp::Ordering;
+
+// One entry of the render queue. Entries are sorted by the fields in this order:
+// priority, transparency, and then either (program, material, front-to-back depth) for opaque objects
+// or (back-to-front depth, program, material) for transparent ones.
+//
+// Should NOT be exposed to the user.
+#[derive(Copy, Clone)]
+pub(crate) struct DrawItem {
+    pub object: usize,
+    pub priority: i32,
+    pub transparent: bool,
+    pub program: u32,
+    pub material: u32,
+    pub depth: f32,
+}
+
+// Reused between frames so that sorting doesn't allocate every frame.
+//
+// Should NOT be exposed to the user.
+#[derive(Default)]
+pub(crate) struct RenderQueue {
+    pub items: Vec<DrawItem>,
+}
+
+impl RenderQueue {
+    pub(crate) fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    pub(crate) fn push(&mut self, item: DrawItem) {
+        self.items.push(item);
+    }
+
+    // The sort is stable, so objects that compare equal keep their creation order.
+    pub(crate) fn sort(&mut self) {
+        self.items.sort_by(compare);
+    }
+}
+
+fn compare(a: &DrawItem, b: &DrawItem) -> Ordering {
+    a.priority.cmp(&b.priority)
+        .then(a.transparent.cmp(&b.transparent))
+        .then_with(|| {
+            if a.transparent {
+                compare_depth(b.depth, a.depth)
+                    .then(a.program.cmp(&b.program))
+                    .then(a.material.cmp(&b.material))
+            } else {
+                a.program.cmp(&b.program)
+                    .then(a.material.cmp(&b.material))
+                    .then(compare_depth(a.depth, b.depth))
+            }
+        })
+}
+
+fn compare_depth(a: f32, b: f32) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+// The queue is internal to the renderer, so its order is tested here instead of in `tests/`.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(object: usize, priority: i32, transparent: bool, program: u32, material: u32, depth: f32) -> DrawItem {
+        DrawItem { object, priority, transparent, program, material, depth }
+    }
+
+    fn sorted(items: Vec<DrawItem>) -> Vec<usize> {
+        let mut queue = RenderQueue::default();
+        for item in items {
+            queue.push(item);
+        }
+        queue.sort();
+
+        queue.items.iter().map(|x| x.object).collect()
+    }
+
+    #[test]
+    fn priority_comes_first() {
+        let items = vec![
+            item(0, 1, false, 1, 1, 1.0),
+            item(1, 0, true, 9, 9, 9.0),
+            item(2, -1, false, 5, 5, 5.0),
+        ];
+
+        assert_eq!(sorted(items), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn opaque_before_transparent() {
+        let items = vec![
+            item(0, 0, true, 0, 0, 1.0),
+            item(1, 0, false, 9, 9, 9.0),
+        ];
+
+        assert_eq!(sorted(items), vec![1, 0]);
+    }
+
+    #[test]
+    fn opaque_by_program_then_material_then_front_to_back() {
+        let items = vec![
+            item(0, 0, false, 2, 0, 1.0),
+            item(1, 0, false, 1, 2, 1.0),
+            item(2, 0, false, 1, 1, 5.0),
+            item(3, 0, false, 1, 1, 2.0),
+        ];
+
+        assert_eq!(sorted(items), vec![3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn transparent_back_to_front_then_program_then_material() {
+        let items = vec![
+            item(0, 0, true, 1, 1, 1.0),
+            item(1, 0, true, 1, 1, 5.0),
+            item(2, 0, true, 2, 1, 3.0),
+            item(3, 0, true, 1, 2, 3.0),
+            item(4, 0, true, 1, 1, 3.0),
+        ];
+
+        assert_eq!(sorted(items), vec![1, 4, 3, 2, 0]);
+    }
+
+    #[test]
+    fn nan_depths_compare_equal() {
+        assert_eq!(compare_depth(f32::NAN, 1.0), Ordering::Equal);
+        assert_eq!(compare_depth(1.0, f32::NAN), Ordering::Equal);
+        assert_eq!(compare(&item(0, 0, false, 1, 1, f32::NAN), &item(1, 0, false, 1, 1, 2.0)), Ordering::Equal);
+
+        // Equal items keep their order, since the sort is stable.
+        let items = vec![
+            item(0, 0, true, 1, 1, f32::NAN),
+            item(1, 0, true, 1, 1, 2.0),
+            item(2, 0, false, 1, 1, f32::NAN),
+        ];
+        assert_eq!(sorted(items), vec![2, 0, 1]);
+    }
+}
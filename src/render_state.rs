@@ -0,0 +1,447 @@
+use crate::render::FaceCulling;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BlendEquation {
+    Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BlendFactor {
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha,
+    ConstantColor,
+    OneMinusConstantColor,
+    ConstantAlpha,
+    OneMinusConstantAlpha,
+    SrcAlphaSaturate,
+}
+
+// Separate equations and factors for the color and alpha channels, just like `glBlendFuncSeparate`.
+// `constant` is the color used by the `Constant*` factors.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BlendState {
+    pub color_equation: BlendEquation,
+    pub alpha_equation: BlendEquation,
+    pub src_color: BlendFactor,
+    pub dst_color: BlendFactor,
+    pub src_alpha: BlendFactor,
+    pub dst_alpha: BlendFactor,
+    pub constant: (f32, f32, f32, f32),
+}
+
+impl BlendState {
+    pub fn new(equation: BlendEquation, src: BlendFactor, dst: BlendFactor) -> Self {
+        BlendState {
+            color_equation: equation,
+            alpha_equation: equation,
+            src_color: src,
+            dst_color: dst,
+            src_alpha: src,
+            dst_alpha: dst,
+            constant: (0.0, 0.0, 0.0, 0.0),
+        }
+    }
+
+    // Classic transparency: `src * src.a + dst * (1 - src.a)`.
+    pub fn alpha() -> Self {
+        BlendState {
+            src_alpha: BlendFactor::One,
+            ..BlendState::new(BlendEquation::Add, BlendFactor::SrcAlpha, BlendFactor::OneMinusSrcAlpha)
+        }
+    }
+
+    // For colors that already have been multiplied by their alpha: `src + dst * (1 - src.a)`.
+    pub fn premultiplied() -> Self {
+        BlendState::new(BlendEquation::Add, BlendFactor::One, BlendFactor::OneMinusSrcAlpha)
+    }
+
+    // Lights, particles and other things that only brighten: `src * src.a + dst`.
+    pub fn additive() -> Self {
+        BlendState::new(BlendEquation::Add, BlendFactor::SrcAlpha, BlendFactor::One)
+    }
+
+    // `src * dst`.
+    pub fn multiply() -> Self {
+        BlendState::new(BlendEquation::Add, BlendFactor::DstColor, BlendFactor::Zero)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CompareFunction {
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DepthState {
+    pub test: bool,
+    pub write: bool,
+    pub function: CompareFunction,
+}
+
+impl DepthState {
+    pub fn new(test: bool, write: bool, function: CompareFunction) -> Self {
+        DepthState { test, write, function }
+    }
+}
+
+impl Default for DepthState {
+    fn default() -> Self {
+        DepthState::new(true, true, CompareFunction::Less)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StencilOperation {
+    Keep,
+    Zero,
+    Replace,
+    Increment,
+    IncrementWrap,
+    Decrement,
+    DecrementWrap,
+    Invert,
+}
+
+// Used for both front and back faces.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StencilState {
+    pub function: CompareFunction,
+    pub reference: i32,
+    pub read_mask: u32,
+    pub write_mask: u32,
+    pub stencil_fail: StencilOperation,
+    pub depth_fail: StencilOperation,
+    pub pass: StencilOperation,
+}
+
+impl StencilState {
+    // Writes `reference` everywhere the object is drawn.
+    pub fn write(reference: i32) -> Self {
+        StencilState {
+            function: CompareFunction::Always,
+            reference,
+            read_mask: 0xFF,
+            write_mask: 0xFF,
+            stencil_fail: StencilOperation::Keep,
+            depth_fail: StencilOperation::Keep,
+            pass: StencilOperation::Replace,
+        }
+    }
+
+    // Only draws where the stencil buffer passes `function` against `reference`, without changing it.
+    pub fn test(function: CompareFunction, reference: i32) -> Self {
+        StencilState {
+            function,
+            reference,
+            read_mask: 0xFF,
+            write_mask: 0x00,
+            stencil_fail: StencilOperation::Keep,
+            depth_fail: StencilOperation::Keep,
+            pass: StencilOperation::Keep,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PolygonOffset {
+    pub factor: f32,
+    pub units: f32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ColorMask {
+    pub red: bool,
+    pub green: bool,
+    pub blue: bool,
+    pub alpha: bool,
+}
+
+impl ColorMask {
+    pub fn all() -> Self {
+        ColorMask { red: true, green: true, blue: true, alpha: true }
+    }
+
+    pub fn none() -> Self {
+        ColorMask { red: false, green: false, blue: false, alpha: false }
+    }
+}
+
+// The fixed-function state used while drawing with a material.
+// `depth` and `cull` fall back to the renderer's global settings when they are `None`,
+// so the default render state draws exactly like a material without one.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RenderState {
+    pub blend: Option<BlendState>,
+    pub depth: Option<DepthState>,
+    pub stencil: Option<StencilState>,
+    pub cull: Option<FaceCulling>,
+    pub polygon_offset: Option<PolygonOffset>,
+    pub color_mask: ColorMask,
+    pub wireframe: bool,
+}
+
+impl Default for RenderState {
+    fn default() -> Self {
+        RenderState {
+            blend: None,
+            depth: None,
+            stencil: None,
+            cull: None,
+            polygon_offset: None,
+            color_mask: ColorMask::all(),
+            wireframe: false,
+        }
+    }
+}
+
+impl RenderState {
+    pub fn opaque() -> Self {
+        RenderState::default()
+    }
+
+    // Alpha blended, depth tested but without writing depth so that objects behind it still show up.
+    pub fn transparent() -> Self {
+        RenderState {
+            blend: Some(BlendState::alpha()),
+            depth: Some(DepthState::new(true, false, CompareFunction::Less)),
+            ..RenderState::default()
+        }
+    }
+
+    pub fn additive() -> Self {
+        RenderState {
+            blend: Some(BlendState::additive()),
+            depth: Some(DepthState::new(true, false, CompareFunction::Less)),
+            ..RenderState::default()
+        }
+    }
+
+    // Whether objects using this state have to be drawn after (and sorted separately from) opaque ones.
+    pub fn is_transparent(&self) -> bool {
+        self.blend.is_some()
+    }
+}
+
+// Remembers what was last sent to OpenGL so that `apply` only calls into the driver for state that
+// actually changes between two draws. `None` means unknown, which forces the next `apply` to set it.
+//
+// Should NOT be exposed to the user.
+#[derive(Default)]
+pub(crate) struct StateCache {
+    blend: Option<Option<BlendState>>,
+    depth: Option<DepthState>,
+    stencil: Option<Option<StencilState>>,
+    cull: Option<FaceCulling>,
+    polygon_offset: Option<Option<PolygonOffset>>,
+    color_mask: Option<ColorMask>,
+    wireframe: Option<bool>,
+}
+
+impl StateCache {
+    // Forgets everything, e.g. after the state was changed behind the cache's back.
+    pub(crate) fn invalidate(&mut self) {
+        *self = StateCache::default();
+    }
+
+    pub(crate) fn apply(&mut self, state: &RenderState, default_depth: DepthState, default_cull: FaceCulling) {
+        unsafe {
+            if self.blend != Some(state.blend) {
+                match &state.blend {
+                    Some(blend) => {
+                        gl::Enable(gl::BLEND);
+                        gl::BlendEquationSeparate(blend_equation(blend.color_equation), blend_equation(blend.alpha_equation));
+                        gl::BlendFuncSeparate(
+                            blend_factor(blend.src_color),
+                            blend_factor(blend.dst_color),
+                            blend_factor(blend.src_alpha),
+                            blend_factor(blend.dst_alpha),
+                        );
+                        gl::BlendColor(blend.constant.0, blend.constant.1, blend.constant.2, blend.constant.3);
+                    }
+                    None => gl::Disable(gl::BLEND),
+                }
+                self.blend = Some(state.blend);
+            }
+
+            let depth = state.depth.unwrap_or(default_depth);
+            if self.depth != Some(depth) {
+                if depth.test { gl::Enable(gl::DEPTH_TEST) } else { gl::Disable(gl::DEPTH_TEST) }
+                gl::DepthMask(if depth.write { gl::TRUE } else { gl::FALSE });
+                gl::DepthFunc(compare_function(depth.function));
+                self.depth = Some(depth);
+            }
+
+            if self.stencil != Some(state.stencil) {
+                match &state.stencil {
+                    Some(stencil) => {
+                        gl::Enable(gl::STENCIL_TEST);
+                        gl::StencilFunc(compare_function(stencil.function), stencil.reference, stencil.read_mask);
+                        gl::StencilMask(stencil.write_mask);
+                        gl::StencilOp(
+                            stencil_operation(stencil.stencil_fail),
+                            stencil_operation(stencil.depth_fail),
+                            stencil_operation(stencil.pass),
+                        );
+                    }
+                    None => gl::Disable(gl::STENCIL_TEST),
+                }
+                self.stencil = Some(state.stencil);
+            }
+
+            let cull = state.cull.unwrap_or(default_cull);
+            if self.cull != Some(cull) {
+                apply_face_culling(cull);
+                self.cull = Some(cull);
+            }
+
+            if self.polygon_offset != Some(state.polygon_offset) {
+                match &state.polygon_offset {
+                    Some(offset) => {
+                        gl::Enable(gl::POLYGON_OFFSET_FILL);
+                        gl::Enable(gl::POLYGON_OFFSET_LINE);
+                        gl::PolygonOffset(offset.factor, offset.units);
+                    }
+                    None => {
+                        gl::Disable(gl::POLYGON_OFFSET_FILL);
+                        gl::Disable(gl::POLYGON_OFFSET_LINE);
+                    }
+                }
+                self.polygon_offset = Some(state.polygon_offset);
+            }
+
+            if self.color_mask != Some(state.color_mask) {
+                let mask = state.color_mask;
+                gl::ColorMask(gl_bool(mask.red), gl_bool(mask.green), gl_bool(mask.blue), gl_bool(mask.alpha));
+                self.color_mask = Some(mask);
+            }
+
+            if self.wireframe != Some(state.wireframe) {
+                gl::PolygonMode(gl::FRONT_AND_BACK, if state.wireframe { gl::LINE } else { gl::FILL });
+                self.wireframe = Some(state.wireframe);
+            }
+        }
+    }
+
+    // `glClear` respects the depth, color and stencil write masks, so they have to be
+    // fully enabled before clearing or a material that disabled them would keep the buffers from clearing.
+    pub(crate) fn prepare_clear(&mut self) {
+        unsafe {
+            if let Some(depth) = self.depth {
+                if !depth.write {
+                    gl::DepthMask(gl::TRUE);
+                    self.depth = Some(DepthState { write: true, ..depth });
+                }
+            }
+
+            if self.color_mask != Some(ColorMask::all()) {
+                gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+                self.color_mask = Some(ColorMask::all());
+            }
+
+            // The stencil write mask is only restored, the rest of the stencil state is left alone.
+            gl::StencilMask(0xFFFFFFFF);
+            self.stencil = None;
+        }
+    }
+}
+
+pub(crate) fn apply_face_culling(cull: FaceCulling) {
+    unsafe {
+        match cull {
+            FaceCulling::None => gl::Disable(gl::CULL_FACE),
+            FaceCulling::Front => {
+                gl::Enable(gl::CULL_FACE);
+                gl::CullFace(gl::FRONT);
+            }
+            FaceCulling::Back => {
+                gl::Enable(gl::CULL_FACE);
+                gl::CullFace(gl::BACK);
+            }
+            FaceCulling::FrontAndBack => {
+                gl::Enable(gl::CULL_FACE);
+                gl::CullFace(gl::FRONT_AND_BACK);
+            }
+        }
+    }
+}
+
+fn gl_bool(value: bool) -> u8 {
+    if value { gl::TRUE } else { gl::FALSE }
+}
+
+fn blend_equation(equation: BlendEquation) -> u32 {
+    match equation {
+        BlendEquation::Add => gl::FUNC_ADD,
+        BlendEquation::Subtract => gl::FUNC_SUBTRACT,
+        BlendEquation::ReverseSubtract => gl::FUNC_REVERSE_SUBTRACT,
+        BlendEquation::Min => gl::MIN,
+        BlendEquation::Max => gl::MAX,
+    }
+}
+
+fn blend_factor(factor: BlendFactor) -> u32 {
+    match factor {
+        BlendFactor::Zero => gl::ZERO,
+        BlendFactor::One => gl::ONE,
+        BlendFactor::SrcColor => gl::SRC_COLOR,
+        BlendFactor::OneMinusSrcColor => gl::ONE_MINUS_SRC_COLOR,
+        BlendFactor::DstColor => gl::DST_COLOR,
+        BlendFactor::OneMinusDstColor => gl::ONE_MINUS_DST_COLOR,
+        BlendFactor::SrcAlpha => gl::SRC_ALPHA,
+        BlendFactor::OneMinusSrcAlpha => gl::ONE_MINUS_SRC_ALPHA,
+        BlendFactor::DstAlpha => gl::DST_ALPHA,
+        BlendFactor::OneMinusDstAlpha => gl::ONE_MINUS_DST_ALPHA,
+        BlendFactor::ConstantColor => gl::CONSTANT_COLOR,
+        BlendFactor::OneMinusConstantColor => gl::ONE_MINUS_CONSTANT_COLOR,
+        BlendFactor::ConstantAlpha => gl::CONSTANT_ALPHA,
+        BlendFactor::OneMinusConstantAlpha => gl::ONE_MINUS_CONSTANT_ALPHA,
+        BlendFactor::SrcAlphaSaturate => gl::SRC_ALPHA_SATURATE,
+    }
+}
+
+fn compare_function(function: CompareFunction) -> u32 {
+    match function {
+        CompareFunction::Never => gl::NEVER,
+        CompareFunction::Less => gl::LESS,
+        CompareFunction::Equal => gl::EQUAL,
+        CompareFunction::LessEqual => gl::LEQUAL,
+        CompareFunction::Greater => gl::GREATER,
+        CompareFunction::NotEqual => gl::NOTEQUAL,
+        CompareFunction::GreaterEqual => gl::GEQUAL,
+        CompareFunction::Always => gl::ALWAYS,
+    }
+}
+
+fn stencil_operation(operation: StencilOperation) -> u32 {
+    match operation {
+        StencilOperation::Keep => gl::KEEP,
+        StencilOperation::Zero => gl::ZERO,
+        StencilOperation::Replace => gl::REPLACE,
+        StencilOperation::Increment => gl::INCR,
+        StencilOperation::IncrementWrap => gl::INCR_WRAP,
+        StencilOperation::Decrement => gl::DECR,
+        StencilOperation::DecrementWrap => gl::DECR_WRAP,
+        StencilOperation::Invert => gl::INVERT,
+    }
+}
@@ -0,0 +1,376 @@
+use crate::camera::Camera;
+use crate::math::{Matrix4, Quaternion, Vector3};
+use crate::render::Renderer;
+use crate::transform::Transform;
+
+// The kinds of lights that can be attached to scene nodes. Angles are in radians.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LightKind {
+    // Shines along the node's -Z axis from infinitely far away, like the sun.
+    Directional,
+    Point { range: f32 },
+    // Shines along the node's -Z axis.
+    Spot { range: f32, inner_angle: f32, outer_angle: f32 },
+}
+
+// The renderer doesn't do any lighting itself. Lights only describe what the application's shaders should use,
+// see `SceneGraph::collect_lights`.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Light {
+    pub kind: LightKind,
+    pub color: (f32, f32, f32),
+    pub intensity: f32,
+}
+
+impl Light {
+    pub fn directional(color: (f32, f32, f32), intensity: f32) -> Self {
+        Light { kind: LightKind::Directional, color, intensity }
+    }
+
+    pub fn point(color: (f32, f32, f32), intensity: f32, range: f32) -> Self {
+        Light { kind: LightKind::Point { range }, color, intensity }
+    }
+
+    pub fn spot(color: (f32, f32, f32), intensity: f32, range: f32, inner_angle: f32, outer_angle: f32) -> Self {
+        Light { kind: LightKind::Spot { range, inner_angle, outer_angle }, color, intensity }
+    }
+}
+
+// A light together with where its node currently is in the world.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SceneLight {
+    pub node: u32,
+    pub light: Light,
+    pub position: Vector3,
+    pub direction: Vector3,
+}
+
+// Data-Oriented hierarchy of nodes. Every node has a local transform relative to its parent and any number
+// of renderer objects attached to it, plus optionally a camera and a light. Moving a node moves everything
+// below it. Nodes are identified by the `u32` returned from `create_node`.
+//
+// World matrices are only recalculated for nodes that changed (and their children) in `update`.
+// `apply` then hands them to the renderer, so the renderer's object list stays the only thing that is drawn.
+//
+// Should be exposed to the user.
+#[derive(Default)]
+pub struct SceneGraph {
+    names: Vec<String>,
+    parents: Vec<Option<u32>>,
+    children: Vec<Vec<u32>>,
+    // Nodes without a parent, in creation order.
+    roots: Vec<u32>,
+    translations: Vec<Vector3>,
+    rotations: Vec<Quaternion>,
+    scales: Vec<Vector3>,
+    world_matrices: Vec<Matrix4>,
+    dirty: Vec<bool>,
+    enabled: Vec<bool>,
+    objects: Vec<Vec<u32>>,
+    // Objects detached since the last `apply`, whose node matrices still have to be cleared.
+    detached_objects: Vec<u32>,
+    cameras: Vec<Option<Camera>>,
+    lights: Vec<Option<Light>>,
+    active_camera: Option<u32>,
+}
+
+impl SceneGraph {
+    pub fn new() -> Self {
+        SceneGraph::default()
+    }
+
+    // Creates a node at the parent's origin (or the world origin without a parent).
+    pub fn create_node(&mut self, name: &str, parent: Option<u32>) -> u32 {
+        let node = self.names.len() as u32;
+
+        self.names.push(name.to_string());
+        self.parents.push(None);
+        self.children.push(vec![]);
+        self.translations.push(Vector3::zero());
+        self.rotations.push(Quaternion::identity());
+        self.scales.push(Vector3::one());
+        self.world_matrices.push(Matrix4::identity());
+        self.dirty.push(true);
+        self.enabled.push(true);
+        self.objects.push(vec![]);
+        self.cameras.push(None);
+        self.lights.push(None);
+
+        match parent {
+            Some(parent) => {
+                self.parents[node as usize] = Some(parent);
+                self.children[parent as usize].push(node);
+            }
+            None => self.roots.push(node),
+        }
+
+        node
+    }
+
+    pub fn get_node_count(&self) -> u32 {
+        self.names.len() as u32
+    }
+
+    pub fn get_name(&self, node: u32) -> &str {
+        &self.names[node as usize]
+    }
+
+    // Returns the first node with the given name.
+    pub fn find_node(&self, name: &str) -> Option<u32> {
+        self.names.iter().position(|x| x == name).map(|x| x as u32)
+    }
+
+    // Moves the node (and everything below it) under another parent. Its local transform is kept,
+    // so it ends up somewhere else in the world if the parents are in different places.
+    pub fn set_parent(&mut self, node: u32, parent: Option<u32>) {
+        let mut ancestor = parent;
+        while let Some(current) = ancestor {
+            if current == node {
+                panic!("Node {} can't be a child of itself!", node);
+            }
+            ancestor = self.parents[current as usize];
+        }
+
+        match self.parents[node as usize] {
+            Some(old) => self.children[old as usize].retain(|x| *x != node),
+            None => self.roots.retain(|x| *x != node),
+        }
+        match parent {
+            Some(parent) => self.children[parent as usize].push(node),
+            None => self.roots.push(node),
+        }
+
+        self.parents[node as usize] = parent;
+        self.dirty[node as usize] = true;
+    }
+
+    pub fn get_parent(&self, node: u32) -> Option<u32> {
+        self.parents[node as usize]
+    }
+
+    pub fn get_children(&self, node: u32) -> &[u32] {
+        &self.children[node as usize]
+    }
+
+    // Sets the node's translation, rotation and scale relative to its parent.
+    // The `parent` of the transform is ignored, use `set_parent` instead.
+    pub fn set_local_transform(&mut self, node: u32, transform: &Transform) {
+        self.translations[node as usize] = transform.translation;
+        self.rotations[node as usize] = transform.rotation;
+        self.scales[node as usize] = transform.scale;
+        self.dirty[node as usize] = true;
+    }
+
+    pub fn get_local_transform(&self, node: u32) -> Transform {
+        Transform::new(self.translations[node as usize], self.rotations[node as usize], self.scales[node as usize])
+    }
+
+    pub fn set_translation(&mut self, node: u32, translation: Vector3) {
+        self.translations[node as usize] = translation;
+        self.dirty[node as usize] = true;
+    }
+
+    pub fn set_rotation(&mut self, node: u32, rotation: Quaternion) {
+        self.rotations[node as usize] = rotation;
+        self.dirty[node as usize] = true;
+    }
+
+    pub fn set_scale(&mut self, node: u32, scale: Vector3) {
+        self.scales[node as usize] = scale;
+        self.dirty[node as usize] = true;
+    }
+
+    // The world matrix as of the last `update`.
+    pub fn get_world_matrix(&self, node: u32) -> Matrix4 {
+        self.world_matrices[node as usize]
+    }
+
+    // Objects attached to disabled nodes, or to nodes below a disabled node, are hidden by `apply`.
+    // Detaching an object shows it again.
+    pub fn set_node_enabled(&mut self, node: u32, enabled: bool) {
+        self.enabled[node as usize] = enabled;
+    }
+
+    pub fn is_node_enabled(&self, node: u32) -> bool {
+        self.enabled[node as usize]
+    }
+
+    // Attaches a renderer object (as returned by `create_object`) to the node. The object's own
+    // transform becomes relative to the node.
+    pub fn attach_object(&mut self, node: u32, object: u32) {
+        if !self.objects[node as usize].contains(&object) {
+            self.objects[node as usize].push(object);
+        }
+    }
+
+    // The next `apply` clears the object's node matrix, so from then on it is placed by its own transform again.
+    pub fn detach_object(&mut self, node: u32, object: u32) {
+        self.objects[node as usize].retain(|x| *x != object);
+        self.detached_objects.push(object);
+    }
+
+    pub fn get_objects(&self, node: u32) -> &[u32] {
+        &self.objects[node as usize]
+    }
+
+    // The camera is placed at the node, its own position and rotation are ignored.
+    pub fn attach_camera(&mut self, node: u32, camera: Camera) {
+        self.cameras[node as usize] = Some(camera);
+    }
+
+    pub fn detach_camera(&mut self, node: u32) {
+        self.cameras[node as usize] = None;
+        if self.active_camera == Some(node) {
+            self.active_camera = None;
+        }
+    }
+
+    pub fn get_camera_mut(&mut self, node: u32) -> Option<&mut Camera> {
+        self.cameras[node as usize].as_mut()
+    }
+
+    // The camera `apply` gives to the renderer. The node needs a camera attached to it.
+    pub fn set_active_camera(&mut self, node: Option<u32>) {
+        if let Some(node) = node {
+            if self.cameras[node as usize].is_none() {
+                panic!("Node {} doesn't have a camera attached to it!", node);
+            }
+        }
+
+        self.active_camera = node;
+    }
+
+    pub fn get_active_camera(&self) -> Option<u32> {
+        self.active_camera
+    }
+
+    pub fn attach_light(&mut self, node: u32, light: Light) {
+        self.lights[node as usize] = Some(light);
+    }
+
+    pub fn detach_light(&mut self, node: u32) {
+        self.lights[node as usize] = None;
+    }
+
+    // Recalculates the world matrices of every node that changed since the last update, and of their children.
+    pub fn update(&mut self) {
+        let mut stack: Vec<(u32, Matrix4, bool)> = self.roots.iter().rev()
+            .map(|x| (*x, Matrix4::identity(), false))
+            .collect();
+
+        while let Some((node, parent_matrix, parent_changed)) = stack.pop() {
+            let i = node as usize;
+            let changed = parent_changed || self.dirty[i];
+            if changed {
+                self.world_matrices[i] = parent_matrix
+                    * Matrix4::from_transform(self.translations[i], self.rotations[i], self.scales[i]);
+                self.dirty[i] = false;
+            }
+
+            for child in self.children[i].iter().rev() {
+                stack.push((*child, self.world_matrices[i], changed));
+            }
+        }
+    }
+
+    // Updates the scene and hands it to the renderer: attached objects are moved to their nodes and shown or hidden
+    // depending on whether their nodes are enabled (see `Renderer::set_object_node_enabled`, objects hidden with
+    // `Renderer::set_object_visible` stay hidden), and the active camera becomes the renderer's camera.
+    // The renderer's current aspect ratio is kept so that `Renderer::resize` keeps working.
+    pub fn apply(&mut self, renderer: &mut Renderer) {
+        self.update();
+
+        // Objects that were attached to another node since are placed there again right after.
+        for object in self.detached_objects.drain(..) {
+            renderer.set_object_node_matrix(object, None);
+            renderer.set_object_node_enabled(object, true);
+        }
+        for (node, enabled) in self.depth_first(None) {
+            for object in self.objects[node as usize].iter() {
+                renderer.set_object_node_matrix(*object, Some(self.world_matrices[node as usize]));
+                renderer.set_object_node_enabled(*object, enabled);
+            }
+        }
+
+        if let Some(node) = self.active_camera {
+            let mut camera = self.get_world_camera(node).unwrap();
+            if let Some(current) = renderer.get_camera() {
+                camera.aspect = current.aspect;
+            }
+            renderer.set_camera(camera);
+        }
+    }
+
+    // The objects attached to `node` and everything below it (or the whole scene), in depth-first order.
+    // Useful with `DrawFilter::Objects` to draw only part of the scene.
+    pub fn collect_objects(&self, node: Option<u32>) -> Vec<u32> {
+        self.depth_first(node).iter()
+            .flat_map(|(node, _)| self.objects[*node as usize].iter().copied())
+            .collect()
+    }
+
+    // Every light on an enabled node, placed where its node is as of the last `update`.
+    pub fn collect_lights(&self) -> Vec<SceneLight> {
+        self.depth_first(None).iter()
+            .filter(|(node, enabled)| *enabled && self.lights[*node as usize].is_some())
+            .map(|(node, _)| {
+                let matrix = &self.world_matrices[*node as usize];
+
+                SceneLight {
+                    node: *node,
+                    light: self.lights[*node as usize].unwrap(),
+                    position: matrix.get_translation(),
+                    direction: matrix.transform_vector(Vector3::new(0.0, 0.0, -1.0)).normalize(),
+                }
+            })
+            .collect()
+    }
+
+    // The node's camera with its position and rotation taken from the node, as of the last `update`.
+    pub fn get_world_camera(&self, node: u32) -> Option<Camera> {
+        let mut camera = self.cameras[node as usize]?;
+        let matrix = &self.world_matrices[node as usize];
+
+        // Scale doesn't make sense for a camera, so only the directions of the axes are used.
+        camera.position = matrix.get_translation();
+        camera.rotation = Quaternion::from_rotation_axes(
+            matrix.transform_vector(Vector3::new(1.0, 0.0, 0.0)).normalize(),
+            matrix.transform_vector(Vector3::new(0.0, 1.0, 0.0)).normalize(),
+            matrix.transform_vector(Vector3::new(0.0, 0.0, 1.0)).normalize(),
+        );
+
+        Some(camera)
+    }
+
+    // Every node below `start` (or every node), with whether it and all of its parents are enabled.
+    fn depth_first(&self, start: Option<u32>) -> Vec<(u32, bool)> {
+        let mut nodes = vec![];
+        let mut stack: Vec<(u32, bool)> = match start {
+            Some(node) => {
+                let mut enabled = true;
+                let mut ancestor = Some(node);
+                while let Some(current) = ancestor {
+                    enabled &= self.enabled[current as usize];
+                    ancestor = self.parents[current as usize];
+                }
+                vec![(node, enabled)]
+            }
+            None => self.roots.iter().rev().map(|x| (*x, self.enabled[*x as usize])).collect(),
+        };
+
+        while let Some((node, enabled)) = stack.pop() {
+            nodes.push((node, enabled));
+            for child in self.children[node as usize].iter().rev() {
+                stack.push((*child, enabled && self.enabled[*child as usize]));
+            }
+        }
+
+        nodes
+    }
+}
@@ -1,30 +1,76 @@
+use std::collections::HashMap;
 use std::ffi::CString;
-use std::{ptr, str};
+use std::{fmt, ptr};
 
+use crate::diagnostics::{self, Diagnostic};
+use crate::preprocessor::{self, PreprocessedShader, PreprocessError};
+use crate::program_cache::ProgramCache;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum ShaderType {
     Vertex,
     Fragment,
 }
 
-fn compile_shader(shader_type: ShaderType, source: String) -> u32 {
-    let shader_name;
-
-    let shader = unsafe {
-        let shader_type = match shader_type {
-            ShaderType::Vertex => {
-                shader_name = "Vertex Shader: ";
-                gl::VERTEX_SHADER
-            },
-            ShaderType::Fragment => {
-                shader_name = "Fragment Shader: ";
-                gl::FRAGMENT_SHADER
+// Everything that can go wrong while turning shader source code into a program.
+// The compile variant carries the parsed diagnostics so that editors can underline the errors.
+#[derive(Debug)]
+pub enum ShaderError {
+    Preprocess(PreprocessError),
+    Compile { shader_type: ShaderType, log: String, diagnostics: Vec<Diagnostic> },
+    Link { log: String },
+}
+
+impl ShaderError {
+    // All parsed diagnostics, empty for anything other than a compile error.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        match self {
+            ShaderError::Compile { diagnostics, .. } => diagnostics,
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::Preprocess(e) => write!(f, "{}", e),
+            ShaderError::Compile { shader_type, diagnostics, .. } => {
+                match shader_type {
+                    ShaderType::Vertex => write!(f, "Vertex Shader: ")?,
+                    ShaderType::Fragment => write!(f, "Fragment Shader: ")?,
+                }
+
+                for diagnostic in diagnostics.iter() {
+                    write!(f, "\n{}\n", diagnostic)?;
+                }
+
+                Ok(())
             }
+            ShaderError::Link { log } => write!(f, "{}", log),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+impl From<PreprocessError> for ShaderError {
+    fn from(error: PreprocessError) -> Self {
+        ShaderError::Preprocess(error)
+    }
+}
+
+pub(crate) fn compile_shader(shader_type: ShaderType, source: &str, files: &[String]) -> Result<u32, ShaderError> {
+    unsafe {
+        let gl_type = match shader_type {
+            ShaderType::Vertex => gl::VERTEX_SHADER,
+            ShaderType::Fragment => gl::FRAGMENT_SHADER,
         };
 
-        let shader = gl::CreateShader(shader_type);
+        let shader = gl::CreateShader(gl_type);
 
-        let source = CString::new(source.as_str().as_bytes()).unwrap();
-        gl::ShaderSource(shader, 1, &source.as_ptr(), ptr::null());
+        let source_cstr = CString::new(source.as_bytes()).unwrap();
+        gl::ShaderSource(shader, 1, &source_cstr.as_ptr(), ptr::null());
         gl::CompileShader(shader);
 
         let mut success: i32 = 0;
@@ -34,51 +80,82 @@ fn compile_shader(shader_type: ShaderType, source: String) -> u32 {
             let mut len: i32 = 0;
             gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
 
-            let mut info_log = Vec::with_capacity(len as usize);
-            info_log.set_len((len as usize) - 1);
-
+            let mut info_log = vec![0u8; len.max(1) as usize];
             gl::GetShaderInfoLog(
                 shader,
                 len,
                 ptr::null_mut(),
                 info_log.as_mut_ptr() as *mut i8
             );
+            gl::DeleteShader(shader);
 
-            panic!(
-                "{}{}",
-                shader_name,
-                str::from_utf8(&info_log)
-                    .ok()
-                    .expect("ShaderInfoLog not valid utf8")
-            );
-        }
+            let log = info_log_to_string(info_log);
+            let diagnostics = diagnostics::parse_info_log(&log, files, source);
 
-        shader
-    };
+            return Err(ShaderError::Compile { shader_type, log, diagnostics });
+        }
 
-    shader
+        Ok(shader)
+    }
 }
 
 pub fn compile_program(vertex_shader: String, fragment_shader: String) -> u32 {
-    let program = unsafe {
+    try_compile_program(vertex_shader, fragment_shader).unwrap_or_else(|e| panic!("{}", e))
+}
+
+// Same as `compile_program`, but hands back the error instead of panicking.
+pub fn try_compile_program(vertex_shader: String, fragment_shader: String) -> Result<u32, ShaderError> {
+    link_program(&vertex_shader, &fragment_shader, &[], &[], false)
+}
+
+// Compiles a program from the output of the preprocessor, so that diagnostics point at the
+// original files instead of the combined source.
+pub fn compile_preprocessed_program(vertex_shader: &PreprocessedShader, fragment_shader: &PreprocessedShader) -> u32 {
+    try_compile_preprocessed_program(vertex_shader, fragment_shader).unwrap_or_else(|e| panic!("{}", e))
+}
+
+pub fn try_compile_preprocessed_program(vertex_shader: &PreprocessedShader, fragment_shader: &PreprocessedShader)
+    -> Result<u32, ShaderError> {
+    link_program(&vertex_shader.source, &fragment_shader.source, &vertex_shader.files, &fragment_shader.files, false)
+}
+
+// `retrievable` asks the driver to keep the program binary around so it can be read back with `glGetProgramBinary`.
+pub(crate) fn link_program(vertex_shader: &str, fragment_shader: &str, vertex_files: &[String], fragment_files: &[String],
+    retrievable: bool) -> Result<u32, ShaderError> {
+    let vertex = compile_shader(ShaderType::Vertex, vertex_shader, vertex_files)?;
+    let fragment = match compile_shader(ShaderType::Fragment, fragment_shader, fragment_files) {
+        Ok(fragment) => fragment,
+        Err(e) => {
+            unsafe { gl::DeleteShader(vertex); }
+            return Err(e);
+        }
+    };
+
+    unsafe {
         let program = gl::CreateProgram();
 
-        gl::AttachShader(
-            program,
-            compile_shader(
-                ShaderType::Vertex,
-                vertex_shader
-            )
-        );
-        gl::AttachShader(
-            program,
-            compile_shader(
-                ShaderType::Fragment,
-                fragment_shader
-            )
-        );
+        gl::AttachShader(program, vertex);
+        gl::AttachShader(program, fragment);
+        if retrievable {
+            gl::ProgramParameteri(program, gl::PROGRAM_BINARY_RETRIEVABLE_HINT, gl::TRUE as i32);
+        }
         gl::LinkProgram(program);
 
+        // The program keeps the compiled code, the shader objects are no longer needed.
+        gl::DetachShader(program, vertex);
+        gl::DetachShader(program, fragment);
+        gl::DeleteShader(vertex);
+        gl::DeleteShader(fragment);
+
+        check_link_status(program)?;
+
+        Ok(program)
+    }
+}
+
+// Deletes the program and returns its info log if it failed to link.
+pub(crate) fn check_link_status(program: u32) -> Result<(), ShaderError> {
+    unsafe {
         let mut success: i32 = 0;
         gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
 
@@ -86,26 +163,104 @@ pub fn compile_program(vertex_shader: String, fragment_shader: String) -> u32 {
             let mut len: i32 = 0;
             gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut len);
 
-            let mut info_log = Vec::with_capacity(len as usize);
-            info_log.set_len((len as usize) - 1);
-
+            let mut info_log = vec![0u8; len.max(1) as usize];
             gl::GetProgramInfoLog(
                 program,
                 len,
                 ptr::null_mut(),
                 info_log.as_mut_ptr() as *mut i8
             );
+            gl::DeleteProgram(program);
 
-            panic!(
-                "{}",
-                str::from_utf8(&info_log)
-                    .ok()
-                    .expect("ProgramInfoLog not valid utf8")
-            );
+            return Err(ShaderError::Link { log: info_log_to_string(info_log) });
         }
 
-        program
-    };
+        Ok(())
+    }
+}
+
+// Info logs are nul-terminated and not guaranteed to be valid utf8.
+fn info_log_to_string(mut info_log: Vec<u8>) -> String {
+    if let Some(end) = info_log.iter().position(|x| *x == 0) {
+        info_log.truncate(end);
+    }
+
+    String::from_utf8_lossy(&info_log).into_owned()
+}
+
+// A pair of shader files that gets compiled once for every set of defines it is requested with.
+// The programs are cached, so asking for the same permutation twice does not compile anything.
+//
+// Should be exposed to the user.
+pub struct ShaderVariants {
+    vertex_path: String,
+    fragment_path: String,
+    programs: HashMap<Vec<(String, String)>, u32>,
+    cache: Option<ProgramCache>,
+}
+
+impl ShaderVariants {
+    pub fn new(vertex_shader_path: &str, fragment_shader_path: &str) -> Self {
+        ShaderVariants {
+            vertex_path: vertex_shader_path.to_string(),
+            fragment_path: fragment_shader_path.to_string(),
+            programs: HashMap::new(),
+            cache: None,
+        }
+    }
 
-    program
-}
\ No newline at end of file
+    // Permutations that haven't been compiled yet are looked up in (and stored to) the given on-disk cache.
+    pub fn set_program_cache(&mut self, cache: ProgramCache) {
+        self.cache = Some(cache);
+    }
+
+    // Returns the program for the given defines, compiling it if this is the first time
+    // the permutation is used. The order of the defines does not matter.
+    pub fn get_program(&mut self, defines: &[(&str, &str)]) -> u32 {
+        self.try_get_program(defines).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_get_program(&mut self, defines: &[(&str, &str)]) -> Result<u32, ShaderError> {
+        let key = ShaderVariants::key(defines);
+        if let Some(program) = self.programs.get(&key) {
+            return Ok(*program);
+        }
+
+        let vertex_source = preprocessor::preprocess_file(&self.vertex_path, defines)?;
+        let fragment_source = preprocessor::preprocess_file(&self.fragment_path, defines)?;
+
+        let program = match &self.cache {
+            Some(cache) => cache.try_compile_preprocessed_program(&vertex_source, &fragment_source)?,
+            None => try_compile_preprocessed_program(&vertex_source, &fragment_source)?,
+        };
+        self.programs.insert(key, program);
+
+        Ok(program)
+    }
+
+    // The amount of permutations that have been compiled so far.
+    pub fn variant_count(&self) -> usize {
+        self.programs.len()
+    }
+
+    // Deletes every compiled permutation.
+    pub fn delete(&mut self) {
+        for program in self.programs.values() {
+            unsafe {
+                gl::DeleteProgram(*program);
+            }
+        }
+
+        self.programs.clear();
+    }
+
+    fn key(defines: &[(&str, &str)]) -> Vec<(String, String)> {
+        let mut key = defines.iter()
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+            .collect::<Vec<_>>();
+        key.sort();
+        key.dedup();
+
+        key
+    }
+}
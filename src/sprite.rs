@@ -0,0 +1,384 @@
+use std::ffi::c_void;
+use std::ptr;
+
+use crate::camera::Camera;
+use crate::material::{AttributeType, BuiltinUniform, Material};
+use crate::render::{FaceCulling, Renderer};
+use crate::render_state::{BlendState, CompareFunction, DepthState, RenderState};
+use crate::texture::Texture;
+
+static FOUR_BYTES: usize = 4;
+// Position (3), color (4) and texture coordinates (2), at the same locations as the attributes of `Vertex`.
+static SPRITE_VERTEX_DATA_SIZE: usize = 9;
+
+pub(crate) static SPRITE_VERTEX_SHADER: &str = "#version 330 core
+layout (location = 0) in vec3 vertexPosition;
+layout (location = 1) in vec4 vertexColor;
+layout (location = 2) in vec2 texCoords;
+
+uniform mat4 u_view;
+uniform mat4 u_projection;
+
+out vec4 fragmentColor;
+out vec2 fragmentTexCoords;
+
+void main() {
+    fragmentColor = vertexColor;
+    fragmentTexCoords = texCoords;
+    gl_Position = u_projection * u_view * vec4(vertexPosition, 1.0);
+}";
+
+static SPRITE_FRAGMENT_SHADER: &str = "#version 330 core
+in vec4 fragmentColor;
+in vec2 fragmentTexCoords;
+
+uniform sampler2D u_texture;
+
+out vec4 FragColor;
+
+void main() {
+    FragColor = texture(u_texture, fragmentTexCoords) * fragmentColor;
+}";
+
+// A textured quad drawn by a `SpriteBatch`. Built with `Sprite::new` and the methods below, e.g.
+// `Sprite::new((100.0, 50.0), (32.0, 32.0)).rotation(0.5).tint((1.0, 0.0, 0.0, 1.0))`.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sprite {
+    pub position: (f32, f32),
+    // A negative width or height flips the sprite.
+    pub size: (f32, f32),
+    // Counter-clockwise, in radians, around the origin.
+    pub rotation: f32,
+    // The point that ends up at `position`, as a fraction of the size. (0.0, 0.0) is the bottom left corner.
+    pub origin: (f32, f32),
+    // The part of the texture that is shown: (u_min, v_min, u_max, v_max), with v = 0.0 at the top row of the image.
+    pub uv_rect: (f32, f32, f32, f32),
+    // Multiplied with the texture color.
+    pub tint: (f32, f32, f32, f32),
+    // Z coordinate of the quad. With `Camera::screen` anything between -1.0 and 1.0 is visible.
+    pub depth: f32,
+}
+
+impl Sprite {
+    // A sprite showing the whole texture, untinted, with its bottom left corner at `position`.
+    pub fn new(position: (f32, f32), size: (f32, f32)) -> Self {
+        Sprite {
+            position,
+            size,
+            rotation: 0.0,
+            origin: (0.0, 0.0),
+            uv_rect: (0.0, 0.0, 1.0, 1.0),
+            tint: (1.0, 1.0, 1.0, 1.0),
+            depth: 0.0,
+        }
+    }
+
+    pub fn rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn origin(mut self, origin: (f32, f32)) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn uv_rect(mut self, uv_rect: (f32, f32, f32, f32)) -> Self {
+        self.uv_rect = uv_rect;
+        self
+    }
+
+    // Same as `uv_rect`, but in pixels of a texture that is `texture_size` big, with y = 0 at the top row.
+    pub fn source_rect(mut self, x: f32, y: f32, width: f32, height: f32, texture_size: (f32, f32)) -> Self {
+        self.uv_rect = (x / texture_size.0, y / texture_size.1, (x + width) / texture_size.0, (y + height) / texture_size.1);
+        self
+    }
+
+    pub fn tint(mut self, tint: (f32, f32, f32, f32)) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    pub fn depth(mut self, depth: f32) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    // The bottom left, bottom right, top right and top left corners after rotating around the origin.
+    pub fn corners(&self) -> [(f32, f32); 4] {
+        let (sin, cos) = self.rotation.sin_cos();
+        let corner = |x: f32, y: f32| {
+            let local_x = (x - self.origin.0) * self.size.0;
+            let local_y = (y - self.origin.1) * self.size.1;
+
+            (self.position.0 + local_x * cos - local_y * sin, self.position.1 + local_x * sin + local_y * cos)
+        };
+
+        [corner(0.0, 0.0), corner(1.0, 0.0), corner(1.0, 1.0), corner(0.0, 1.0)]
+    }
+}
+
+// The order in which a `SpriteBatch` draws its sprites.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpriteSortMode {
+    // In the order they were added. Consecutive sprites with the same texture share a draw call.
+    Deferred,
+    // Grouped by texture, keeping the order within each texture. Takes the fewest draw calls, but overlapping
+    // sprites with different textures can end up in a different order (unless depth testing sorts them out).
+    Texture,
+}
+
+// Collects sprites between `begin` and `end` and draws them with as few draw calls as possible,
+// from a single vertex buffer that is refilled every time instead of one object per sprite.
+//
+// Should be exposed to the user.
+pub struct SpriteBatch {
+    vao: u32,
+    vbo: u32,
+    ibo: u32,
+    // How many sprites fit in the buffers. Batches with more sprites are drawn in several parts.
+    capacity: usize,
+    material: Material,
+    sort_mode: SpriteSortMode,
+    sprites: Vec<(Texture, Sprite)>,
+    // Reused between flushes so that it isn't reallocated every frame.
+    vertex_data: Vec<f32>,
+    view: [f32; 16],
+    projection: [f32; 16],
+    draw_calls: u32,
+}
+
+impl SpriteBatch {
+    // Uses the built-in material, which multiplies the texture with the tint and alpha blends the result
+    // without depth testing.
+    pub fn new(capacity: usize) -> Self {
+        let mut material = Material::from_shaders(SPRITE_VERTEX_SHADER, SPRITE_FRAGMENT_SHADER, vec![],
+            vec![("u_texture", AttributeType::Int1(0))]);
+        material.set_render_state(sprite_render_state());
+
+        SpriteBatch::with_material(capacity, material)
+    }
+
+    // Draws with a custom material. Its vertex shader gets the position at location 0, the tint at location 1
+    // and the texture coordinates at location 2, plus `u_view` and `u_projection`. The sprite's texture is
+    // bound to unit 0. A capacity of 0 is raised to 1.
+    pub fn with_material(capacity: usize, material: Material) -> Self {
+        let capacity = capacity.max(1);
+        let (vao, vbo, ibo) = SpriteBatch::create_buffers(capacity);
+
+        SpriteBatch {
+            vao,
+            vbo,
+            ibo,
+            capacity,
+            material,
+            sort_mode: SpriteSortMode::Deferred,
+            sprites: vec![],
+            vertex_data: Vec::with_capacity(capacity * 4 * SPRITE_VERTEX_DATA_SIZE),
+            view: [0.0; 16],
+            projection: [0.0; 16],
+            draw_calls: 0,
+        }
+    }
+
+    pub fn get_material(&self) -> &Material {
+        &self.material
+    }
+
+    pub fn get_material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    pub fn set_sort_mode(&mut self, sort_mode: SpriteSortMode) {
+        self.sort_mode = sort_mode;
+    }
+
+    pub fn get_sort_mode(&self) -> SpriteSortMode {
+        self.sort_mode
+    }
+
+    // Starts a new batch seen through `camera`, usually one made with `Camera::screen`.
+    // Sprites that weren't drawn with `end` yet are thrown away.
+    pub fn begin(&mut self, camera: &Camera) {
+        self.view = camera.view_matrix().data;
+        self.projection = camera.projection_matrix().data;
+        self.sprites.clear();
+    }
+
+    // The texture is only read in `end`, so it has to stay alive until then.
+    pub fn draw(&mut self, texture: &Texture, sprite: Sprite) {
+        self.sprites.push((texture.clone(), sprite));
+    }
+
+    // How many sprites were added since `begin`.
+    pub fn get_sprite_count(&self) -> usize {
+        self.sprites.len()
+    }
+
+    // Draws everything added since `begin`. The render state of the material is set through the renderer,
+    // so that the renderer knows what changed.
+    pub fn end(&mut self, renderer: &mut Renderer) {
+        self.draw_calls = 0;
+        if self.sprites.is_empty() {
+            return;
+        }
+
+        if self.sort_mode == SpriteSortMode::Texture {
+            self.sprites.sort_by_key(|(texture, _)| texture.get_texture_id());
+        }
+
+        renderer.apply_render_state(self.material.get_render_state());
+        self.material.bind();
+        self.material.apply_attributes();
+        self.material.apply_builtin(BuiltinUniform::View, AttributeType::Matrix4(self.view.as_ptr()));
+        self.material.apply_builtin(BuiltinUniform::Projection, AttributeType::Matrix4(self.projection.as_ptr()));
+
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ibo);
+        }
+
+        let texture_ids = self.sprites.iter().map(|(x, _)| x.get_texture_id()).collect::<Vec<_>>();
+        let runs = sprite_runs(&texture_ids, self.capacity);
+        let mut run = 0;
+        for (p, part) in self.sprites.chunks(self.capacity).enumerate() {
+            self.vertex_data.clear();
+            for (_, sprite) in part.iter() {
+                push_sprite_vertices(&mut self.vertex_data, sprite);
+            }
+
+            unsafe {
+                // Orphaning the buffer lets the driver hand out fresh memory instead of waiting for the last draw.
+                let size = (self.capacity * 4 * SPRITE_VERTEX_DATA_SIZE * FOUR_BYTES) as isize;
+                gl::BufferData(gl::ARRAY_BUFFER, size, ptr::null(), gl::STREAM_DRAW);
+                gl::BufferSubData(
+                    gl::ARRAY_BUFFER,
+                    0,
+                    (self.vertex_data.len() * FOUR_BYTES) as isize,
+                    self.vertex_data.as_ptr() as *const c_void,
+                );
+            }
+
+            while run < runs.len() && runs[run].0 / self.capacity == p {
+                let (start, count) = runs[run];
+                let start = start - p * self.capacity;
+
+                part[start].0.use_texture(0);
+                unsafe {
+                    gl::DrawElements(
+                        gl::TRIANGLES,
+                        (count * 6) as i32,
+                        gl::UNSIGNED_INT,
+                        (start * 6 * FOUR_BYTES) as *const c_void,
+                    );
+                }
+
+                self.draw_calls += 1;
+                run += 1;
+            }
+        }
+
+        self.sprites.clear();
+    }
+
+    // How many draw calls the last `end` took.
+    pub fn get_draw_call_count(&self) -> u32 {
+        self.draw_calls
+    }
+
+    // Frees the buffers and the material's program.
+    pub fn delete(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteBuffers(1, &self.ibo);
+        }
+
+        self.material.delete();
+    }
+
+    // The vertex buffer is filled in `end`, the indices of every quad never change.
+    fn create_buffers(capacity: usize) -> (u32, u32, u32) {
+        let mut indices: Vec<u32> = Vec::with_capacity(capacity * 6);
+        for i in 0..capacity as u32 {
+            indices.extend([i * 4, i * 4 + 1, i * 4 + 2, i * 4 + 2, i * 4 + 3, i * 4].iter());
+        }
+
+        unsafe {
+            let mut vao = 0u32;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+
+            let mut vbo = 0u32;
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (capacity * 4 * SPRITE_VERTEX_DATA_SIZE * FOUR_BYTES) as isize,
+                ptr::null(),
+                gl::STREAM_DRAW,
+            );
+
+            let stride = (SPRITE_VERTEX_DATA_SIZE * FOUR_BYTES) as i32;
+            Renderer::enable_vertex_attrib_ptr(0, 3, stride, 0);
+            Renderer::enable_vertex_attrib_ptr(1, 4, stride, 3 * FOUR_BYTES);
+            Renderer::enable_vertex_attrib_ptr(2, 2, stride, 7 * FOUR_BYTES);
+
+            let mut ibo = 0u32;
+            gl::GenBuffers(1, &mut ibo);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ibo);
+            gl::BufferData(
+                gl::ELEMENT_ARRAY_BUFFER,
+                (indices.len() * FOUR_BYTES) as isize,
+                indices.as_ptr() as *const c_void,
+                gl::STATIC_DRAW,
+            );
+
+            (vao, vbo, ibo)
+        }
+    }
+}
+
+// Splits sprites with these textures into the draw calls of a `SpriteBatch` with `capacity`, as (first sprite, count).
+// Consecutive sprites with the same texture share a draw call, unless the buffers are refilled between them.
+//
+// Should be exposed to the user.
+pub fn sprite_runs(texture_ids: &[u32], capacity: usize) -> Vec<(usize, usize)> {
+    let capacity = capacity.max(1);
+    let mut runs: Vec<(usize, usize)> = vec![];
+    for (i, id) in texture_ids.iter().enumerate() {
+        match runs.last_mut() {
+            Some((start, count)) if texture_ids[*start] == *id && i % capacity != 0 => *count += 1,
+            _ => runs.push((i, 1)),
+        }
+    }
+
+    runs
+}
+
+// Alpha blended without depth testing. Flipped sprites face away, so nothing is culled.
+pub(crate) fn sprite_render_state() -> RenderState {
+    RenderState {
+        blend: Some(BlendState::alpha()),
+        depth: Some(DepthState::new(false, false, CompareFunction::Always)),
+        cull: Some(FaceCulling::None),
+        ..RenderState::default()
+    }
+}
+
+// The four vertices of a sprite, in the order of `Sprite::corners`.
+fn push_sprite_vertices(data: &mut Vec<f32>, sprite: &Sprite) {
+    let (u_min, v_min, u_max, v_max) = sprite.uv_rect;
+    // The bottom of the sprite shows the bottom of the image, which is the larger v.
+    let tex_coords = [(u_min, v_max), (u_max, v_max), (u_max, v_min), (u_min, v_min)];
+    let (r, g, b, a) = sprite.tint;
+
+    for ((x, y), (u, v)) in sprite.corners().iter().zip(tex_coords.iter()) {
+        data.extend([*x, *y, sprite.depth, r, g, b, a, *u, *v].iter());
+    }
+}
@@ -1,6 +1,7 @@
 use std::ffi::c_void;
 use image::GenericImageView;
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum WrappingType {
     Repeat,
     MirroredRepeat,
@@ -8,17 +9,20 @@ pub enum WrappingType {
     ClampBorder(Vec<f32>),
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum FilteringType {
     Nearest,
     Linear,
 }
 
+#[derive(Clone)]
 pub struct TextureData {
     pub width: i32,
     pub height: i32,
     pub data: Vec<u8>
 }
 
+#[derive(Clone)]
 pub struct Texture {
     texture: u32
 }
@@ -29,43 +33,90 @@ impl Texture {
         mipmap_filtering_type: FilteringType,
         mipmap_level: i32,
         texture_data: TextureData) -> Self {
+        Texture::upload(gl::RGB, &wrapping_type, &wrapping_type, filtering_type, mipmap_filtering_type, mipmap_level, texture_data)
+    }
+
+    // Same as `new`, but for data with 4 channels (RGBA) and with separate wrapping for the S and T axes.
+    pub fn from_rgba(wrapping_s: WrappingType,
+        wrapping_t: WrappingType,
+        filtering_type: FilteringType,
+        mipmap_filtering_type: FilteringType,
+        texture_data: TextureData) -> Self {
+        Texture::upload(gl::RGBA, &wrapping_s, &wrapping_t, filtering_type, mipmap_filtering_type, 0, texture_data)
+    }
+
+    // A single channel (red) texture, one byte per pixel, e.g. a mask or a glyph atlas. Clamped to the edges.
+    pub fn from_red(filtering_type: FilteringType, texture_data: TextureData) -> Self {
+        Texture::upload(gl::RED, &WrappingType::ClampEdge, &WrappingType::ClampEdge, filtering_type, filtering_type, 0, texture_data)
+    }
+
+    // Replaces the image of a texture created with `from_red`. Clones of the texture see the new image too.
+    pub fn update_red(&self, texture_data: &TextureData) {
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.texture);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RED as i32,
+                texture_data.width,
+                texture_data.height,
+                0,
+                gl::RED,
+                gl::UNSIGNED_BYTE,
+                texture_data.data.as_ptr() as *const c_void
+            );
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+        }
+    }
+
+    fn upload(format: u32,
+        wrapping_s: &WrappingType,
+        wrapping_t: &WrappingType,
+        filtering_type: FilteringType,
+        mipmap_filtering_type: FilteringType,
+        mipmap_level: i32,
+        texture_data: TextureData) -> Self {
 
         let mut texture: u32 = 0;
         unsafe {
             gl::GenTextures(1, &mut texture);
             gl::BindTexture(gl::TEXTURE_2D, texture);
 
+            // Rows of single channel images aren't padded to 4 bytes.
+            if format == gl::RED {
+                gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            }
             gl::TexImage2D(
                 gl::TEXTURE_2D,
                 mipmap_level,
-                gl::RGB as i32,
+                format as i32,
                 texture_data.width,
                 texture_data.height,
                 0,
-                gl::RGB,
+                format,
                 gl::UNSIGNED_BYTE,
                 texture_data.data.as_ptr() as *const c_void
             );
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
             gl::GenerateMipmap(gl::TEXTURE_2D);
 
-            match &wrapping_type {
-                WrappingType::Repeat => {
-                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
-                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
-                },
-                WrappingType::MirroredRepeat => {
-                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::MIRRORED_REPEAT as i32);
-                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::MIRRORED_REPEAT as i32);
-                },
-                WrappingType::ClampEdge => {
-                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
-                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
-                },
-                WrappingType::ClampBorder(x) => {
-                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_BORDER as i32);
-                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_BORDER as i32);
+            for (axis, wrapping_type) in [(gl::TEXTURE_WRAP_S, wrapping_s), (gl::TEXTURE_WRAP_T, wrapping_t)].iter() {
+                match wrapping_type {
+                    WrappingType::Repeat => {
+                        gl::TexParameteri(gl::TEXTURE_2D, *axis, gl::REPEAT as i32);
+                    },
+                    WrappingType::MirroredRepeat => {
+                        gl::TexParameteri(gl::TEXTURE_2D, *axis, gl::MIRRORED_REPEAT as i32);
+                    },
+                    WrappingType::ClampEdge => {
+                        gl::TexParameteri(gl::TEXTURE_2D, *axis, gl::CLAMP_TO_EDGE as i32);
+                    },
+                    WrappingType::ClampBorder(x) => {
+                        gl::TexParameteri(gl::TEXTURE_2D, *axis, gl::CLAMP_TO_BORDER as i32);
 
-                    gl::TexParameterfv(gl::TEXTURE_2D, gl::TEXTURE_BORDER_COLOR, &x[0]);
+                        gl::TexParameterfv(gl::TEXTURE_2D, gl::TEXTURE_BORDER_COLOR, &x[0]);
+                    }
                 }
             }
 
@@ -93,6 +144,18 @@ impl Texture {
         }
     }
 
+    // The OpenGL name of the texture. Clones of a texture share it.
+    pub fn get_texture_id(&self) -> u32 {
+        self.texture
+    }
+
+    // Frees the texture. Every clone of it becomes unusable.
+    pub fn delete(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.texture);
+        }
+    }
+
     pub fn use_texture(&self, index: usize) {
         unsafe {
             gl::ActiveTexture(gl::TEXTURE0 + index as u32);
@@ -0,0 +1,44 @@
+use crate::math::{Matrix4, Quaternion, Vector3};
+
+// Where an object is placed in the world. Every object starts with the identity transform.
+// With a parent, the transform is relative to the parent object, so moving the parent moves its children too.
+//
+// Should be exposed to the user.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: Vector3,
+    pub rotation: Quaternion,
+    pub scale: Vector3,
+    // Another object (as returned by `create_object`) this transform is relative to.
+    pub parent: Option<u32>,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+impl Transform {
+    pub fn new(translation: Vector3, rotation: Quaternion, scale: Vector3) -> Self {
+        Transform { translation, rotation, scale, parent: None }
+    }
+
+    pub fn identity() -> Self {
+        Transform::new(Vector3::zero(), Quaternion::identity(), Vector3::one())
+    }
+
+    pub fn from_translation(translation: Vector3) -> Self {
+        Transform { translation, ..Transform::identity() }
+    }
+
+    pub fn with_parent(mut self, parent: u32) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    // The transform relative to the parent, ignoring the parent itself.
+    pub fn get_local_matrix(&self) -> Matrix4 {
+        Matrix4::from_transform(self.translation, self.rotation, self.scale)
+    }
+}
@@ -0,0 +1,141 @@
+use std::f32::consts::FRAC_PI_2;
+
+use meliusgfx::animation::{AnimationClip, Channel, ChannelValues, Interpolation, Pose, Skeleton};
+use meliusgfx::math::{Matrix4, Quaternion, Vector3};
+
+fn close(a: Vector3, b: Vector3) -> bool {
+    (a - b).length() < 1e-5
+}
+
+fn clip() -> AnimationClip {
+    AnimationClip::new(Some("test".to_string()), vec![
+        Channel {
+            joint: 0,
+            interpolation: Interpolation::Step,
+            times: vec![0.0, 1.0],
+            values: ChannelValues::Translations(vec![Vector3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 2.0, 0.0)]),
+        },
+        Channel {
+            joint: 1,
+            interpolation: Interpolation::Linear,
+            times: vec![0.0, 2.0],
+            values: ChannelValues::Rotations(vec![
+                Quaternion::identity(),
+                Quaternion::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), FRAC_PI_2),
+            ]),
+        },
+        Channel {
+            joint: 1,
+            interpolation: Interpolation::CubicSpline,
+            times: vec![0.0, 1.0],
+            // In-tangent, value, out-tangent per keyframe. Flat tangents ease in and out.
+            values: ChannelValues::Scales(vec![
+                Vector3::zero(), Vector3::one(), Vector3::zero(),
+                Vector3::zero(), Vector3::one() * 3.0, Vector3::zero(),
+            ]),
+        },
+    ])
+}
+
+#[test]
+fn duration() {
+    assert_eq!(clip().duration, 2.0);
+}
+
+#[test]
+fn step_interpolation() {
+    let mut pose = Pose::new(2);
+    clip().sample(0.99, false, &mut pose);
+    assert_eq!(pose.translations[0], Vector3::new(0.0, 1.0, 0.0));
+
+    clip().sample(1.0, false, &mut pose);
+    assert_eq!(pose.translations[0], Vector3::new(0.0, 2.0, 0.0));
+}
+
+#[test]
+fn linear_rotation_is_slerped() {
+    let mut pose = Pose::new(2);
+    clip().sample(1.0, false, &mut pose);
+
+    let expected = Quaternion::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), FRAC_PI_2 * 0.5);
+    assert!((pose.rotations[1].dot(expected) - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn cubic_spline_interpolation() {
+    let mut pose = Pose::new(2);
+    clip().sample(0.5, false, &mut pose);
+    assert!(close(pose.scales[1], Vector3::one() * 2.0));
+
+    clip().sample(0.25, false, &mut pose);
+    assert!(close(pose.scales[1], Vector3::one() * 1.3125));
+}
+
+#[test]
+fn looping_and_clamping() {
+    let mut looped = Pose::new(2);
+    let mut expected = Pose::new(2);
+    clip().sample(2.5, true, &mut looped);
+    clip().sample(0.5, false, &mut expected);
+    assert_eq!(looped, expected);
+
+    let mut held = Pose::new(2);
+    clip().sample(5.0, false, &mut held);
+    assert_eq!(held.translations[0], Vector3::new(0.0, 2.0, 0.0));
+    assert!(close(held.scales[1], Vector3::one() * 3.0));
+}
+
+#[test]
+fn joint_matrices_follow_the_hierarchy() {
+    let mut bind_pose = Pose::new(2);
+    bind_pose.translations[1] = Vector3::new(1.0, 0.0, 0.0);
+    // Joint 1 is listed before its parent on purpose.
+    let skeleton = Skeleton::new(
+        vec![None, Some(0)],
+        vec![Matrix4::identity(), Matrix4::translation(Vector3::new(-1.0, 0.0, 0.0))],
+        bind_pose.clone(),
+    );
+
+    let mut pose = bind_pose;
+    pose.rotations[0] = Quaternion::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), FRAC_PI_2);
+    let matrices = skeleton.joint_matrices(&pose);
+
+    // A vertex at joint 1 swings around joint 0.
+    assert!(close(matrices[1].transform_point(Vector3::new(1.0, 0.0, 0.0)), Vector3::new(0.0, 1.0, 0.0)));
+    assert!(close(skeleton.model_matrices(&pose)[1].get_translation(), Vector3::new(0.0, 1.0, 0.0)));
+}
+
+#[test]
+#[should_panic]
+fn cyclic_skeleton() {
+    Skeleton::new(vec![Some(1), Some(0)], vec![Matrix4::identity(); 2], Pose::new(2));
+}
+
+#[test]
+fn blend() {
+    let a = Pose::new(1);
+    let mut b = Pose::new(1);
+    b.translations[0] = Vector3::new(2.0, 0.0, 0.0);
+
+    assert_eq!(a.blend(&b, 0.25).translations[0], Vector3::new(0.5, 0.0, 0.0));
+}
+
+#[test]
+fn weights() {
+    let clip = AnimationClip::new(None, vec![Channel {
+        joint: 3,
+        interpolation: Interpolation::Step,
+        times: vec![0.0, 1.0],
+        // Two morph targets per keyframe.
+        values: ChannelValues::Weights(vec![0.0, 1.0, 1.0, 0.0]),
+    }]);
+
+    assert_eq!(clip.sample_weights(0.5, false, 3), Some(vec![0.0, 1.0]));
+    assert_eq!(clip.sample_weights(1.5, false, 3), Some(vec![1.0, 0.0]));
+    assert_eq!(clip.sample_weights(0.5, false, 0), None);
+
+    // Weights don't change the pose.
+    let mut pose = Pose::new(4);
+    clip.sample(0.5, false, &mut pose);
+    assert_eq!(pose, Pose::new(4));
+}
@@ -0,0 +1,136 @@
+use meliusgfx::bounds::{Aabb, BoundingSphere, Frustum};
+use meliusgfx::camera::Camera;
+use meliusgfx::math::{Matrix4, Quaternion, Vector3};
+
+// At the origin looking down -Z with a 90 degree field of view, so at a depth of `d` the frustum goes
+// from -d to d on both X and Y. Near is at 1, far at 100.
+fn frustum() -> Frustum {
+    Frustum::from_matrix(&Camera::perspective(90.0, 1.0, 1.0, 100.0).view_projection_matrix())
+}
+
+// For each plane (left, right, bottom, top, near, far): a center well outside of it and one right on it.
+// Spheres with a radius of 1 and boxes with half extents of 1 around these are outside and straddling.
+fn plane_cases() -> [(&'static str, Vector3, Vector3); 6] {
+    [
+        ("left", Vector3::new(-15.0, 0.0, -10.0), Vector3::new(-10.5, 0.0, -10.0)),
+        ("right", Vector3::new(15.0, 0.0, -10.0), Vector3::new(10.5, 0.0, -10.0)),
+        ("bottom", Vector3::new(0.0, -15.0, -10.0), Vector3::new(0.0, -10.5, -10.0)),
+        ("top", Vector3::new(0.0, 15.0, -10.0), Vector3::new(0.0, 10.5, -10.0)),
+        ("near", Vector3::new(0.0, 0.0, 2.0), Vector3::new(0.0, 0.0, -1.2)),
+        ("far", Vector3::new(0.0, 0.0, -105.0), Vector3::new(0.0, 0.0, -100.5)),
+    ]
+}
+
+fn unit_box(center: Vector3) -> Aabb {
+    Aabb::new(center - Vector3::one(), center + Vector3::one())
+}
+
+fn corners(aabb: &Aabb) -> Vec<Vector3> {
+    (0..8).map(|i| Vector3::new(
+        if i & 1 == 0 { aabb.min.x } else { aabb.max.x },
+        if i & 2 == 0 { aabb.min.y } else { aabb.max.y },
+        if i & 4 == 0 { aabb.min.z } else { aabb.max.z },
+    )).collect()
+}
+
+fn contains(aabb: &Aabb, point: Vector3) -> bool {
+    let tolerance = 1e-4;
+
+    point.x >= aabb.min.x - tolerance && point.x <= aabb.max.x + tolerance
+        && point.y >= aabb.min.y - tolerance && point.y <= aabb.max.y + tolerance
+        && point.z >= aabb.min.z - tolerance && point.z <= aabb.max.z + tolerance
+}
+
+fn some_transform() -> Matrix4 {
+    Matrix4::from_transform(
+        Vector3::new(1.0, -2.0, 3.0),
+        Quaternion::from_euler(0.3, -1.1, 0.7),
+        Vector3::new(2.0, 0.5, 3.0),
+    )
+}
+
+#[test]
+fn planes_face_inwards() {
+    let inside = Vector3::new(1.0, -2.0, -10.0);
+    for plane in frustum().planes.iter() {
+        assert!(plane.signed_distance(inside) > 0.0);
+        assert!((plane.normal.length() - 1.0).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn spheres_inside_outside_and_straddling() {
+    let frustum = frustum();
+    assert!(frustum.intersects_sphere(&BoundingSphere::new(Vector3::new(0.0, 0.0, -10.0), 1.0)));
+    // Bigger than the whole frustum.
+    assert!(frustum.intersects_sphere(&BoundingSphere::new(Vector3::zero(), 1000.0)));
+
+    for (name, outside, straddling) in plane_cases().iter() {
+        assert!(!frustum.intersects_sphere(&BoundingSphere::new(*outside, 1.0)), "outside {}", name);
+        assert!(frustum.intersects_sphere(&BoundingSphere::new(*straddling, 1.0)), "straddling {}", name);
+    }
+}
+
+#[test]
+fn boxes_inside_outside_and_straddling() {
+    let frustum = frustum();
+    assert!(frustum.intersects_aabb(&unit_box(Vector3::new(0.0, 0.0, -10.0))));
+    // Around the whole frustum.
+    assert!(frustum.intersects_aabb(&Aabb::new(Vector3::new(-500.0, -500.0, -500.0), Vector3::new(500.0, 500.0, 500.0))));
+
+    for (name, outside, straddling) in plane_cases().iter() {
+        assert!(!frustum.intersects_aabb(&unit_box(*outside)), "outside {}", name);
+        assert!(frustum.intersects_aabb(&unit_box(*straddling)), "straddling {}", name);
+    }
+}
+
+#[test]
+fn frustum_follows_the_camera() {
+    let mut camera = Camera::perspective(90.0, 1.0, 1.0, 100.0);
+    camera.position = Vector3::new(0.0, 0.0, 50.0);
+    camera.look_at(Vector3::new(50.0, 0.0, 50.0), Vector3::up());
+    let frustum = Frustum::from_matrix(&camera.view_projection_matrix());
+
+    assert!(frustum.intersects_sphere(&BoundingSphere::new(Vector3::new(60.0, 0.0, 50.0), 1.0)));
+    assert!(!frustum.intersects_sphere(&BoundingSphere::new(Vector3::new(0.0, 0.0, 40.0), 1.0)));
+    assert!(!frustum.intersects_aabb(&unit_box(Vector3::new(0.0, 0.0, 40.0))));
+}
+
+#[test]
+fn transformed_boxes_contain_every_corner() {
+    let aabb = Aabb::new(Vector3::new(-1.0, -2.0, -3.0), Vector3::new(1.0, 2.0, 0.5));
+    let matrix = some_transform();
+    let transformed = aabb.transform(&matrix);
+
+    for corner in corners(&aabb) {
+        let point = matrix.transform_point(corner);
+        assert!(contains(&transformed, point), "{:?} isn't in {:?}", point, transformed);
+    }
+}
+
+#[test]
+fn translated_boxes_stay_tight() {
+    let aabb = Aabb::new(Vector3::new(-1.0, -2.0, -3.0), Vector3::new(1.0, 2.0, 0.5));
+    let transformed = aabb.transform(&Matrix4::translation(Vector3::new(5.0, 0.0, -1.0)));
+
+    assert_eq!(transformed, Aabb::new(Vector3::new(4.0, -2.0, -4.0), Vector3::new(6.0, 2.0, -0.5)));
+}
+
+#[test]
+fn non_uniformly_scaled_spheres_use_the_largest_scale() {
+    let sphere = BoundingSphere::new(Vector3::new(1.0, 0.0, 0.0), 1.0);
+    let matrix = Matrix4::from_transform(Vector3::new(0.0, 2.0, 0.0), Quaternion::from_euler(0.3, -1.1, 0.7),
+        Vector3::new(1.0, 3.0, 0.5));
+    let transformed = sphere.transform(&matrix);
+
+    assert!((transformed.radius - 3.0).abs() < 1e-4);
+    assert!(((transformed.center - matrix.transform_point(sphere.center)).length()) < 1e-4);
+
+    // Points on the surface of the sphere end up inside the transformed one.
+    for i in 0..64 {
+        let (theta, phi) = (i as f32 * 0.7, i as f32 * 0.3);
+        let direction = Vector3::new(theta.cos() * phi.sin(), phi.cos(), theta.sin() * phi.sin());
+        let point = matrix.transform_point(sphere.center + direction * sphere.radius);
+        assert!((point - transformed.center).length() <= transformed.radius + 1e-4);
+    }
+}
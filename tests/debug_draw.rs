@@ -0,0 +1,103 @@
+use meliusgfx::bounds::Aabb;
+use meliusgfx::camera::Camera;
+use meliusgfx::debug_draw::DebugDraw;
+use meliusgfx::math::{Matrix4, Vector3};
+
+static RED: (f32, f32, f32, f32) = (1.0, 0.0, 0.0, 1.0);
+
+fn close(a: Vector3, b: Vector3) -> bool {
+    (a - b).length() < 1e-4
+}
+
+#[test]
+fn one_frame_lines_are_removed_after_update() {
+    let mut debug = DebugDraw::new();
+    debug.line(Vector3::zero(), Vector3::one(), RED);
+    assert_eq!(debug.get_lines().len(), 1);
+
+    debug.update(0.0);
+    assert!(debug.get_lines().is_empty());
+}
+
+#[test]
+fn lines_with_a_duration_count_down() {
+    let mut debug = DebugDraw::new();
+    debug.set_duration(1.0);
+    debug.line(Vector3::zero(), Vector3::one(), RED);
+    debug.set_duration(0.0);
+    debug.line(Vector3::zero(), Vector3::one(), RED);
+
+    debug.update(0.6);
+    assert_eq!(debug.get_lines().len(), 1);
+    assert!((debug.get_lines()[0].duration - 0.4).abs() < 1e-6);
+
+    debug.update(0.6);
+    assert!(debug.get_lines().is_empty());
+}
+
+#[test]
+fn depth_test_applies_to_new_shapes() {
+    let mut debug = DebugDraw::new();
+    debug.set_depth_test(false);
+    debug.aabb(&Aabb::new(Vector3::zero(), Vector3::one()), RED);
+    debug.set_depth_test(true);
+    debug.sphere(Vector3::zero(), 1.0, RED);
+
+    let on_top = debug.get_lines().iter().filter(|x| !x.depth_test).count();
+    assert_eq!(on_top, 12);
+    assert_eq!(debug.get_lines().len(), 12 + 3 * 24);
+}
+
+#[test]
+fn aabb_edges_touch_the_corners() {
+    let mut debug = DebugDraw::new();
+    let aabb = Aabb::new(Vector3::new(-1.0, 0.0, 2.0), Vector3::new(1.0, 3.0, 4.0));
+    debug.aabb(&aabb, RED);
+
+    for line in debug.get_lines() {
+        for point in [line.from, line.to].iter() {
+            assert!(point.x.abs() == 1.0 && (point.y == 0.0 || point.y == 3.0) && (point.z == 2.0 || point.z == 4.0));
+        }
+        // Every edge is parallel to an axis.
+        let direction = line.to - line.from;
+        let axes = [direction.x, direction.y, direction.z].iter().filter(|x| **x != 0.0).count();
+        assert_eq!(axes, 1);
+    }
+}
+
+#[test]
+fn frustum_corners_lie_on_the_near_and_far_planes() {
+    let mut camera = Camera::perspective(90.0, 1.0, 1.0, 10.0);
+    camera.position = Vector3::new(0.0, 0.0, 5.0);
+
+    let mut debug = DebugDraw::new();
+    debug.frustum(&camera.view_projection_matrix(), RED);
+    assert_eq!(debug.get_lines().len(), 12);
+
+    let points = debug.get_lines().iter().flat_map(|x| vec![x.from, x.to]).collect::<Vec<_>>();
+    assert!(points.iter().any(|x| close(*x, Vector3::new(1.0, 1.0, 4.0))));
+    assert!(points.iter().any(|x| close(*x, Vector3::new(-10.0, -10.0, -5.0))));
+    assert!(points.iter().all(|x| (x.z - 4.0).abs() < 1e-3 || (x.z + 5.0).abs() < 1e-3));
+}
+
+#[test]
+fn axes_are_colored_arrows() {
+    let mut debug = DebugDraw::new();
+    debug.axes(&Matrix4::translation(Vector3::new(1.0, 2.0, 3.0)), 2.0);
+
+    // A shaft and four lines for the head per axis.
+    assert_eq!(debug.get_lines().len(), 15);
+    let x_axis = debug.get_lines()[0];
+    assert_eq!(x_axis.color, (1.0, 0.0, 0.0, 1.0));
+    assert!(close(x_axis.from, Vector3::new(1.0, 2.0, 3.0)));
+    assert!(close(x_axis.to, Vector3::new(3.0, 2.0, 3.0)));
+}
+
+#[test]
+fn grid_has_lines_along_both_axes() {
+    let mut debug = DebugDraw::new();
+    debug.grid(Vector3::zero(), 10.0, 10, RED);
+
+    assert_eq!(debug.get_lines().len(), 22);
+    assert!(debug.get_lines().iter().all(|x| x.from.y == 0.0 && x.to.y == 0.0));
+}
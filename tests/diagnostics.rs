@@ -0,0 +1,127 @@
+use meliusgfx::diagnostics::{parse_info_log, Severity};
+use meliusgfx::preprocessor::{preprocess_file, preprocess_source};
+
+static LIT_SHADER: &str = "#version 330 core
+uniform float u_value;
+
+void main() {
+    foo = u_value;
+\tbar = 1.0;
+}
+";
+
+#[test]
+fn mesa_logs_have_a_column() {
+    let shader = preprocess_source(LIT_SHADER, &[]).unwrap();
+    let diagnostics = parse_info_log("0:5(5): error: `foo' undeclared\n", &shader.files, &shader.source);
+
+    assert_eq!(diagnostics.len(), 1);
+    let diagnostic = &diagnostics[0];
+    assert_eq!(diagnostic.severity, Severity::Error);
+    assert_eq!(diagnostic.source_string, Some(0));
+    assert_eq!(diagnostic.line, Some(5));
+    assert_eq!(diagnostic.column, Some(5));
+    assert_eq!(diagnostic.message, "`foo' undeclared");
+    assert_eq!(diagnostic.source_line.as_deref(), Some("    foo = u_value;"));
+}
+
+#[test]
+fn nvidia_logs_skip_the_error_code() {
+    let shader = preprocess_source(LIT_SHADER, &[]).unwrap();
+    let diagnostics = parse_info_log("0(6) : warning C7022: unrecognized profile specifier \"bar\"\n",
+        &shader.files, &shader.source);
+
+    assert_eq!(diagnostics.len(), 1);
+    let diagnostic = &diagnostics[0];
+    assert_eq!(diagnostic.severity, Severity::Warning);
+    assert_eq!(diagnostic.line, Some(6));
+    assert_eq!(diagnostic.column, None);
+    assert_eq!(diagnostic.message, "unrecognized profile specifier \"bar\"");
+    assert_eq!(diagnostic.source_line.as_deref(), Some("\tbar = 1.0;"));
+}
+
+#[test]
+fn amd_logs_drop_the_summary_line() {
+    let shader = preprocess_source(LIT_SHADER, &[]).unwrap();
+    let log = "ERROR: 0:5: 'foo' : undeclared identifier \nERROR: 1 compilation errors.  No code generated.\n\0";
+    let diagnostics = parse_info_log(log, &shader.files, &shader.source);
+
+    assert_eq!(diagnostics.len(), 1);
+    let diagnostic = &diagnostics[0];
+    assert_eq!(diagnostic.severity, Severity::Error);
+    assert_eq!(diagnostic.line, Some(5));
+    assert_eq!(diagnostic.message, "'foo' : undeclared identifier");
+    assert_eq!(diagnostic.source_line.as_deref(), Some("    foo = u_value;"));
+}
+
+#[test]
+fn unknown_lines_are_kept_as_info() {
+    let diagnostics = parse_info_log("Vertex shader failed to compile with the following errors:\n", &[], "");
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Info);
+    assert_eq!(diagnostics[0].line, None);
+    assert_eq!(diagnostics[0].file, None);
+}
+
+#[test]
+fn source_strings_map_to_included_files() {
+    let shader = preprocess_file("tests/shaders/preprocessor/main.glsl", &[]).unwrap();
+    let log = "1:2(7): error: syntax error\n2(3) : error C0000: syntax error\nERROR: 0:6: 'main' : redefinition\n";
+    let diagnostics = parse_info_log(log, &shader.files, &shader.source);
+
+    assert_eq!(diagnostics.len(), 3);
+    assert_eq!(diagnostics[0].file.as_deref(), Some("tests/shaders/preprocessor/common.glsl"));
+    assert_eq!(diagnostics[0].source_line.as_deref(), Some("float common_value() { return 1.0; }"));
+    assert_eq!(diagnostics[1].file.as_deref(), Some("tests/shaders/preprocessor/guarded.glsl"));
+    assert_eq!(diagnostics[1].source_line.as_deref(), Some("float guarded_value() { return 2.0; }"));
+    assert_eq!(diagnostics[2].file.as_deref(), Some("tests/shaders/preprocessor/main.glsl"));
+    assert_eq!(diagnostics[2].source_line.as_deref(), Some("void main() {}"));
+}
+
+#[test]
+fn source_lines_follow_legacy_line_directives() {
+    let shader = preprocess_file("tests/shaders/preprocessor/legacy.glsl", &[]).unwrap();
+    let diagnostics = parse_info_log("0:3(1): error: syntax error\n1:2(1): error: syntax error\n",
+        &shader.files, &shader.source);
+
+    assert_eq!(diagnostics[0].source_line.as_deref(), Some("void main() {}"));
+    assert_eq!(diagnostics[1].source_line.as_deref(), Some("float common_value() { return 1.0; }"));
+}
+
+#[test]
+fn unknown_source_strings_have_no_file() {
+    let shader = preprocess_source(LIT_SHADER, &[]).unwrap();
+    let diagnostics = parse_info_log("3:5(1): error: syntax error\n", &shader.files, &shader.source);
+
+    assert_eq!(diagnostics[0].file, None);
+    assert_eq!(diagnostics[0].source_line, None);
+    assert_eq!(diagnostics[0].to_string(), "error: syntax error\n  --> <source 3>:5:1");
+}
+
+#[test]
+fn display_puts_a_caret_under_the_column() {
+    let shader = preprocess_source(LIT_SHADER, &[]).unwrap();
+    let diagnostics = parse_info_log("0:5(11): error: `u_value' is not a float\n", &shader.files, &shader.source);
+
+    let expected = "error: `u_value' is not a float\n  \
+        --> <source>:5:11\n  \
+        |\n\
+        5 |     foo = u_value;\n  \
+        |           ^";
+    assert_eq!(diagnostics[0].to_string(), expected);
+}
+
+#[test]
+fn display_without_a_column_points_at_the_first_character() {
+    let shader = preprocess_source(LIT_SHADER, &[]).unwrap();
+    let diagnostics = parse_info_log("0(6) : error C1008: undefined variable \"bar\"\n", &shader.files, &shader.source);
+
+    // The tab is kept so the caret lines up with the source line.
+    let expected = "error: undefined variable \"bar\"\n  \
+        --> <source>:6\n  \
+        |\n\
+        6 | \tbar = 1.0;\n  \
+        | \t^";
+    assert_eq!(diagnostics[0].to_string(), expected);
+}
@@ -0,0 +1,153 @@
+use meliusgfx::font::{Font, FontError, TextAlign, TextStyle};
+
+// DejaVu Sans, see fonts/LICENSE.
+fn font() -> Font {
+    Font::from_file("tests/fonts/DejaVuSans.ttf", 32.0)
+}
+
+#[test]
+fn missing_file() {
+    match Font::try_from_file("tests/fonts/missing.ttf", 32.0) {
+        Err(FontError::FileNotFound { path }) => assert_eq!(path, "tests/fonts/missing.ttf"),
+        _ => panic!("expected FileNotFound"),
+    }
+}
+
+#[test]
+fn invalid_data() {
+    assert!(matches!(Font::try_from_bytes(vec![0; 64], 32.0), Err(FontError::Invalid)));
+}
+
+#[test]
+fn size_is_ascent_to_descent() {
+    let font = font();
+    assert!((font.get_ascent() - font.get_descent() - 32.0).abs() < 1e-3);
+}
+
+#[test]
+fn kerning_pulls_pairs_together() {
+    let font = font();
+    let style = TextStyle::new();
+
+    let pair = font.measure("AV", &style).0;
+    let separate = font.measure("A", &style).0 + font.measure("V", &style).0;
+    assert!(pair < separate - 1.0);
+}
+
+#[test]
+fn line_breaks() {
+    let mut font = font();
+    let style = TextStyle::new();
+    let line_height = font.get_line_height();
+
+    assert_eq!(font.measure("a\nb\r\nc", &style).1, 2.0 * line_height + 32.0);
+
+    let sprites = font.layout("a\nb", (0.0, 100.0), &style);
+    assert_eq!(sprites.len(), 2);
+    assert!((sprites[0].position.1 - sprites[1].position.1 - line_height).abs() <= 1.0);
+}
+
+#[test]
+fn wrapping_between_words() {
+    let font = font();
+    let style = TextStyle::new();
+    let word = font.measure("hello", &style).0;
+
+    let wrapped = TextStyle::new().max_width(word * 1.5);
+    let (width, height) = font.measure("hello hello hello", &wrapped);
+    assert!((width - word).abs() < 1e-3);
+    assert_eq!(height, 2.0 * font.get_line_height() + 32.0);
+
+    // A word longer than the line stays on it.
+    assert_eq!(font.measure("hello", &TextStyle::new().max_width(1.0)).1, 32.0);
+}
+
+#[test]
+fn alignment() {
+    let mut font = font();
+    let width = font.measure("i", &TextStyle::new()).0;
+
+    let left = font.layout("i", (100.0, 0.0), &TextStyle::new())[0].position.0;
+    let center = font.layout("i", (100.0, 0.0), &TextStyle::new().align(TextAlign::Center))[0].position.0;
+    let right = font.layout("i", (100.0, 0.0), &TextStyle::new().align(TextAlign::Right))[0].position.0;
+    assert!((left - center - width * 0.5).abs() <= 1.0);
+    assert!((left - right - width).abs() <= 1.0);
+
+    let boxed = TextStyle::new().align(TextAlign::Right).max_width(200.0);
+    assert!((font.layout("i", (100.0, 0.0), &boxed)[0].position.0 - (right + 200.0)).abs() <= 1.0);
+}
+
+#[test]
+fn glyphs_are_rasterized_once() {
+    let mut font = font();
+    let style = TextStyle::new().color((1.0, 0.0, 0.0, 1.0));
+
+    let sprites = font.layout("ü ü", (0.0, 0.0), &style);
+    // Spaces have no pixels and the second ü reuses the first one's place in the atlas.
+    assert_eq!(sprites.len(), 2);
+    assert_eq!(sprites[0].uv_rect, sprites[1].uv_rect);
+    assert_eq!(sprites[0].tint, (1.0, 0.0, 0.0, 1.0));
+
+    let atlas = font.get_atlas();
+    let (u_min, v_min, u_max, v_max) = sprites[0].uv_rect;
+    let covered = (((v_min * atlas.height as f32) as i32)..((v_max * atlas.height as f32) as i32))
+        .flat_map(|y| (((u_min * atlas.width as f32) as i32)..((u_max * atlas.width as f32) as i32)).map(move |x| (x, y)))
+        .filter(|(x, y)| atlas.data[(y * atlas.width + x) as usize] > 0)
+        .count();
+    assert!(covered > 0);
+    assert_eq!(atlas.data.iter().filter(|x| **x > 0).count(), covered);
+}
+
+// Every character from '!' up to `last` that the font has, as one line of text.
+fn characters(last: u32) -> String {
+    (0x21..=last).filter_map(std::char::from_u32).collect()
+}
+
+// The glyph's rectangle in the atlas, in pixels.
+fn atlas_rect(font: &Font, uv_rect: (f32, f32, f32, f32)) -> (i32, i32, i32, i32) {
+    let (width, height) = (font.get_atlas().width as f32, font.get_atlas().height as f32);
+    let (u_min, v_min, u_max, v_max) = uv_rect;
+
+    ((u_min * width).round() as i32, (v_min * height).round() as i32, (u_max * width).round() as i32, (v_max * height).round() as i32)
+}
+
+#[test]
+fn atlas_grows_when_full() {
+    let mut font = font();
+    let first = font.layout("A", (0.0, 0.0), &TextStyle::new())[0];
+    let (width, height) = (font.get_atlas().width, font.get_atlas().height);
+    let pixels = atlas_rect(&font, first.uv_rect);
+
+    let text = characters(0x4ff);
+    let sprites = font.layout(&text, (0.0, 0.0), &TextStyle::new());
+    assert!(!font.is_atlas_full());
+    assert_eq!(font.get_atlas().width, width);
+    assert!(font.get_atlas().height > height);
+    assert_eq!(font.get_atlas().data.len(), (font.get_atlas().width * font.get_atlas().height) as usize);
+
+    // Texture coordinates use the final size, even for glyphs added before the atlas grew.
+    for sprite in sprites.iter() {
+        let (u_min, v_min, u_max, v_max) = sprite.uv_rect;
+        assert!(u_min >= 0.0 && v_min >= 0.0 && u_max <= 1.0 && v_max <= 1.0);
+    }
+    // Glyphs that were already there stay where they are.
+    let again = font.layout("A", (0.0, 0.0), &TextStyle::new())[0];
+    assert_eq!(atlas_rect(&font, again.uv_rect), pixels);
+}
+
+#[test]
+fn glyphs_that_do_not_fit_are_tried_again() {
+    // Big enough for the atlas to start out at its largest size.
+    let mut font = Font::from_file("tests/fonts/DejaVuSans.ttf", 400.0);
+    let text = characters(0x17f);
+    let sprites = font.layout(&text, (0.0, 0.0), &TextStyle::new());
+    assert!(font.is_atlas_full());
+    assert!(sprites.len() < text.chars().count());
+
+    // The last character is one of the ones left out, but not for good.
+    let last = text.chars().last().unwrap().to_string();
+    assert!(font.layout(&last, (0.0, 0.0), &TextStyle::new()).is_empty());
+    font.clear_atlas();
+    assert!(!font.is_atlas_full());
+    assert_eq!(font.layout(&last, (0.0, 0.0), &TextStyle::new()).len(), 1);
+}
@@ -0,0 +1,168 @@
+use meliusgfx::camera::Projection;
+use meliusgfx::gltf_import::{self, AlphaMode, GltfError};
+use meliusgfx::math::Vector3;
+use meliusgfx::render::PrimitiveMode;
+use meliusgfx::texture::{FilteringType, WrappingType};
+
+// The sample files are generated by hand and only contain what the tests below check:
+// triangle.gltf - a triangle in an embedded (base64) buffer, a child node and a camera
+// triangle.glb  - the same scene as a binary file, with a PNG texture stored in the binary chunk
+// external.gltf - a line strip in an external buffer with an escaped file name, and an external PNG
+// skinned.gltf  - a triangle skinned to two joints, with an animation using all three interpolations
+// morph.gltf    - a triangle with two morph targets and an animation of their weights
+
+#[test]
+fn embedded_buffer() {
+    let scene = gltf_import::load_gltf("tests/models/triangle.gltf");
+
+    assert_eq!(scene.meshes.len(), 1);
+    let primitive = &scene.meshes[0].primitives[0];
+    assert_eq!(primitive.mode, PrimitiveMode::Triangles);
+    assert_eq!(primitive.indices, vec![0, 1, 2]);
+    assert_eq!(primitive.vertices.len(), 3);
+    assert_eq!(primitive.material, Some(0));
+
+    let vertex = primitive.vertices[1];
+    assert_eq!({ vertex.position }, (1.0, 0.0, 0.0));
+    assert_eq!({ vertex.normals }, (0.0, 0.0, 1.0));
+    assert_eq!({ vertex.tex_coords }, (1.0, 0.0));
+    // Without COLOR_0 the vertices are white.
+    assert_eq!({ vertex.color }, (1.0, 1.0, 1.0, 1.0));
+}
+
+#[test]
+fn pbr_material() {
+    let scene = gltf_import::load_gltf("tests/models/triangle.gltf");
+
+    let material = &scene.materials[0];
+    assert_eq!(material.name.as_deref(), Some("red"));
+    assert_eq!(material.base_color_factor, [1.0, 0.0, 0.0, 1.0]);
+    assert_eq!(material.metallic_factor, 0.25);
+    assert_eq!(material.roughness_factor, 0.75);
+    assert_eq!(material.alpha_mode, AlphaMode::Mask(0.3));
+    assert!(material.double_sided);
+    assert_eq!(material.base_color_texture, None);
+}
+
+#[test]
+fn node_hierarchy() {
+    let scene = gltf_import::load_gltf("tests/models/triangle.gltf");
+
+    assert_eq!(scene.roots, vec![0, 2]);
+    assert_eq!(scene.nodes[0].name.as_deref(), Some("root"));
+    assert_eq!(scene.nodes[0].children, vec![1]);
+    assert_eq!(scene.nodes[0].transform.translation, Vector3::new(1.0, 2.0, 3.0));
+
+    let child = &scene.nodes[1];
+    assert_eq!(child.mesh, Some(0));
+    assert_eq!(child.transform.scale, Vector3::new(2.0, 2.0, 2.0));
+    assert!((child.transform.rotation.y - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+
+    let camera = scene.nodes[2].camera.unwrap();
+    assert_eq!(camera.projection, Projection::Perspective { fov_y: 0.8, near: 0.1, far: 50.0 });
+    assert_eq!(camera.aspect, 1.5);
+}
+
+#[test]
+fn binary_file_with_texture() {
+    let scene = gltf_import::load_gltf("tests/models/triangle.glb");
+
+    assert_eq!(scene.meshes[0].primitives[0].indices, vec![0, 1, 2]);
+    assert_eq!(scene.materials[0].alpha_mode, AlphaMode::Blend);
+    assert_eq!(scene.materials[0].base_color_texture, Some(0));
+
+    let texture = &scene.textures[0];
+    assert_eq!(texture.image, 0);
+    assert_eq!(texture.sampler.mag_filter, FilteringType::Nearest);
+    assert_eq!(texture.sampler.min_filter, FilteringType::Linear);
+    assert_eq!(texture.sampler.wrap_s, WrappingType::ClampEdge);
+    assert_eq!(texture.sampler.wrap_t, WrappingType::MirroredRepeat);
+
+    let image = &scene.images[0];
+    assert_eq!((image.width, image.height), (2, 2));
+    assert_eq!(&image.data[0..4], &[255, 0, 0, 255]);
+    assert_eq!(&image.data[12..16], &[255, 255, 255, 128]);
+}
+
+#[test]
+fn external_files() {
+    let scene = gltf_import::load_gltf("tests/models/external.gltf");
+
+    let primitive = &scene.meshes[0].primitives[0];
+    assert_eq!(primitive.mode, PrimitiveMode::LineStrip);
+    // Primitives without indices are drawn in order.
+    assert_eq!(primitive.indices, vec![0, 1, 2, 3]);
+    assert_eq!(primitive.material, None);
+    assert_eq!({ primitive.vertices[2].position }, (1.0, 1.0, 0.0));
+
+    assert_eq!(scene.images.len(), 1);
+    assert_eq!((scene.images[0].width, scene.images[0].height), (2, 2));
+    // Textures without a sampler repeat and filter linearly.
+    assert_eq!(scene.textures[0].sampler.wrap_s, WrappingType::Repeat);
+    assert_eq!(scene.textures[0].sampler.mag_filter, FilteringType::Linear);
+}
+
+#[test]
+fn missing_file() {
+    match gltf_import::try_load_gltf("tests/models/missing.gltf") {
+        Err(GltfError::FileNotFound { path }) => assert_eq!(path, "tests/models/missing.gltf"),
+        _ => panic!("Expected FileNotFound"),
+    }
+}
+
+#[test]
+fn invalid_file() {
+    let result = gltf_import::parse_gltf(b"{ \"asset\": {} }", std::path::Path::new(""));
+    assert!(matches!(result, Err(GltfError::Invalid(_))));
+}
+
+#[test]
+fn skin_and_animation() {
+    let scene = gltf_import::load_gltf("tests/models/skinned.gltf");
+
+    assert_eq!(scene.nodes[0].skin, Some(0));
+    assert_eq!(scene.skins[0].joints, vec![1, 2]);
+    let skin = scene.meshes[0].primitives[0].skin.as_ref().unwrap();
+    assert_eq!({ skin[1].joints }, (0.0, 1.0, 0.0, 0.0));
+    assert_eq!({ skin[1].weights }, (0.5, 0.5, 0.0, 0.0));
+
+    let skeleton = scene.create_skeleton(0);
+    assert_eq!(skeleton.get_parent(1), Some(0));
+    assert_eq!(skeleton.find_joint("knee"), Some(1));
+    // The bind pose doesn't move any vertex.
+    for matrix in skeleton.joint_matrices(skeleton.get_bind_pose()) {
+        let point = matrix.transform_point(Vector3::new(1.0, 2.0, 3.0));
+        assert!((point - Vector3::new(1.0, 2.0, 3.0)).length() < 1e-5);
+    }
+
+    // The channel animating the mesh node isn't part of the skin.
+    assert_eq!(scene.animations[0].channels.len(), 4);
+    let clip = scene.get_skin_animation(0, 0);
+    assert_eq!(clip.name.as_deref(), Some("bend"));
+    assert_eq!(clip.channels.len(), 3);
+
+    // At the end the hip is moved up by 1, the knee turned by 90 degrees around Z and scaled by 3.
+    let mut pose = skeleton.get_bind_pose().clone();
+    clip.sample(1.0, false, &mut pose);
+    let point = skeleton.joint_matrices(&pose)[1].transform_point(Vector3::new(1.0, 2.0, 0.0));
+    assert!((point - Vector3::new(0.0, 6.0, 0.0)).length() < 1e-5, "{:?}", point);
+}
+
+#[test]
+fn morph_targets() {
+    let scene = gltf_import::load_gltf("tests/models/morph.gltf");
+
+    let targets = &scene.meshes[0].primitives[0].morph_targets;
+    assert_eq!(targets.len(), 2);
+    assert_eq!(targets[0].position_deltas[2], (0.0, 1.0, 0.0));
+    assert_eq!(targets[0].normal_deltas[2], (0.0, 1.0, -1.0));
+    // The second target has no normal deltas.
+    assert_eq!(targets[1].position_deltas[1], (1.0, 0.0, 0.0));
+    assert!(targets[1].normal_deltas.is_empty());
+    assert_eq!(scene.meshes[0].weights, vec![0.5, 0.0]);
+
+    let clip = &scene.animations[0];
+    assert_eq!(clip.sample_weights(0.5, false, 0), Some(vec![0.5, 0.25]));
+    // Only node 0 has animated weights.
+    assert_eq!(clip.sample_weights(0.5, false, 1), None);
+}
@@ -0,0 +1,76 @@
+use meliusgfx::lod::{self, LodMetric};
+
+// Levels 1 and 2 start at a distance of 10 and 20.
+const DISTANCES: [f32; 2] = [10.0, 20.0];
+// Levels 1 and 2 start below half and a quarter of the screen.
+const SCREEN_SIZES: [f32; 2] = [0.5, 0.25];
+
+#[test]
+fn distance_without_hysteresis() {
+    assert_eq!(lod::select_lod(LodMetric::Distance, 5.0, &DISTANCES, 0, 0.0), 0);
+    assert_eq!(lod::select_lod(LodMetric::Distance, 10.0, &DISTANCES, 0, 0.0), 1);
+    assert_eq!(lod::select_lod(LodMetric::Distance, 19.9, &DISTANCES, 0, 0.0), 1);
+    assert_eq!(lod::select_lod(LodMetric::Distance, 25.0, &DISTANCES, 0, 0.0), 2);
+    // The current level doesn't matter.
+    assert_eq!(lod::select_lod(LodMetric::Distance, 5.0, &DISTANCES, 2, 0.0), 0);
+}
+
+#[test]
+fn screen_size_without_hysteresis() {
+    assert_eq!(lod::select_lod(LodMetric::ScreenSize, 1.0, &SCREEN_SIZES, 0, 0.0), 0);
+    assert_eq!(lod::select_lod(LodMetric::ScreenSize, 0.5, &SCREEN_SIZES, 0, 0.0), 1);
+    assert_eq!(lod::select_lod(LodMetric::ScreenSize, 0.3, &SCREEN_SIZES, 0, 0.0), 1);
+    assert_eq!(lod::select_lod(LodMetric::ScreenSize, 0.1, &SCREEN_SIZES, 0, 0.0), 2);
+}
+
+#[test]
+fn no_thresholds_is_always_level_0() {
+    assert_eq!(lod::select_lod(LodMetric::Distance, 1000.0, &[], 0, 0.1), 0);
+    assert_eq!(lod::select_lod(LodMetric::ScreenSize, 0.0, &[], 0, 0.1), 0);
+}
+
+#[test]
+fn distance_hysteresis_delays_switching() {
+    // 10% around the threshold of level 1: coarser from 11 on, finer again below 9.
+    assert_eq!(lod::select_lod(LodMetric::Distance, 10.5, &DISTANCES, 0, 0.1), 0);
+    assert_eq!(lod::select_lod(LodMetric::Distance, 11.0, &DISTANCES, 0, 0.1), 1);
+    assert_eq!(lod::select_lod(LodMetric::Distance, 9.5, &DISTANCES, 1, 0.1), 1);
+    assert_eq!(lod::select_lod(LodMetric::Distance, 8.9, &DISTANCES, 1, 0.1), 0);
+}
+
+#[test]
+fn screen_size_hysteresis_delays_switching() {
+    // 10% around the threshold of level 1: coarser from 0.45 on, finer again above 0.55.
+    assert_eq!(lod::select_lod(LodMetric::ScreenSize, 0.48, &SCREEN_SIZES, 0, 0.1), 0);
+    assert_eq!(lod::select_lod(LodMetric::ScreenSize, 0.45, &SCREEN_SIZES, 0, 0.1), 1);
+    assert_eq!(lod::select_lod(LodMetric::ScreenSize, 0.52, &SCREEN_SIZES, 1, 0.1), 1);
+    assert_eq!(lod::select_lod(LodMetric::ScreenSize, 0.56, &SCREEN_SIZES, 1, 0.1), 0);
+}
+
+#[test]
+fn hysteresis_does_not_flicker_on_a_threshold() {
+    // An object moving back and forth around a threshold keeps the level it reached first.
+    let mut level = 0;
+    for value in [9.0, 10.0, 10.5, 9.5, 10.5, 9.5].iter() {
+        level = lod::select_lod(LodMetric::Distance, *value, &DISTANCES, level, 0.1);
+        assert_eq!(level, 0, "switched at {}", value);
+    }
+
+    level = lod::select_lod(LodMetric::Distance, 12.0, &DISTANCES, level, 0.1);
+    assert_eq!(level, 1);
+    for value in [10.5, 9.5, 10.5, 9.5].iter() {
+        level = lod::select_lod(LodMetric::Distance, *value, &DISTANCES, level, 0.1);
+        assert_eq!(level, 1, "switched at {}", value);
+    }
+}
+
+#[test]
+fn hysteresis_can_skip_levels() {
+    // A jump past both thresholds goes straight to the coarsest level and back.
+    assert_eq!(lod::select_lod(LodMetric::Distance, 30.0, &DISTANCES, 0, 0.1), 2);
+    assert_eq!(lod::select_lod(LodMetric::Distance, 5.0, &DISTANCES, 2, 0.1), 0);
+    // Between the levels, the finer of two coarser levels is used.
+    assert_eq!(lod::select_lod(LodMetric::Distance, 21.0, &DISTANCES, 0, 0.1), 1);
+    // Level 2 is kept slightly inside its threshold.
+    assert_eq!(lod::select_lod(LodMetric::Distance, 19.0, &DISTANCES, 2, 0.1), 2);
+}
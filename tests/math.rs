@@ -0,0 +1,193 @@
+use std::f32::consts::PI;
+
+use meliusgfx::math::{Matrix3, Matrix4, Quaternion, Vector3};
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+fn close_vectors(a: Vector3, b: Vector3) -> bool {
+    (a - b).length() < 1e-4
+}
+
+fn close_matrices(a: &Matrix4, b: &Matrix4) -> bool {
+    a.data.iter().zip(b.data.iter()).all(|(a, b)| close(*a, *b))
+}
+
+// `q` and `-q` are the same rotation.
+fn same_rotation(a: Quaternion, b: Quaternion) -> bool {
+    close(a.dot(b).abs(), 1.0)
+}
+
+fn multiply(matrix: &Matrix3, vector: Vector3) -> Vector3 {
+    let m = &matrix.data;
+
+    Vector3::new(
+        m[0] * vector.x + m[3] * vector.y + m[6] * vector.z,
+        m[1] * vector.x + m[4] * vector.y + m[7] * vector.z,
+        m[2] * vector.x + m[5] * vector.y + m[8] * vector.z,
+    )
+}
+
+fn some_transform() -> Matrix4 {
+    Matrix4::from_transform(
+        Vector3::new(1.0, -2.0, 3.0),
+        Quaternion::from_euler(0.3, -1.1, 0.7),
+        Vector3::new(2.0, 0.5, 3.0),
+    )
+}
+
+#[test]
+fn inverse_undoes_the_matrix() {
+    let matrix = some_transform();
+    let inverse = matrix.inverse().unwrap();
+
+    assert!(close_matrices(&(matrix * inverse), &Matrix4::identity()));
+    assert!(close_matrices(&(inverse * matrix), &Matrix4::identity()));
+
+    let point = Vector3::new(0.5, 4.0, -2.0);
+    assert!(close_vectors(inverse.transform_point(matrix.transform_point(point)), point));
+}
+
+#[test]
+fn inverse_of_a_projection() {
+    let projection = Matrix4::perspective(1.0, 16.0 / 9.0, 0.1, 100.0);
+    let inverse = projection.inverse().unwrap();
+
+    assert!(close_matrices(&(projection * inverse), &Matrix4::identity()));
+}
+
+#[test]
+fn singular_matrices_have_no_inverse() {
+    assert!(Matrix4::scale(Vector3::new(1.0, 0.0, 1.0)).inverse().is_none());
+    assert!(Matrix4 { data: [0.0; 16] }.inverse().is_none());
+}
+
+#[test]
+fn normal_matrix_keeps_normals_perpendicular_to_surfaces() {
+    let matrix = some_transform();
+    let normal_matrix = Matrix3::normal_matrix(&matrix);
+
+    // Two tangents of a surface and its normal.
+    let tangent = Vector3::new(1.0, 1.0, 0.0);
+    let bitangent = Vector3::new(0.0, 1.0, 1.0);
+    let normal = tangent.cross(bitangent);
+
+    let normal = multiply(&normal_matrix, normal);
+    assert!(close(normal.dot(matrix.transform_vector(tangent)), 0.0));
+    assert!(close(normal.dot(matrix.transform_vector(bitangent)), 0.0));
+}
+
+#[test]
+fn normal_matrix_of_a_rotation_is_the_rotation() {
+    let rotation = Quaternion::from_axis_angle(Vector3::new(1.0, 2.0, 3.0), 0.8);
+    let normal_matrix = Matrix3::normal_matrix(&Matrix4::from_transform(Vector3::new(5.0, 5.0, 5.0), rotation, Vector3::one()));
+
+    for axis in [Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 0.0, 1.0)].iter() {
+        assert!(close_vectors(multiply(&normal_matrix, *axis), rotation.rotate(*axis)));
+    }
+}
+
+#[test]
+fn normal_matrix_of_a_singular_matrix_is_the_identity() {
+    let normal_matrix = Matrix3::normal_matrix(&Matrix4::scale(Vector3::new(1.0, 1.0, 0.0)));
+
+    assert_eq!(normal_matrix, Matrix3::identity());
+}
+
+#[test]
+fn from_rotation_axes_recovers_the_rotation() {
+    // Covers every branch: a positive trace and a large x, y and z diagonal element (180 degree turns).
+    let rotations = [
+        Quaternion::identity(),
+        Quaternion::from_euler(0.3, -1.1, 0.7),
+        Quaternion::from_axis_angle(Vector3::new(1.0, 0.0, 0.0), PI),
+        Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), PI),
+        Quaternion::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), PI),
+        Quaternion::from_axis_angle(Vector3::new(1.0, 1.0, 0.2), 3.0),
+    ];
+
+    for rotation in rotations.iter() {
+        let recovered = Quaternion::from_rotation_axes(
+            rotation.rotate(Vector3::new(1.0, 0.0, 0.0)),
+            rotation.rotate(Vector3::new(0.0, 1.0, 0.0)),
+            rotation.rotate(Vector3::new(0.0, 0.0, 1.0)),
+        );
+
+        assert!(same_rotation(recovered, *rotation), "{:?} became {:?}", rotation, recovered);
+    }
+}
+
+#[test]
+fn slerp_hits_both_ends() {
+    let a = Quaternion::from_axis_angle(Vector3::up(), 0.2);
+    let b = Quaternion::from_axis_angle(Vector3::up(), 1.4);
+
+    assert!(same_rotation(a.slerp(b, 0.0), a));
+    assert!(same_rotation(a.slerp(b, 1.0), b));
+}
+
+#[test]
+fn slerp_moves_at_constant_angular_speed() {
+    let a = Quaternion::identity();
+    let b = Quaternion::from_axis_angle(Vector3::up(), 2.0);
+
+    for i in 0..=4 {
+        let t = i as f32 / 4.0;
+        assert!(same_rotation(a.slerp(b, t), Quaternion::from_axis_angle(Vector3::up(), 2.0 * t)));
+    }
+}
+
+#[test]
+fn slerp_takes_the_shortest_path() {
+    let a = Quaternion::from_axis_angle(Vector3::up(), 0.0);
+    let b = Quaternion::from_axis_angle(Vector3::up(), 1.0);
+    let negated = Quaternion::new(-b.x, -b.y, -b.z, -b.w);
+
+    // Halfway to -b is the same as halfway to b, not a turn the long way around.
+    assert!(same_rotation(a.slerp(negated, 0.5), Quaternion::from_axis_angle(Vector3::up(), 0.5)));
+}
+
+#[test]
+fn slerp_of_nearly_identical_rotations_stays_normalized() {
+    let a = Quaternion::from_axis_angle(Vector3::up(), 1.0);
+    let b = Quaternion::from_axis_angle(Vector3::up(), 1.0001);
+    let result = a.slerp(b, 0.5);
+
+    assert!(close(result.dot(result), 1.0));
+    assert!(same_rotation(result, a));
+}
+
+// A glTF asset in millimetres, turned a bit.
+fn millimetre_transform() -> Matrix4 {
+    Matrix4::from_transform(Vector3::new(0.2, 0.0, -0.1), Quaternion::from_euler(0.3, -1.1, 0.7), Vector3::new(0.001, 0.001, 0.001))
+}
+
+#[test]
+fn normal_matrix_keeps_the_rotation_of_tiny_scales() {
+    let rotation = Quaternion::from_euler(0.3, -1.1, 0.7);
+    let normal_matrix = Matrix3::normal_matrix(&millimetre_transform());
+    assert_ne!(normal_matrix, Matrix3::identity());
+
+    // Normals are normalized in the shader, so only the direction has to match.
+    for axis in [Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 0.0, 1.0)].iter() {
+        assert!(close_vectors(multiply(&normal_matrix, *axis).normalize(), rotation.rotate(*axis)));
+    }
+}
+
+#[test]
+fn inverse_of_tiny_scales() {
+    let matrix = millimetre_transform();
+    let inverse = matrix.inverse().unwrap();
+
+    let point = Vector3::new(0.5, 4.0, -2.0);
+    assert!(close_vectors(matrix.transform_point(inverse.transform_point(point)), point));
+}
+
+#[test]
+fn inverse_of_a_deep_orthographic_projection() {
+    let projection = Matrix4::orthographic(-500.0, 500.0, -500.0, 500.0, 0.1, 10000.0);
+    let inverse = projection.inverse().unwrap();
+
+    assert!(close_matrices(&(projection * inverse), &Matrix4::identity()));
+}
@@ -0,0 +1,163 @@
+use meliusgfx::math::Vector3;
+use meliusgfx::mesh;
+use meliusgfx::render::Vertex;
+
+fn position(vertex: &Vertex) -> Vector3 {
+    Vector3::from(vertex.position)
+}
+
+fn normal(vertex: &Vertex) -> Vector3 {
+    Vector3::from(vertex.normals)
+}
+
+fn assert_counts((vertices, indices): &(Vec<Vertex>, Vec<u32>), vertex_count: usize, index_count: usize) {
+    assert_eq!(vertices.len(), vertex_count);
+    assert_eq!(indices.len(), index_count);
+}
+
+fn assert_unit_normals(vertices: &[Vertex]) {
+    for vertex in vertices.iter() {
+        assert!((normal(vertex).length() - 1.0).abs() < 1e-4, "{:?} isn't unit length", normal(vertex));
+    }
+}
+
+// `outwards(position)` is a direction the normal at that position shouldn't point away from.
+fn assert_outward_normals<F>(vertices: &[Vertex], outwards: F)
+    where F: Fn(Vector3) -> Vector3 {
+    for vertex in vertices.iter() {
+        let direction = outwards(position(vertex));
+        assert!(normal(vertex).dot(direction) > 0.0, "{:?} points inwards at {:?}", normal(vertex), position(vertex));
+    }
+}
+
+// Every triangle is counter-clockwise when seen from the side its normals point to.
+// Triangles with no area (at the poles of a sphere or the tip of a cone) have no winding and are skipped.
+fn assert_counter_clockwise(vertices: &[Vertex], indices: &[u32]) {
+    assert_eq!(indices.len() % 3, 0);
+    for triangle in indices.chunks(3) {
+        let [a, b, c] = [&vertices[triangle[0] as usize], &vertices[triangle[1] as usize], &vertices[triangle[2] as usize]];
+        let face = (position(b) - position(a)).cross(position(c) - position(a));
+        if face.length() < 1e-6 {
+            continue;
+        }
+
+        let average = normal(a) + normal(b) + normal(c);
+        assert!(face.dot(average) > 0.0, "{:?} is clockwise", triangle);
+    }
+}
+
+fn assert_closed_shape(shape: &(Vec<Vertex>, Vec<u32>)) {
+    let (vertices, indices) = shape;
+    assert_unit_normals(vertices);
+    assert_outward_normals(vertices, |x| x);
+    assert_counter_clockwise(vertices, indices);
+}
+
+#[test]
+fn quad() {
+    let shape = mesh::quad(2.0, 1.0);
+    assert_counts(&shape, 4, 6);
+    assert_unit_normals(&shape.0);
+    assert_outward_normals(&shape.0, |_| Vector3::new(0.0, 0.0, 1.0));
+    assert_counter_clockwise(&shape.0, &shape.1);
+}
+
+#[test]
+fn cube() {
+    let shape = mesh::cube(2.0);
+    assert_counts(&shape, 24, 36);
+    assert_closed_shape(&shape);
+}
+
+#[test]
+fn plane() {
+    let shape = mesh::plane(4.0, 2.0, 4, 3);
+    assert_counts(&shape, 5 * 4, 4 * 3 * 6);
+    assert_unit_normals(&shape.0);
+    assert_outward_normals(&shape.0, |_| Vector3::up());
+    assert_counter_clockwise(&shape.0, &shape.1);
+
+    // Subdivisions below 1 are raised to 1.
+    assert_counts(&mesh::plane(1.0, 1.0, 0, 0), 4, 6);
+}
+
+#[test]
+fn uv_sphere() {
+    let shape = mesh::uv_sphere(1.5, 16, 8);
+    assert_counts(&shape, 17 * 9, 16 * 8 * 6);
+    assert_closed_shape(&shape);
+
+    for vertex in shape.0.iter() {
+        assert!((position(vertex).length() - 1.5).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn icosphere() {
+    for subdivisions in 0..3 {
+        let shape = mesh::icosphere(2.0, subdivisions);
+        let triangles = 20 * 4usize.pow(subdivisions);
+        assert_eq!(shape.1.len(), triangles * 3);
+        // The seam and the poles get extra copies of some vertices.
+        assert!(shape.0.len() >= triangles / 2 + 2);
+        assert_closed_shape(&shape);
+    }
+}
+
+#[test]
+fn cylinder() {
+    let shape = mesh::cylinder(1.0, 2.0, 12);
+    assert_counts(&shape, 13 * 2 + 2 * 13, 12 * 6 + 2 * 12 * 3);
+    assert_closed_shape(&shape);
+}
+
+#[test]
+fn cone() {
+    let shape = mesh::cone(1.0, 2.0, 12);
+    assert_counts(&shape, 13 * 2 + 13, 12 * 6 + 12 * 3);
+    assert_closed_shape(&shape);
+}
+
+#[test]
+fn torus() {
+    let shape = mesh::torus(2.0, 0.5, 16, 8);
+    assert_counts(&shape, 17 * 9, 16 * 8 * 6);
+    assert_unit_normals(&shape.0);
+    // Away from the circle through the middle of the tube.
+    assert_outward_normals(&shape.0, |x| x - Vector3::new(x.x, 0.0, x.z).normalize() * 2.0);
+    assert_counter_clockwise(&shape.0, &shape.1);
+}
+
+#[test]
+fn capsule() {
+    let shape = mesh::capsule(0.5, 1.0, 12, 4);
+    assert_counts(&shape, 13 * 2 * 5, 12 * (2 * 4 + 1) * 6);
+    assert_closed_shape(&shape);
+
+    let top = shape.0.iter().map(|x| position(x).y).fold(f32::MIN, f32::max);
+    assert!((top - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn segment_counts_are_raised_to_their_minimum() {
+    assert_counts(&mesh::uv_sphere(1.0, 0, 0), 4 * 3, 3 * 2 * 6);
+    assert_counts(&mesh::cylinder(1.0, 1.0, 1), 4 * 2 + 2 * 4, 3 * 6 + 2 * 3 * 3);
+    assert_counts(&mesh::torus(1.0, 0.5, 0, 0), 4 * 4, 3 * 3 * 6);
+}
+
+#[test]
+fn seams_are_in_the_same_place() {
+    // The first and last column of every row, or the first and last row of the torus' tube.
+    let (sphere, _) = mesh::uv_sphere(1.0, 16, 8);
+    for row in sphere.chunks(17) {
+        assert_eq!(position(&row[0]), position(&row[16]));
+    }
+
+    let (torus, _) = mesh::torus(2.0, 0.5, 16, 8);
+    for row in torus.chunks(17) {
+        assert_eq!(position(&row[0]), position(&row[16]));
+    }
+    for column in 0..17 {
+        assert_eq!(position(&torus[column]), position(&torus[8 * 17 + column]));
+    }
+}
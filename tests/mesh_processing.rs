@@ -0,0 +1,192 @@
+use meliusgfx::math::Vector3;
+use meliusgfx::mesh;
+use meliusgfx::mesh_processing;
+use meliusgfx::render::Vertex;
+
+// The triangles of an index list, each rotated so it starts at its smallest index (which keeps the winding), sorted.
+fn triangle_set(indices: &[u32]) -> Vec<[u32; 3]> {
+    let mut triangles = indices.chunks(3).map(|x| {
+        let first = (0..3).min_by_key(|i| x[*i]).unwrap();
+        [x[first], x[(first + 1) % 3], x[(first + 2) % 3]]
+    }).collect::<Vec<_>>();
+    triangles.sort_unstable();
+
+    triangles
+}
+
+// The triangles in a scrambled order, like an exporter that doesn't care about the vertex cache.
+fn scramble(indices: &[u32]) -> Vec<u32> {
+    let count = indices.len() / 3;
+    // 7919 is prime and doesn't divide the triangle counts used below, so this visits every triangle once.
+    (0..count).flat_map(|i| {
+        let triangle = i * 7919 % count;
+        indices[triangle * 3..triangle * 3 + 3].to_vec()
+    }).collect()
+}
+
+// Every vertex of every triangle on its own, as if the mesh had no index buffer.
+fn unindexed(vertices: &[Vertex], indices: &[u32]) -> (Vec<Vertex>, Vec<u32>) {
+    (indices.iter().map(|x| vertices[*x as usize]).collect(), (0..indices.len() as u32).collect())
+}
+
+fn position(vertices: &[Vertex], index: u32) -> Vector3 {
+    Vector3::from(vertices[index as usize].position)
+}
+
+// A sphere without texture seams, so every edge is shared by two triangles.
+fn closed_sphere() -> (Vec<Vertex>, Vec<u32>) {
+    let (mut vertices, indices) = mesh::icosphere(1.0, 3);
+    for vertex in vertices.iter_mut() {
+        vertex.tex_coords = (0.0, 0.0);
+    }
+
+    mesh_processing::weld_vertices(&vertices, &indices, 0.0001)
+}
+
+#[test]
+fn optimize_vertex_cache_keeps_the_triangles() {
+    let (vertices, indices) = mesh::plane(1.0, 1.0, 24, 24);
+    let scrambled = scramble(&indices);
+    let optimized = mesh_processing::optimize_vertex_cache(&scrambled, vertices.len());
+
+    assert_eq!(triangle_set(&optimized), triangle_set(&indices));
+}
+
+#[test]
+fn optimize_vertex_cache_lowers_the_miss_ratio() {
+    let (vertices, indices) = mesh::plane(1.0, 1.0, 24, 24);
+    let scrambled = scramble(&indices);
+    let optimized = mesh_processing::optimize_vertex_cache(&scrambled, vertices.len());
+
+    let before = mesh_processing::average_cache_miss_ratio(&scrambled, 32);
+    let after = mesh_processing::average_cache_miss_ratio(&optimized, 32);
+    assert!(after < before, "{} isn't lower than {}", after, before);
+    assert!(after < 1.0, "{} is too high for a grid", after);
+}
+
+#[test]
+fn average_cache_miss_ratio_bounds() {
+    // Every triangle has its own vertices, so nothing is ever reused.
+    assert_eq!(mesh_processing::average_cache_miss_ratio(&[0, 1, 2, 3, 4, 5], 32), 3.0);
+    // The second triangle only adds one vertex.
+    assert_eq!(mesh_processing::average_cache_miss_ratio(&[0, 1, 2, 2, 1, 3], 32), 2.0);
+    assert_eq!(mesh_processing::average_cache_miss_ratio(&[], 32), 0.0);
+}
+
+#[test]
+fn weld_vertices_merges_duplicates() {
+    let (vertices, indices) = mesh::cube(2.0);
+    let (copies, copy_indices) = unindexed(&vertices, &indices);
+    assert_eq!(copies.len(), 36);
+
+    let (welded, welded_indices) = mesh_processing::weld_vertices(&copies, &copy_indices, 0.0);
+    // Corners are shared by three sides with different normals, so they stay split.
+    assert_eq!(welded.len(), 24);
+    assert_eq!(welded_indices.len(), 36);
+    for (original, welded_index) in copy_indices.iter().zip(welded_indices.iter()) {
+        assert_eq!(position(&copies, *original), position(&welded, *welded_index));
+    }
+}
+
+#[test]
+fn weld_vertices_merges_nearly_identical_vertices() {
+    let (vertices, indices) = mesh::cube(2.0);
+    let (mut copies, copy_indices) = unindexed(&vertices, &indices);
+    // Every copy is moved by a different amount, all well below the epsilon.
+    for (i, vertex) in copies.iter_mut().enumerate() {
+        vertex.position.0 += i as f32 * 0.000001;
+    }
+
+    assert_eq!(mesh_processing::weld_vertices(&copies, &copy_indices, 0.0).0.len(), 36);
+    assert_eq!(mesh_processing::weld_vertices(&copies, &copy_indices, 0.0001).0.len(), 24);
+}
+
+#[test]
+fn simplify_reaches_its_target() {
+    let (vertices, indices) = closed_sphere();
+    let target = indices.len() / 4;
+    let simplified = mesh_processing::simplify(&vertices, &indices, target);
+
+    assert!(simplified.len() <= target, "{} indices left, wanted {}", simplified.len(), target);
+    assert!(simplified.len() >= target / 2, "{} indices left, wanted {}", simplified.len(), target);
+    assert_eq!(simplified.len() % 3, 0);
+}
+
+#[test]
+fn simplify_does_not_flip_triangles() {
+    let (vertices, indices) = closed_sphere();
+    let simplified = mesh_processing::simplify(&vertices, &indices, indices.len() / 8);
+
+    // All triangles of the sphere face outwards, and still do after the collapses.
+    for triangle in simplified.chunks(3) {
+        let (a, b, c) = (position(&vertices, triangle[0]), position(&vertices, triangle[1]), position(&vertices, triangle[2]));
+        let normal = (b - a).cross(c - a);
+        assert!(normal.dot(a + b + c) > 0.0, "{:?} was flipped", triangle);
+    }
+}
+
+#[test]
+fn simplify_keeps_borders() {
+    let (vertices, indices) = mesh::plane(1.0, 1.0, 8, 8);
+    let simplified = mesh_processing::simplify(&vertices, &indices, indices.len() / 4);
+    assert!(simplified.len() < indices.len());
+
+    // Every vertex on the edge of the plane is still used.
+    for (i, vertex) in vertices.iter().enumerate() {
+        let (x, z) = (vertex.position.0, vertex.position.2);
+        if x.abs() > 0.4999 || z.abs() > 0.4999 {
+            assert!(simplified.contains(&(i as u32)), "border vertex {} was removed", i);
+        }
+    }
+}
+
+#[test]
+fn compute_tangents_follow_u() {
+    let (vertices, indices) = mesh::quad(1.0, 1.0);
+    let tangents = mesh_processing::compute_tangents(&vertices, &indices);
+
+    assert_eq!(tangents.len(), vertices.len());
+    for tangent in tangents.iter() {
+        assert!((Vector3::new(tangent[0], tangent[1], tangent[2]) - Vector3::new(1.0, 0.0, 0.0)).length() < 1e-4);
+        assert_eq!(tangent[3], 1.0);
+    }
+}
+
+#[test]
+fn compute_smooth_normals_are_shared_across_seams() {
+    let (mut vertices, indices) = mesh::uv_sphere(1.0, 16, 8);
+    for vertex in vertices.iter_mut() {
+        vertex.normals = (0.0, 0.0, 1.0);
+    }
+    mesh_processing::compute_smooth_normals(&mut vertices, &indices);
+
+    for (i, a) in vertices.iter().enumerate() {
+        let normal = Vector3::from(a.normals);
+        // A sphere's normals point away from its center, give or take the faceting next to the poles.
+        assert!(normal.dot(Vector3::from(a.position).normalize()) > 0.98, "{:?} at {:?}", normal, { a.position });
+
+        // Seam vertices are separate vertices in the same place, with different texture coordinates.
+        for b in vertices[i + 1..].iter().filter(|b| (Vector3::from(b.position) - Vector3::from(a.position)).length() < 1e-6) {
+            assert_eq!(Vector3::from(b.normals), normal);
+        }
+    }
+}
+
+#[test]
+fn compute_flat_normals_give_every_triangle_its_own_vertices() {
+    let (vertices, indices) = mesh::icosphere(1.0, 1);
+    let (flat, flat_indices) = mesh_processing::compute_flat_normals(&vertices, &indices);
+
+    assert_eq!(flat.len(), indices.len());
+    assert_eq!(flat_indices, (0..indices.len() as u32).collect::<Vec<_>>());
+    for (triangle, original) in flat.chunks(3).zip(indices.chunks(3)) {
+        let (a, b, c) = (Vector3::from(triangle[0].position), Vector3::from(triangle[1].position), Vector3::from(triangle[2].position));
+        let face = (b - a).cross(c - a).normalize();
+        for (vertex, index) in triangle.iter().zip(original.iter()) {
+            assert_eq!(Vector3::from(vertex.position), position(&vertices, *index));
+            assert!((Vector3::from(vertex.normals) - face).length() < 1e-4);
+        }
+        // Counter-clockwise triangles of a sphere face outwards.
+        assert!(face.dot(a + b + c) > 0.0);
+    }
+}
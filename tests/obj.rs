@@ -0,0 +1,68 @@
+use std::path::Path;
+
+use meliusgfx::obj::{self, ObjError};
+
+// colored.obj - two triangles, one using `red` from colored.mtl and one using a material no `.mtl` file defines.
+//               It also references missing.mtl, which doesn't exist.
+
+#[test]
+fn materials_are_loaded_from_mtllib() {
+    let model = obj::load_obj("tests/models/colored.obj");
+
+    assert_eq!(model.materials.len(), 1);
+    let red = &model.materials[0];
+    assert_eq!(red.name, "red");
+    assert_eq!(red.diffuse_color, (1.0, 0.0, 0.0));
+    assert_eq!(red.opacity, 0.5);
+    assert!(red.diffuse_texture.as_ref().unwrap().ends_with("checker.png"));
+
+    let mesh = model.meshes.iter().find(|x| x.material == Some(0)).unwrap();
+    assert_eq!(mesh.indices.len(), 3);
+    let color = mesh.vertices[0].color;
+    assert_eq!((color.0, color.1, color.2, color.3), (1.0, 0.0, 0.0, 0.5));
+}
+
+#[test]
+fn unknown_materials_fall_back_to_the_default() {
+    let model = obj::load_obj("tests/models/colored.obj");
+
+    let mesh = model.meshes.iter().find(|x| x.material.is_none()).unwrap();
+    assert_eq!(mesh.indices.len(), 3);
+    let color = mesh.vertices[0].color;
+    assert_eq!((color.0, color.1, color.2, color.3), (1.0, 1.0, 1.0, 1.0));
+}
+
+#[test]
+fn missing_mtllib_is_skipped() {
+    let source = "mtllib does_not_exist.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl red\nf 1 2 3\n";
+    let model = obj::parse_obj(source, "inline.obj", Path::new("tests/models")).unwrap();
+
+    assert!(model.materials.is_empty());
+    assert_eq!(model.meshes.len(), 1);
+    assert_eq!(model.meshes[0].material, None);
+}
+
+#[test]
+fn polygons_are_triangulated_and_vertices_shared() {
+    let source = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n";
+    let model = obj::parse_obj(source, "inline.obj", Path::new("")).unwrap();
+
+    assert_eq!(model.meshes[0].vertices.len(), 4);
+    assert_eq!(model.meshes[0].indices, vec![0, 1, 2, 0, 2, 3]);
+}
+
+#[test]
+fn missing_obj_files_are_reported() {
+    match obj::try_load_obj("tests/models/does_not_exist.obj") {
+        Err(ObjError::FileNotFound { path }) => assert_eq!(path, "tests/models/does_not_exist.obj"),
+        _ => panic!("Expected a missing file"),
+    }
+}
+
+#[test]
+fn faces_referencing_missing_vertices_are_errors() {
+    match obj::parse_obj("v 0 0 0\nf 1 2 3\n", "inline.obj", Path::new("")) {
+        Err(ObjError::Parse { line, .. }) => assert_eq!(line, 2),
+        _ => panic!("Expected a parse error"),
+    }
+}
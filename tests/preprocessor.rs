@@ -0,0 +1,123 @@
+use meliusgfx::preprocessor::{preprocess_file, preprocess_source, PreprocessError};
+
+static DIRECTORY: &str = "tests/shaders/preprocessor";
+
+fn path(name: &str) -> String {
+    format!("{}/{}", DIRECTORY, name)
+}
+
+#[test]
+fn defines_are_injected_after_version() {
+    let shader = preprocess_source("#version 330 core\nvoid main() {}\n", &[("MAX_LIGHTS", "4")]).unwrap();
+
+    assert_eq!(shader.source, "#version 330 core\n#define MAX_LIGHTS 4\n#line 2 0\nvoid main() {}\n");
+    assert_eq!(shader.defines, vec![("MAX_LIGHTS".to_string(), "4".to_string())]);
+}
+
+#[test]
+fn shaders_without_version_start_at_line_one() {
+    let shader = preprocess_source("void main() {}\n", &[]).unwrap();
+
+    // Without `#version` the shader is GLSL 1.10, where `#line 0` makes the next line 1.
+    assert_eq!(shader.source, "#line 0 0\nvoid main() {}\n");
+}
+
+#[test]
+fn includes_are_inlined_with_line_directives() {
+    let shader = preprocess_file(&path("main.glsl"), &[]).unwrap();
+
+    let expected = "#version 330 core\n\
+        #line 2 0\n\
+        #line 1 1\n\
+        \n\
+        float common_value() { return 1.0; }\n\
+        #line 3 0\n\
+        #line 4 0\n\
+        #line 1 2\n\
+        #ifndef GUARDED_GLSL\n\
+        #define GUARDED_GLSL\n\
+        float guarded_value() { return 2.0; }\n\
+        #endif\n\
+        #line 5 0\n\
+        #line 6 0\n\
+        void main() {}\n";
+    assert_eq!(shader.source, expected);
+}
+
+#[test]
+fn source_string_numbers_map_to_files() {
+    let shader = preprocess_file(&path("main.glsl"), &[]).unwrap();
+
+    assert_eq!(shader.files, vec![path("main.glsl"), path("common.glsl"), path("guarded.glsl")]);
+    assert_eq!(shader.file_name(1), Some(path("common.glsl").as_str()));
+    assert_eq!(shader.file_name(3), None);
+}
+
+#[test]
+fn pragma_once_includes_a_file_once() {
+    let shader = preprocess_file(&path("main.glsl"), &[]).unwrap();
+
+    assert_eq!(shader.source.matches("common_value").count(), 1);
+    assert!(!shader.source.contains("#pragma once"));
+}
+
+#[test]
+fn include_guards_include_a_file_once() {
+    let shader = preprocess_file(&path("main.glsl"), &[]).unwrap();
+
+    assert_eq!(shader.source.matches("guarded_value").count(), 1);
+}
+
+#[test]
+fn include_cycles_are_detected() {
+    match preprocess_file(&path("cycle_a.glsl"), &[]) {
+        Err(PreprocessError::IncludeCycle(chain)) => {
+            assert_eq!(chain.len(), 3);
+            assert!(chain[0].ends_with("cycle_a.glsl"));
+            assert!(chain[1].ends_with("cycle_b.glsl"));
+            assert!(chain[2].ends_with("cycle_a.glsl"));
+        }
+        _ => panic!("Expected an include cycle"),
+    }
+}
+
+#[test]
+fn missing_includes_report_the_including_line() {
+    match preprocess_file(&path("missing.glsl"), &[]) {
+        Err(PreprocessError::FileNotFound { path: missing, included_from: Some((file, line)) }) => {
+            assert_eq!(missing, path("does_not_exist.glsl"));
+            assert_eq!(file, path("missing.glsl"));
+            assert_eq!(line, 3);
+        }
+        _ => panic!("Expected a missing include"),
+    }
+}
+
+#[test]
+fn malformed_includes_are_rejected() {
+    match preprocess_source("#version 330 core\n#include common.glsl\n", &[]) {
+        Err(PreprocessError::MalformedInclude { line, .. }) => assert_eq!(line, 2),
+        _ => panic!("Expected a malformed include"),
+    }
+}
+
+#[test]
+fn line_directives_use_legacy_numbering_before_glsl_330() {
+    let shader = preprocess_file(&path("legacy.glsl"), &[]).unwrap();
+
+    let expected = "#version 120\n\
+        #line 1 0\n\
+        #line 0 1\n\
+        \n\
+        float common_value() { return 1.0; }\n\
+        #line 2 0\n\
+        void main() {}\n";
+    assert_eq!(shader.source, expected);
+}
+
+#[test]
+fn glsl_es_300_uses_the_new_line_numbering() {
+    let shader = preprocess_source("#version 300 es\nvoid main() {}\n", &[]).unwrap();
+
+    assert_eq!(shader.source, "#version 300 es\n#line 2 0\nvoid main() {}\n");
+}
@@ -0,0 +1,136 @@
+use meliusgfx::math::{Quaternion, Vector3};
+use meliusgfx::scene::{Light, SceneGraph};
+use meliusgfx::transform::Transform;
+
+fn close_vectors(a: Vector3, b: Vector3) -> bool {
+    (a - b).length() < 1e-4
+}
+
+fn world_position(scene: &SceneGraph, node: u32) -> Vector3 {
+    scene.get_world_matrix(node).get_translation()
+}
+
+// root -> a -> b, and root -> c
+fn tree() -> (SceneGraph, [u32; 4]) {
+    let mut scene = SceneGraph::new();
+    let root = scene.create_node("root", None);
+    let a = scene.create_node("a", Some(root));
+    let b = scene.create_node("b", Some(a));
+    let c = scene.create_node("c", Some(root));
+
+    (scene, [root, a, b, c])
+}
+
+// Gives every node a light and returns the ones `collect_lights` considers enabled.
+fn enabled_nodes(scene: &mut SceneGraph, nodes: &[u32]) -> Vec<u32> {
+    for node in nodes.iter() {
+        scene.attach_light(*node, Light::directional((1.0, 1.0, 1.0), 1.0));
+    }
+
+    scene.collect_lights().iter().map(|x| x.node).collect()
+}
+
+#[test]
+fn local_transforms_propagate_to_children() {
+    let (mut scene, [root, a, b, _]) = tree();
+    scene.set_translation(a, Vector3::new(1.0, 0.0, 0.0));
+    scene.set_translation(b, Vector3::new(0.0, 2.0, 0.0));
+    scene.update();
+    assert!(close_vectors(world_position(&scene, b), Vector3::new(1.0, 2.0, 0.0)));
+
+    // Only the root changes, but everything below it moves along.
+    scene.set_local_transform(root, &Transform::new(Vector3::new(0.0, 0.0, 5.0),
+        Quaternion::from_axis_angle(Vector3::up(), std::f32::consts::FRAC_PI_2), Vector3::one()));
+    scene.update();
+    assert!(close_vectors(world_position(&scene, a), Vector3::new(0.0, 0.0, 4.0)));
+    assert!(close_vectors(world_position(&scene, b), Vector3::new(0.0, 2.0, 4.0)));
+}
+
+#[test]
+fn world_matrices_only_change_on_update() {
+    let (mut scene, [_, a, b, _]) = tree();
+    scene.update();
+
+    scene.set_translation(a, Vector3::new(1.0, 0.0, 0.0));
+    assert!(close_vectors(world_position(&scene, b), Vector3::zero()));
+    scene.update();
+    assert!(close_vectors(world_position(&scene, b), Vector3::new(1.0, 0.0, 0.0)));
+}
+
+#[test]
+fn set_parent_moves_the_subtree() {
+    let (mut scene, [root, a, b, c]) = tree();
+    scene.set_translation(a, Vector3::new(1.0, 0.0, 0.0));
+    scene.set_translation(c, Vector3::new(0.0, 0.0, 3.0));
+    scene.set_translation(b, Vector3::new(0.0, 1.0, 0.0));
+    scene.update();
+
+    // b keeps its local transform and follows its new parent.
+    scene.set_parent(b, Some(c));
+    scene.update();
+    assert_eq!(scene.get_parent(b), Some(c));
+    assert_eq!(scene.get_children(a), &[] as &[u32]);
+    assert_eq!(scene.get_children(c), &[b]);
+    assert!(close_vectors(world_position(&scene, b), Vector3::new(0.0, 1.0, 3.0)));
+
+    // Without a parent it becomes a root, relative to the world origin.
+    scene.set_parent(b, None);
+    scene.update();
+    assert!(close_vectors(world_position(&scene, b), Vector3::new(0.0, 1.0, 0.0)));
+    assert_eq!(scene.get_children(root), &[a, c]);
+}
+
+#[test]
+#[should_panic]
+fn set_parent_rejects_cycles() {
+    let (mut scene, [root, _, b, _]) = tree();
+    scene.set_parent(root, Some(b));
+}
+
+#[test]
+fn disabled_nodes_disable_everything_below_them() {
+    let (mut scene, nodes) = tree();
+    let [_, a, b, c] = nodes;
+    assert_eq!(enabled_nodes(&mut scene, &nodes), nodes.to_vec());
+
+    scene.set_node_enabled(a, false);
+    assert!(!scene.is_node_enabled(a));
+    // b is still enabled itself, but its parent isn't.
+    assert!(scene.is_node_enabled(b));
+    assert_eq!(enabled_nodes(&mut scene, &nodes), vec![nodes[0], c]);
+
+    scene.set_node_enabled(a, true);
+    assert_eq!(enabled_nodes(&mut scene, &nodes), nodes.to_vec());
+}
+
+#[test]
+fn collect_objects_is_depth_first() {
+    let (mut scene, [root, a, b, c]) = tree();
+    scene.attach_object(c, 3);
+    scene.attach_object(b, 2);
+    scene.attach_object(a, 1);
+    scene.attach_object(root, 0);
+    // Attaching twice doesn't add the object twice.
+    scene.attach_object(a, 1);
+
+    assert_eq!(scene.collect_objects(None), vec![0, 1, 2, 3]);
+    assert_eq!(scene.collect_objects(Some(a)), vec![1, 2]);
+}
+
+#[test]
+fn detach_object() {
+    let (mut scene, [_, a, b, _]) = tree();
+    scene.attach_object(a, 1);
+    scene.attach_object(a, 2);
+    scene.attach_object(b, 3);
+
+    scene.detach_object(a, 1);
+    assert_eq!(scene.get_objects(a), &[2]);
+    assert_eq!(scene.collect_objects(None), vec![2, 3]);
+
+    // Only the node it is detached from loses it.
+    scene.attach_object(b, 2);
+    scene.detach_object(a, 2);
+    assert_eq!(scene.get_objects(a), &[] as &[u32]);
+    assert_eq!(scene.get_objects(b), &[3, 2]);
+}
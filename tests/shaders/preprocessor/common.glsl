@@ -0,0 +1,2 @@
+#pragma once
+float common_value() { return 1.0; }
@@ -0,0 +1,4 @@
+#ifndef GUARDED_GLSL
+#define GUARDED_GLSL
+float guarded_value() { return 2.0; }
+#endif
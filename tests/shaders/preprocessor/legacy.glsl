@@ -0,0 +1,3 @@
+#version 120
+#include "common.glsl"
+void main() {}
@@ -0,0 +1,6 @@
+#version 330 core
+#include "common.glsl"
+#include "common.glsl"
+#include "guarded.glsl"
+#include "guarded.glsl"
+void main() {}
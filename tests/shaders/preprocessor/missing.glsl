@@ -0,0 +1,3 @@
+#version 330 core
+
+#include "does_not_exist.glsl"
@@ -0,0 +1,64 @@
+use std::f32::consts::FRAC_PI_2;
+
+use meliusgfx::sprite::{self, Sprite};
+
+fn assert_corners(sprite: &Sprite, expected: [(f32, f32); 4]) {
+    for (corner, expected) in sprite.corners().iter().zip(expected.iter()) {
+        assert!((corner.0 - expected.0).abs() < 1e-4 && (corner.1 - expected.1).abs() < 1e-4,
+            "{:?} isn't {:?}", sprite.corners(), expected);
+    }
+}
+
+#[test]
+fn corners_of_an_unrotated_sprite() {
+    let sprite = Sprite::new((10.0, 20.0), (4.0, 2.0));
+
+    assert_corners(&sprite, [(10.0, 20.0), (14.0, 20.0), (14.0, 22.0), (10.0, 22.0)]);
+}
+
+#[test]
+fn corners_around_the_origin() {
+    let sprite = Sprite::new((10.0, 20.0), (4.0, 2.0)).origin((0.5, 0.5));
+
+    assert_corners(&sprite, [(8.0, 19.0), (12.0, 19.0), (12.0, 21.0), (8.0, 21.0)]);
+}
+
+#[test]
+fn corners_rotate_counter_clockwise_around_the_origin() {
+    let sprite = Sprite::new((10.0, 20.0), (4.0, 2.0)).origin((0.5, 0.5)).rotation(FRAC_PI_2);
+
+    // The bottom left corner (-2, -1) from the origin ends up at (1, -2).
+    assert_corners(&sprite, [(11.0, 18.0), (11.0, 22.0), (9.0, 22.0), (9.0, 18.0)]);
+}
+
+#[test]
+fn negative_sizes_flip_the_corners() {
+    let sprite = Sprite::new((0.0, 0.0), (-4.0, 2.0));
+
+    assert_corners(&sprite, [(0.0, 0.0), (-4.0, 0.0), (-4.0, 2.0), (0.0, 2.0)]);
+}
+
+#[test]
+fn source_rect_is_in_pixels() {
+    let sprite = Sprite::new((0.0, 0.0), (32.0, 32.0)).source_rect(32.0, 64.0, 32.0, 16.0, (256.0, 128.0));
+
+    assert_eq!(sprite.uv_rect, (0.125, 0.5, 0.25, 0.625));
+}
+
+#[test]
+fn runs_of_the_same_texture_share_a_draw_call() {
+    assert_eq!(sprite::sprite_runs(&[1, 1, 1, 2, 2, 1], 100), vec![(0, 3), (3, 2), (5, 1)]);
+    assert_eq!(sprite::sprite_runs(&[3, 3, 3, 3], 100), vec![(0, 4)]);
+    assert!(sprite::sprite_runs(&[], 100).is_empty());
+}
+
+#[test]
+fn runs_are_split_where_the_buffers_are_refilled() {
+    assert_eq!(sprite::sprite_runs(&[1, 1, 1, 1, 1], 2), vec![(0, 2), (2, 2), (4, 1)]);
+    assert_eq!(sprite::sprite_runs(&[1, 2, 2, 2, 1], 3), vec![(0, 1), (1, 2), (3, 1), (4, 1)]);
+}
+
+#[test]
+fn runs_with_a_capacity_of_0_hold_one_sprite() {
+    assert_eq!(sprite::sprite_runs(&[1, 1, 2], 0), vec![(0, 1), (1, 1), (2, 1)]);
+}